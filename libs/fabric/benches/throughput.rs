@@ -0,0 +1,69 @@
+//! Bulk send throughput with and without `TcpTransportBuilder::throughput_mode`
+//!
+//! Each iteration sends a burst of small frames to a server that
+//! acknowledges the whole burst, so the measured time covers every frame
+//! reaching the peer.
+
+use constellation_fabric::transport::{
+    TcpTransport, TcpTransportBuilder, TcpTransportListener, Transport,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use tokio::runtime::Runtime;
+
+const BURST: usize = 1000;
+const FRAME_SIZES: [usize; 3] = [64, 512, 4096];
+
+/// Connect `builder` to a server that acknowledges every `BURST` frames
+async fn connect(builder: TcpTransportBuilder) -> TcpTransport {
+    // Without NODELAY the ack waits on Nagle and the peer's delayed ACK
+    let listener = TcpTransportListener::builder()
+        .nodelay_on_accept(true)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut server, _) = listener.accept().await.unwrap();
+        loop {
+            for _ in 0..BURST {
+                if server.receive().await.is_err() {
+                    return;
+                }
+            }
+            server.send(b"ack").await.unwrap();
+        }
+    });
+    builder.address(addr).connect().await.unwrap()
+}
+
+async fn send_burst(client: &mut TcpTransport, payload: &[u8]) {
+    for _ in 0..BURST {
+        client.send(payload).await.unwrap();
+    }
+    client.receive().await.unwrap();
+}
+
+fn bulk_send(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("bulk_send");
+    for size in FRAME_SIZES {
+        let payload = vec![0xA5u8; size];
+        group.throughput(Throughput::Bytes((size * BURST) as u64));
+
+        let mut client = rt.block_on(connect(TcpTransport::builder()));
+        group.bench_with_input(BenchmarkId::new("default", size), &payload, |b, payload| {
+            b.iter(|| rt.block_on(send_burst(&mut client, payload)))
+        });
+
+        let mut client = rt.block_on(connect(TcpTransport::builder().throughput_mode()));
+        group.bench_with_input(
+            BenchmarkId::new("throughput_mode", size),
+            &payload,
+            |b, payload| b.iter(|| rt.block_on(send_burst(&mut client, payload))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bulk_send);
+criterion_main!(benches);
@@ -0,0 +1,157 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::codec::Codec;
+use crate::error::{Error, Result};
+use crate::request::request_tcp;
+
+/// Observable state of a [`CircuitBreaker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Calls go through; consecutive failures are counted
+    Closed,
+    /// Calls fail fast until the reset timeout has passed
+    Open,
+    /// The reset timeout has passed and one probe call decides the next state
+    HalfOpen,
+}
+
+/// Fails fast while a backend keeps failing
+///
+/// After `failure_threshold` consecutive failed calls the breaker opens and
+/// every call fails immediately with `Error::Custom("circuit open")` instead
+/// of reaching the backend. Once `reset_timeout` has passed it half-opens and
+/// lets a single probe through: success closes the breaker, failure opens it
+/// for another `reset_timeout`. Other calls keep failing fast while the
+/// probe is in flight.
+///
+/// Every error counts as a failure. Share one breaker per backend, e.g. in
+/// an `Arc`.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    reset_timeout: Duration,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    Probing,
+}
+
+impl CircuitBreaker {
+    /// Open after `failure_threshold` consecutive failures, probing again after `reset_timeout`
+    pub fn new(failure_threshold: u32, reset_timeout: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            reset_timeout,
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    /// Current state
+    pub fn state(&self) -> BreakerState {
+        match *self.lock() {
+            State::Closed { .. } => BreakerState::Closed,
+            State::Open { until } if Instant::now() >= until => BreakerState::HalfOpen,
+            State::Open { .. } => BreakerState::Open,
+            State::Probing => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Perform a one-off TCP request/response through the breaker
+    ///
+    /// See [`request_tcp`].
+    pub async fn request<Req, Res, C>(
+        &self,
+        addr: SocketAddr,
+        request: &Req,
+        codec: C,
+    ) -> Result<Res>
+    where
+        Req: Serialize,
+        Res: for<'de> Deserialize<'de>,
+        C: Codec,
+    {
+        self.call(request_tcp(addr, request, codec)).await
+    }
+
+    /// Run any fallible operation through the breaker
+    ///
+    /// `op` is not polled at all while the breaker is open.
+    pub async fn call<T>(&self, op: impl Future<Output = Result<T>>) -> Result<T> {
+        let mut permit = self.admit()?;
+        let result = op.await;
+        permit.finish(result.is_ok());
+        result
+    }
+
+    fn admit(&self) -> Result<Permit<'_>> {
+        let mut state = self.lock();
+        let probe = match *state {
+            State::Closed { .. } => false,
+            State::Open { until } if Instant::now() >= until => {
+                *state = State::Probing;
+                true
+            }
+            State::Open { .. } | State::Probing => {
+                return Err(Error::Custom("circuit open".to_string()))
+            }
+        };
+        Ok(Permit {
+            breaker: self,
+            probe,
+            finished: false,
+        })
+    }
+
+    fn record(&self, success: bool) {
+        let mut state = self.lock();
+        *state = match (*state, success) {
+            (_, true) => State::Closed { failures: 0 },
+            (State::Closed { failures }, false) if failures + 1 < self.failure_threshold => {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (_, false) => State::Open {
+                until: Instant::now() + self.reset_timeout,
+            },
+        };
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("breaker lock poisoned")
+    }
+}
+
+/// Admission to one call; a probe dropped before finishing lets the next call probe
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+    finished: bool,
+}
+
+impl Permit<'_> {
+    fn finish(&mut self, success: bool) {
+        self.finished = true;
+        self.breaker.record(success);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe && !self.finished {
+            *self.breaker.lock() = State::Open {
+                until: Instant::now(),
+            };
+        }
+    }
+}
@@ -1,11 +1,62 @@
+use std::collections::VecDeque;
+use std::future::Future;
 use std::net::SocketAddr;
+#[cfg(unix)]
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
+use futures::future::Either;
+use futures::stream::{self, Stream};
 use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
 
 use crate::codec::Codec;
-use crate::error::Result;
-use crate::transport::{TcpTransport, Transport, UnixTransport};
+use crate::error::{Error, Result};
+use crate::request::{Envelope, RpcError};
+#[cfg(unix)]
+use crate::transport::UnixTransport;
+use crate::transport::{
+    with_deadline, with_timeout, ConnectionState, FrameType, TcpTransport, Transport,
+    RECEIVE_READY_UNSUPPORTED,
+};
+
+/// Capacity the receive buffer shrinks back to after a larger frame, unless hinted otherwise
+const DEFAULT_RECEIVE_BUFFER_CAP: usize = 64 * 1024;
+
+/// Error message of a [`Channel::ping`] that got no pong in time
+const PING_TIMEOUT: &str = "Ping timeout exceeded";
+
+/// Read-only callback invoked with raw frame bytes
+type Inspector = Box<dyn Fn(&[u8]) + Send + Sync>;
+
+/// Traffic counters of a [`Channel`] over its lifetime
+///
+/// Byte counts are what went over the wire: payloads plus the transport's
+/// per-frame [overhead](Transport::frame_overhead), such as the length
+/// prefix. Only data frames are counted; pings, pongs and other control
+/// frames are not, and neither are bytes below the framing such as TLS or
+/// TCP headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    /// Bytes of data frames sent, including framing
+    pub bytes_sent: u64,
+    /// Bytes of data frames received, including framing
+    pub bytes_received: u64,
+    /// Data frames sent
+    pub messages_sent: u64,
+    /// Data frames received
+    pub messages_received: u64,
+}
+
+/// Atomic backing for [`ChannelStats`], updated from `&self` paths
+#[derive(Debug, Default)]
+struct Counters {
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    messages_sent: AtomicU64,
+    messages_received: AtomicU64,
+}
 
 /// High-level channel for bidirectional communication
 ///
@@ -13,43 +64,691 @@ use crate::transport::{TcpTransport, Transport, UnixTransport};
 pub struct Channel<C> {
     transport: Box<dyn Transport>,
     codec: C,
+    pending: VecDeque<Vec<u8>>,
+    ping_nonce: u64,
+    send_buf: Vec<u8>,
+    recv_buf: Vec<u8>,
+    recv_buf_cap: usize,
+    inbound_inspector: Option<Inspector>,
+    outbound_inspector: Option<Inspector>,
+    send_deadline: Option<Duration>,
+    missed_send_deadline: bool,
+    counters: Counters,
 }
 
 impl<C: Codec> Channel<C> {
     /// Create a channel from an existing transport
     pub fn from_transport(transport: impl Transport + 'static, codec: C) -> Self {
+        Self::from_parts(Box::new(transport), codec)
+    }
+
+    /// Create a channel from an already boxed transport and a codec
+    ///
+    /// The inverse of [`into_parts`](Self::into_parts).
+    pub fn from_parts(transport: Box<dyn Transport>, codec: C) -> Self {
         Self {
-            transport: Box::new(transport),
+            transport,
             codec,
+            pending: VecDeque::new(),
+            ping_nonce: 0,
+            send_buf: Vec::new(),
+            recv_buf: Vec::new(),
+            recv_buf_cap: DEFAULT_RECEIVE_BUFFER_CAP,
+            inbound_inspector: None,
+            outbound_inspector: None,
+            send_deadline: None,
+            missed_send_deadline: false,
+            counters: Counters::default(),
         }
     }
 
+    /// Call `inspector` with the raw bytes of every received frame
+    ///
+    /// Runs before the codec sees the frame, which makes it a tap for logging
+    /// or hex-dumping traffic. Each frame is inspected once, when it is read
+    /// from the transport.
+    pub fn with_inbound_inspector(
+        mut self,
+        inspector: impl Fn(&[u8]) + Send + Sync + 'static,
+    ) -> Self {
+        self.inbound_inspector = Some(Box::new(inspector));
+        self
+    }
+
+    /// Call `inspector` with the raw bytes of every sent frame
+    ///
+    /// Runs after encoding, just before the bytes are handed to the transport.
+    pub fn with_outbound_inspector(
+        mut self,
+        inspector: impl Fn(&[u8]) + Send + Sync + 'static,
+    ) -> Self {
+        self.outbound_inspector = Some(Box::new(inspector));
+        self
+    }
+
+    /// Start the reused receive buffer with `capacity` bytes
+    ///
+    /// [`receive`](Self::receive) reads every frame into one buffer owned by
+    /// the channel, which shrinks back after a larger frame so an occasional
+    /// large frame does not pin its memory. Without a hint the buffer starts
+    /// empty and shrinks back to 64 KiB. With a hint it starts at `capacity`
+    /// and shrinks back to it, so frames up to the hint never reallocate.
+    /// This is a hint, not a limit: larger frames are still received, up to
+    /// the transport's maximum frame size.
+    pub fn with_receive_buffer_hint(mut self, capacity: usize) -> Self {
+        self.recv_buf = Vec::with_capacity(capacity);
+        self.recv_buf_cap = capacity;
+        self
+    }
+
+    /// Fail a send with "Send deadline exceeded" if it is not done within `deadline`
+    ///
+    /// Unlike a transport's send timeout, which only bounds the socket
+    /// write, the deadline starts when `send` is called and spans everything
+    /// until it returns: waiting on a rate limiter or memory budget, flushing
+    /// batched frames, and the write itself. It also bounds
+    /// [`send_cancellable`](Self::send_cancellable) and
+    /// [`flush`](Self::flush). A [`PriorityChannel`] starts it when a message
+    /// is queued instead. A send that misses the deadline may have written
+    /// part of its frame, so the channel [is closed](Self::is_closed) from
+    /// then on.
+    ///
+    /// [`PriorityChannel`]: crate::priority::PriorityChannel
+    pub fn with_send_deadline(mut self, deadline: Duration) -> Self {
+        self.send_deadline = Some(deadline);
+        self
+    }
+
+    /// The deadline set with [`with_send_deadline`](Self::with_send_deadline), if any
+    pub fn send_deadline(&self) -> Option<Duration> {
+        self.send_deadline
+    }
+
+    /// Split the channel into its transport and codec
+    ///
+    /// Useful for swapping the transport (e.g. wrapping it in TLS) while
+    /// keeping the codec. Frames buffered by [`peek`](Self::peek) or
+    /// [`ping`](Self::ping) are dropped.
+    pub fn into_parts(self) -> (Box<dyn Transport>, C) {
+        (self.transport, self.codec)
+    }
+
+    /// Get a reference to the codec
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Get a reference to the underlying transport
+    pub fn transport(&self) -> &dyn Transport {
+        self.transport.as_ref()
+    }
+
+    /// Get a mutable reference to the underlying transport
+    pub fn transport_mut(&mut self) -> &mut dyn Transport {
+        self.transport.as_mut()
+    }
+
     /// Open a TCP channel
     pub async fn tcp(addr: SocketAddr, codec: C) -> Result<Self> {
         let transport = TcpTransport::connect(addr).await?;
         Ok(Self::from_transport(transport, codec))
     }
 
+    /// Open a TCP channel and run `setup` on it before returning it
+    ///
+    /// `setup` is the place for a post-connect handshake: the channel is only
+    /// handed out once it succeeds, so nothing else can send first. If
+    /// `setup` fails the connection is closed and its error returned.
+    pub async fn connect_then<F>(addr: SocketAddr, codec: C, setup: F) -> Result<Self>
+    where
+        F: AsyncFnOnce(&mut Self) -> Result<()>,
+    {
+        Self::tcp(addr, codec).await?.init(setup).await
+    }
+
+    /// Run `setup` on a freshly created channel, closing it if `setup` fails
+    ///
+    /// Works with any transport, e.g.
+    /// `Channel::from_transport(transport, codec).init(handshake)`.
+    pub async fn init<F>(mut self, setup: F) -> Result<Self>
+    where
+        F: AsyncFnOnce(&mut Self) -> Result<()>,
+    {
+        match setup(&mut self).await {
+            Ok(()) => Ok(self),
+            Err(e) => {
+                let _ = self.close().await;
+                Err(e)
+            }
+        }
+    }
+
     /// Open a Unix socket channel
+    #[cfg(unix)]
     pub async fn unix(path: impl AsRef<Path>, codec: C) -> Result<Self> {
         let transport = UnixTransport::connect(path).await?;
         Ok(Self::from_transport(transport, codec))
     }
 
     /// Send a message over the channel
+    ///
+    /// Messages are encoded into a buffer owned by the channel and reused
+    /// across sends, so its capacity grows to the largest message sent.
     pub async fn send<T: Serialize>(&mut self, message: &T) -> Result<()> {
-        let bytes = self.codec.encode(message)?;
-        self.transport.send(&bytes).await
+        let deadline = self.deadline_from_now();
+        let mut buf = std::mem::take(&mut self.send_buf);
+        let result = match self.codec.encode_into(message, &mut buf) {
+            Ok(()) => self.send_encoded_by(&buf, deadline).await,
+            Err(e) => Err(e),
+        };
+        self.send_buf = buf;
+        result
+    }
+
+    /// Send an already-encoded payload, bypassing the codec
+    ///
+    /// The bytes go straight to the transport, so they must already be in the
+    /// format the peer's codec expects. An empty slice sends a zero-length frame.
+    pub async fn send_encoded(&mut self, bytes: &[u8]) -> Result<()> {
+        self.send_encoded_by(bytes, self.deadline_from_now()).await
+    }
+
+    /// Send an encoded payload, failing if it is not done by `deadline`
+    pub(crate) async fn send_encoded_by(
+        &mut self,
+        bytes: &[u8],
+        deadline: Option<tokio::time::Instant>,
+    ) -> Result<()> {
+        self.inspect_outbound(bytes);
+        let result = with_deadline(deadline, "Send", self.transport.send(bytes)).await;
+        self.check_send_deadline(deadline, result)?;
+        self.record_sent(bytes.len());
+        Ok(())
+    }
+
+    /// Pass `result` through, noting if its send failed for running into `deadline`
+    fn check_send_deadline(
+        &mut self,
+        deadline: Option<tokio::time::Instant>,
+        result: Result<()>,
+    ) -> Result<()> {
+        if result.is_err() && deadline.is_some_and(|at| tokio::time::Instant::now() >= at) {
+            self.missed_send_deadline = true;
+        }
+        result
+    }
+
+    /// When a send starting now must be done by, per [`with_send_deadline`](Self::with_send_deadline)
+    fn deadline_from_now(&self) -> Option<tokio::time::Instant> {
+        self.send_deadline
+            .map(|deadline| tokio::time::Instant::now() + deadline)
+    }
+
+    /// Write out frames the transport has batched
+    ///
+    /// See [`Transport::flush`].
+    pub async fn flush(&mut self) -> Result<()> {
+        let deadline = self.deadline_from_now();
+        let result = with_deadline(deadline, "Send", self.transport.flush()).await;
+        self.check_send_deadline(deadline, result)
+    }
+
+    /// Receive a raw frame payload, bypassing the codec
+    ///
+    /// Zero-length frames are returned as an empty `Vec`, which makes this the
+    /// way to observe empty frames such as heartbeats.
+    pub async fn receive_encoded(&mut self) -> Result<Vec<u8>> {
+        self.next_frame().await
     }
 
     /// Receive a message from the channel
+    ///
+    /// Frames are read into a buffer owned by the channel and reused across
+    /// receives; see [`with_receive_buffer_hint`](Self::with_receive_buffer_hint).
+    ///
+    /// A frame that arrives intact but fails to decode is reported as
+    /// [`Error::Codec`], whatever error the codec itself returned, and the
+    /// connection stays usable. Every other error comes from the transport,
+    /// which is typically broken afterwards and worth reconnecting; a codec
+    /// error will fail the same way again. The same holds for every receive
+    /// method of the channel.
     pub async fn receive<T: for<'de> Deserialize<'de>>(&mut self) -> Result<T> {
-        let bytes = self.transport.receive().await?;
-        self.codec.decode(&bytes)
+        if let Some(bytes) = self.pending.pop_front() {
+            return self.decode(&bytes);
+        }
+
+        self.transport.receive_into(&mut self.recv_buf).await?;
+        self.record_inbound(&self.recv_buf);
+        let result = self.decode(&self.recv_buf);
+        if self.recv_buf.capacity() > self.recv_buf_cap {
+            self.recv_buf.clear();
+            self.recv_buf.shrink_to(self.recv_buf_cap);
+        }
+        result
+    }
+
+    /// Receive a message, or return the output of `other` if it completes first
+    ///
+    /// For stopping a receive on a signal other than a
+    /// [`CancellationToken`], such as a shutdown broadcast. Unlike a plain
+    /// `select!` on [`receive`](Self::receive), which can drop a frame that
+    /// was half read, `other` winning never consumes part of a frame:
+    ///
+    /// - `other` is polled first, so one that is already complete wins even
+    ///   if messages are waiting.
+    /// - A frame is only taken off the transport once all of it has arrived.
+    ///   Until then, `other` completing returns [`Either::Right`], and any
+    ///   bytes that arrived stay buffered, so the next receive sees the
+    ///   stream exactly where it was, partial frame included.
+    /// - Control frames are handled as by `receive`, and `other` is checked
+    ///   again between them.
+    ///
+    /// The guarantee rests on [`Transport::readable`]; stream transports
+    /// buffer a whole frame in memory before reading it, applying their
+    /// receive timeouts and memory budget to that wait. Transports without
+    /// `readable` only check `other` between frames. Frames buffered by
+    /// [`peek`](Self::peek) or [`ping`](Self::ping) are returned without
+    /// polling `other`. Batched sends are flushed first.
+    pub async fn receive_or<T, F>(&mut self, other: F) -> Result<Either<T, F::Output>>
+    where
+        T: for<'de> Deserialize<'de>,
+        F: Future,
+    {
+        if let Some(bytes) = self.pending.pop_front() {
+            return self.decode(&bytes).map(Either::Left);
+        }
+        self.transport.flush().await?;
+
+        let mut other = std::pin::pin!(other);
+        loop {
+            tokio::select! {
+                biased;
+                output = &mut other => return Ok(Either::Right(output)),
+                ready = self.transport.readable() => ready?,
+            }
+            match self.transport.receive_frame().await? {
+                (FrameType::Data, payload) => {
+                    self.record_inbound(&payload);
+                    return self.decode(&payload).map(Either::Left);
+                }
+                (FrameType::Ping, payload) => {
+                    self.transport
+                        .send_control(FrameType::Pong, &payload)
+                        .await?
+                }
+                (FrameType::Close, _) => return Err(Error::ConnectionClosed),
+                (FrameType::Pong | FrameType::Control, _) => {}
+            }
+        }
+    }
+
+    /// Receive every message that has already arrived, without waiting
+    ///
+    /// Returns frames buffered by [`peek`](Self::peek) or [`ping`](Self::ping)
+    /// if there are any, otherwise those the transport can deliver right now,
+    /// or an empty `Vec` if there are none; see [`Transport::receive_ready`].
+    /// Handy for draining a burst after one `receive` woke the caller up.
+    /// Transports that cannot receive without waiting always yield an empty
+    /// batch.
+    ///
+    /// If a frame fails to decode, that frame is consumed and its error
+    /// returned, and the other frames of the batch stay buffered for the
+    /// following receives.
+    pub async fn receive_ready<T: for<'de> Deserialize<'de>>(&mut self) -> Result<Vec<T>> {
+        if self.pending.is_empty() {
+            let ready = match self.transport.receive_ready().await {
+                Ok(ready) => ready,
+                Err(Error::Custom(msg)) if msg == RECEIVE_READY_UNSUPPORTED => Vec::new(),
+                Err(e) => return Err(e),
+            };
+            for bytes in &ready {
+                self.record_inbound(bytes);
+            }
+            self.pending.extend(ready);
+        }
+
+        let mut messages = Vec::with_capacity(self.pending.len());
+        for (i, bytes) in self.pending.iter().enumerate() {
+            match self.decode(bytes) {
+                Ok(message) => messages.push(message),
+                Err(e) => {
+                    self.pending.remove(i);
+                    return Err(e);
+                }
+            }
+        }
+        self.pending.clear();
+        Ok(messages)
+    }
+
+    /// Receive a message, returning `Ok(None)` for a frame the codec cannot decode
+    ///
+    /// The bad frame is consumed, so the caller can log it and carry on with
+    /// the next one instead of tearing down the connection. Transport errors
+    /// are still returned as errors.
+    pub async fn receive_lenient<T: for<'de> Deserialize<'de>>(&mut self) -> Result<Option<T>> {
+        let bytes = self.next_frame().await?;
+        match self.decode(&bytes) {
+            Ok(value) => Ok(Some(value)),
+            Err(Error::Codec(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Receive a message together with the raw frame it was decoded from
+    ///
+    /// Useful for persisting the original bytes, since re-encoding the value
+    /// is not guaranteed to reproduce them. A frame that fails to decode is
+    /// consumed like with [`receive`](Self::receive).
+    pub async fn receive_with_bytes<T: for<'de> Deserialize<'de>>(
+        &mut self,
+    ) -> Result<(T, Vec<u8>)> {
+        let bytes = self.next_frame().await?;
+        let value = self.decode(&bytes)?;
+        Ok((value, bytes))
+    }
+
+    /// Send `items` as a sequence that [`receive_stream`](Self::receive_stream) reads to its end
+    ///
+    /// Each item goes in its own frame, followed by the end marker of
+    /// [`end_sequence`](Self::end_sequence).
+    pub async fn send_sequence<'a, T>(
+        &mut self,
+        items: impl IntoIterator<Item = &'a T>,
+    ) -> Result<()>
+    where
+        T: Serialize + 'a,
+    {
+        for item in items {
+            self.send(item).await?;
+        }
+        self.end_sequence().await
+    }
+
+    /// Mark the end of a sequence of messages sent one by one
+    ///
+    /// The end marker is a zero-length frame, so items of a sequence must not
+    /// encode to zero bytes (with bincode, e.g. `()` or a unit struct).
+    pub async fn end_sequence(&mut self) -> Result<()> {
+        self.send_encoded(&[]).await
+    }
+
+    /// Receive the messages of a sequence as a stream, ending at its end marker
+    ///
+    /// The counterpart of [`send_sequence`](Self::send_sequence): yields each
+    /// item as it arrives and ends once the zero-length end marker is read,
+    /// leaving the channel ready for whatever follows, so a complete
+    /// sequence can be told apart from a connection that is merely idle.
+    /// An item that fails to decode is yielded as an error and the stream
+    /// carries on; a transport error is yielded and ends the stream.
+    pub fn receive_stream<T>(&mut self) -> impl Stream<Item = Result<T>> + '_
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        stream::unfold(Some(self), |channel| async move {
+            let channel = channel?;
+            match channel.next_frame().await {
+                Ok(bytes) if bytes.is_empty() => None,
+                Ok(bytes) => Some((channel.decode(&bytes), Some(channel))),
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+    }
+
+    /// Send a request and receive its response
+    ///
+    /// Assumes strict request/response ordering: the next frame on the
+    /// channel is taken to be the response, so requests must not be pipelined
+    /// and the peer must answer each one before sending anything else.
+    pub async fn request<Req, Res>(&mut self, request: &Req) -> Result<Res>
+    where
+        Req: Serialize,
+        Res: for<'de> Deserialize<'de>,
+    {
+        self.send(request).await?;
+        self.receive().await
+    }
+
+    /// Like [`request`](Self::request), failing if the round trip takes longer than `timeout`
+    ///
+    /// A response that arrives after the timeout stays queued and would be
+    /// mistaken for the answer to the next request, so discard the channel
+    /// after a timeout.
+    pub async fn request_timeout<Req, Res>(
+        &mut self,
+        request: &Req,
+        timeout: Duration,
+    ) -> Result<Res>
+    where
+        Req: Serialize,
+        Res: for<'de> Deserialize<'de>,
+    {
+        with_timeout(Some(timeout), "Request", self.request(request)).await
+    }
+
+    /// Send a response wrapped in an [`Envelope`]
+    ///
+    /// Pass `Ok(&value)` to avoid moving the payload. The peer reads it with
+    /// [`receive_response`](Self::receive_response).
+    pub async fn send_response<T: Serialize>(
+        &mut self,
+        response: std::result::Result<T, RpcError>,
+    ) -> Result<()> {
+        self.send(&Envelope::from(response)).await
+    }
+
+    /// Receive a response sent with [`send_response`](Self::send_response)
+    ///
+    /// The outer error is a transport or codec failure; the inner one is the
+    /// [`RpcError`] the peer answered with.
+    pub async fn receive_response<T: for<'de> Deserialize<'de>>(
+        &mut self,
+    ) -> Result<std::result::Result<T, RpcError>> {
+        let envelope: Envelope<T> = self.receive().await?;
+        Ok(envelope.into())
+    }
+
+    /// Decode the next message without consuming it
+    ///
+    /// The raw frame is buffered so the following receive returns the same
+    /// message. Only a single frame of lookahead is supported: repeated peeks
+    /// return the same frame until it is received.
+    pub async fn peek<T: for<'de> Deserialize<'de>>(&mut self) -> Result<T> {
+        if self.pending.is_empty() {
+            let bytes = self.receive_from_transport().await?;
+            self.pending.push_back(bytes);
+        }
+        let bytes = self.pending.front().expect("frame was just buffered");
+        self.decode(bytes)
+    }
+
+    /// Send a message, aborting with [`Error::Cancelled`] if the token fires first
+    pub async fn send_cancellable<T: Serialize>(
+        &mut self,
+        message: &T,
+        token: &CancellationToken,
+    ) -> Result<()> {
+        let deadline = self.deadline_from_now();
+        let bytes = self.codec.encode(message)?;
+        self.inspect_outbound(&bytes);
+        let send = self.transport.send_cancellable(&bytes, token);
+        let result = with_deadline(deadline, "Send", send).await;
+        self.check_send_deadline(deadline, result)?;
+        self.record_sent(bytes.len());
+        Ok(())
+    }
+
+    /// Receive a message, aborting with [`Error::Cancelled`] if the token fires first
+    pub async fn receive_cancellable<T: for<'de> Deserialize<'de>>(
+        &mut self,
+        token: &CancellationToken,
+    ) -> Result<T> {
+        let bytes = match self.pending.pop_front() {
+            Some(bytes) => bytes,
+            None => {
+                let bytes = self.transport.receive_cancellable(token).await?;
+                self.record_inbound(&bytes);
+                bytes
+            }
+        };
+        self.decode(&bytes)
+    }
+
+    /// Measure the round-trip time to the peer
+    ///
+    /// Sends a ping control frame and waits up to `timeout` for the matching
+    /// pong. The peer answers pings automatically while it receives, so no
+    /// handler code is needed on the other side. Data frames that arrive in
+    /// the meantime are buffered for the following receives.
+    ///
+    /// Only the waits for the next frame count towards `timeout`, and frames
+    /// are only taken off the transport once all of it has arrived (see
+    /// [`Transport::readable`]), so a timeout never leaves part of a frame
+    /// read or written and the channel stays usable after one. Sending the
+    /// ping and answering the peer's pings are bounded by the transport's
+    /// send timeout instead.
+    ///
+    /// Requires frame types on both ends, e.g.
+    /// [`TcpTransportBuilder::frame_types`](crate::transport::TcpTransportBuilder::frame_types).
+    pub async fn ping(&mut self, timeout: Duration) -> Result<Duration> {
+        self.ping_nonce = self.ping_nonce.wrapping_add(1);
+        let nonce = self.ping_nonce.to_be_bytes();
+        let start = Instant::now();
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        self.transport.send_control(FrameType::Ping, &nonce).await?;
+        loop {
+            tokio::time::timeout_at(deadline, self.transport.readable())
+                .await
+                .map_err(|_| Error::Custom(PING_TIMEOUT.to_string()))??;
+            match self.transport.receive_frame().await? {
+                (FrameType::Pong, payload) if payload == nonce => break,
+                (FrameType::Pong, _) => {}
+                (FrameType::Ping, payload) => {
+                    self.transport
+                        .send_control(FrameType::Pong, &payload)
+                        .await?
+                }
+                (FrameType::Data, payload) => {
+                    self.record_inbound(&payload);
+                    self.pending.push_back(payload);
+                }
+                (FrameType::Close, _) => return Err(Error::ConnectionClosed),
+                (FrameType::Control, _) => {}
+            }
+        }
+        Ok(start.elapsed())
+    }
+
+    /// Check whether the underlying connection is known to be closed
+    ///
+    /// Best-effort; see [`Transport::is_closed`].
+    pub fn is_closed(&self) -> bool {
+        self.missed_send_deadline || self.transport.is_closed()
+    }
+
+    /// Whether the channel can still be used after `error` from one of its methods
+    ///
+    /// See [`Transport::is_reusable_after`]. A frame that failed to decode
+    /// has been consumed whole, so the next receive reads the frame after
+    /// it; after a failed [`peek`](Self::peek) it stays buffered instead. A
+    /// [`ping`](Self::ping) only times out between frames, so the channel
+    /// stays usable after that too.
+    pub fn is_reusable_after(&self, error: &Error) -> bool {
+        if self.missed_send_deadline {
+            return false;
+        }
+        let ping_timeout = matches!(error, Error::Custom(msg) if msg == PING_TIMEOUT);
+        (ping_timeout && !self.transport.is_closed()) || self.transport.is_reusable_after(error)
+    }
+
+    /// Where the underlying connection is in its lifecycle
+    ///
+    /// See [`Transport::state`].
+    pub fn state(&self) -> ConnectionState {
+        if self.missed_send_deadline {
+            return ConnectionState::Closed;
+        }
+        self.transport.state()
+    }
+
+    /// Bytes and messages sent and received so far
+    ///
+    /// See [`ChannelStats`] for what is counted.
+    pub fn stats(&self) -> ChannelStats {
+        ChannelStats {
+            bytes_sent: self.counters.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.counters.bytes_received.load(Ordering::Relaxed),
+            messages_sent: self.counters.messages_sent.load(Ordering::Relaxed),
+            messages_received: self.counters.messages_received.load(Ordering::Relaxed),
+        }
     }
 
     /// Close the channel
     pub async fn close(mut self) -> Result<()> {
         self.transport.close().await
     }
+
+    async fn next_frame(&mut self) -> Result<Vec<u8>> {
+        match self.pending.pop_front() {
+            Some(bytes) => Ok(bytes),
+            None => self.receive_from_transport().await,
+        }
+    }
+
+    async fn receive_from_transport(&mut self) -> Result<Vec<u8>> {
+        let bytes = self.transport.receive().await?;
+        self.record_inbound(&bytes);
+        Ok(bytes)
+    }
+
+    /// Decode a well-framed payload, reporting any failure as [`Error::Codec`]
+    fn decode<T: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<T> {
+        self.codec.decode(bytes).map_err(|e| match e {
+            Error::Codec(_) => e,
+            other => Error::Codec(other.to_string()),
+        })
+    }
+
+    fn record_sent(&self, len: usize) {
+        let wire_len = (len + self.transport.frame_overhead()) as u64;
+        self.counters
+            .bytes_sent
+            .fetch_add(wire_len, Ordering::Relaxed);
+        self.counters.messages_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a received data frame and pass it to the inbound inspector
+    fn record_inbound(&self, bytes: &[u8]) {
+        let wire_len = (bytes.len() + self.transport.frame_overhead()) as u64;
+        self.counters
+            .bytes_received
+            .fetch_add(wire_len, Ordering::Relaxed);
+        self.counters
+            .messages_received
+            .fetch_add(1, Ordering::Relaxed);
+        if let Some(inspector) = &self.inbound_inspector {
+            inspector(bytes);
+        }
+    }
+
+    fn inspect_outbound(&self, bytes: &[u8]) {
+        if let Some(inspector) = &self.outbound_inspector {
+            inspector(bytes);
+        }
+    }
+}
+
+impl<C> std::fmt::Debug for Channel<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Buffered frames are application data, so only their count is shown
+        f.debug_struct("Channel")
+            .field("transport", &self.transport.describe())
+            .field("codec", &std::any::type_name::<C>())
+            .field("pending_frames", &self.pending.len())
+            .field("inbound_inspector", &self.inbound_inspector.is_some())
+            .field("outbound_inspector", &self.outbound_inspector.is_some())
+            .field("send_deadline", &self.send_deadline)
+            .finish_non_exhaustive()
+    }
 }
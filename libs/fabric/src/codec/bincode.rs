@@ -1,9 +1,14 @@
+use bincode::Options;
 use serde::{Deserialize, Serialize};
 
-use crate::codec::Codec;
+use crate::codec::{depth, guard_decode, Codec};
 use crate::error::{Error, Result};
 
 /// Bincode codec for binary serialization
+///
+/// Decoding fails if bytes remain after the value, which surfaces framing
+/// and length bugs instead of silently ignoring the extra data. Use
+/// [`LenientBincodeCodec`] to tolerate trailing bytes.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct BincodeCodec;
 
@@ -12,7 +17,114 @@ impl Codec for BincodeCodec {
         bincode::serialize(value).map_err(|e| Error::Codec(e.to_string()))
     }
 
+    fn encode_into<T: Serialize>(&self, value: &T, buf: &mut Vec<u8>) -> Result<()> {
+        buf.clear();
+        bincode::serialize_into(buf, value).map_err(|e| Error::Codec(e.to_string()))
+    }
+
+    fn decode<T: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<T> {
+        guard_decode(|| {
+            let mut deserializer = bincode::Deserializer::from_slice(bytes, options());
+            let value =
+                T::deserialize(&mut deserializer).map_err(|e| Error::Codec(e.to_string()))?;
+            reject_trailing(&mut deserializer)?;
+            Ok(value)
+        })
+    }
+}
+
+impl BincodeCodec {
+    /// Reject messages nesting more than `max_depth` compound values when decoding
+    ///
+    /// See [`DepthLimitedBincodeCodec`].
+    pub fn with_max_depth(self, max_depth: usize) -> DepthLimitedBincodeCodec {
+        DepthLimitedBincodeCodec::new(max_depth)
+    }
+}
+
+/// [`BincodeCodec`] that bounds how deeply decoded values may nest
+///
+/// A recursive type such as a tree enum lets a few bytes per level describe
+/// a value nested deep enough to overflow the stack while decoding. Every
+/// struct, tuple, sequence, map, enum, `Some` and newtype counts as one
+/// level; a frame nesting deeper than `max_depth` fails with
+/// `Error::Codec("max depth exceeded")`. The wire format is unchanged.
+#[derive(Debug, Clone, Copy)]
+pub struct DepthLimitedBincodeCodec {
+    max_depth: usize,
+}
+
+impl DepthLimitedBincodeCodec {
+    /// Create a codec accepting at most `max_depth` levels of nesting
+    pub fn new(max_depth: usize) -> Self {
+        Self { max_depth }
+    }
+
+    /// The configured nesting limit
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+}
+
+impl Codec for DepthLimitedBincodeCodec {
+    fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        BincodeCodec.encode(value)
+    }
+
+    fn encode_into<T: Serialize>(&self, value: &T, buf: &mut Vec<u8>) -> Result<()> {
+        BincodeCodec.encode_into(value, buf)
+    }
+
     fn decode<T: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<T> {
-        bincode::deserialize(bytes).map_err(|e| Error::Codec(e.to_string()))
+        guard_decode(|| {
+            let mut deserializer = bincode::Deserializer::from_slice(bytes, options());
+            let value = depth::deserialize(&mut deserializer, self.max_depth)
+                .map_err(|e| Error::Codec(e.to_string()))?;
+            reject_trailing(&mut deserializer)?;
+            Ok(value)
+        })
+    }
+}
+
+/// Bincode codec that ignores bytes left over after the decoded value
+///
+/// Same wire format as [`BincodeCodec`], for peers that append data the
+/// receiver is expected to skip.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LenientBincodeCodec;
+
+impl Codec for LenientBincodeCodec {
+    fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        bincode::serialize(value).map_err(|e| Error::Codec(e.to_string()))
     }
+
+    fn encode_into<T: Serialize>(&self, value: &T, buf: &mut Vec<u8>) -> Result<()> {
+        buf.clear();
+        bincode::serialize_into(buf, value).map_err(|e| Error::Codec(e.to_string()))
+    }
+
+    fn decode<T: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<T> {
+        guard_decode(|| bincode::deserialize(bytes).map_err(|e| Error::Codec(e.to_string())))
+    }
+}
+
+/// Fail if `deserializer` has input left after the decoded value
+///
+/// Reading one more byte only succeeds when there is one to read.
+fn reject_trailing<'de>(deserializer: impl serde::Deserializer<'de>) -> Result<()> {
+    match u8::deserialize(deserializer) {
+        Ok(_) => Err(Error::Codec("trailing bytes after value".to_string())),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Options matching `bincode::serialize`, leaving the trailing check to the caller
+///
+/// Always decode from a slice: a slice reader checks a length prefix against
+/// the bytes actually present, while an `io::Read` source allocates whatever
+/// length the frame claims.
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
 }
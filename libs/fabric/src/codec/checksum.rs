@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::codec::Codec;
+use crate::error::{Error, Result};
+
+const CHECKSUM_LEN: usize = 4;
+
+/// Codec wrapper that appends a CRC32 of the encoded bytes
+///
+/// The checksum is written as 4 big-endian bytes after the inner payload and
+/// verified before the inner codec sees the data, so corrupted frames fail
+/// with a codec error instead of decoding into garbage.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChecksummedCodec<C> {
+    inner: C,
+}
+
+impl<C: Codec> ChecksummedCodec<C> {
+    /// Wrap a codec with CRC32 integrity checking
+    pub fn new(inner: C) -> Self {
+        Self { inner }
+    }
+
+    /// Get a reference to the wrapped codec
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+impl<C: Codec> Codec for ChecksummedCodec<C> {
+    fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        let mut bytes = self.inner.encode(value)?;
+        let checksum = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&checksum.to_be_bytes());
+        Ok(bytes)
+    }
+
+    fn encode_into<T: Serialize>(&self, value: &T, buf: &mut Vec<u8>) -> Result<()> {
+        self.inner.encode_into(value, buf)?;
+        let checksum = crc32fast::hash(buf);
+        buf.extend_from_slice(&checksum.to_be_bytes());
+        Ok(())
+    }
+
+    fn decode<T: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<T> {
+        if bytes.len() < CHECKSUM_LEN {
+            return Err(Error::Codec("missing checksum".to_string()));
+        }
+
+        let (payload, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        let expected = u32::from_be_bytes(checksum.try_into().expect("checksum is 4 bytes"));
+        if crc32fast::hash(payload) != expected {
+            return Err(Error::Codec("checksum mismatch".to_string()));
+        }
+
+        self.inner.decode(payload)
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::{Read, Write};
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use crate::codec::Codec;
+use crate::error::{Error, Result};
+
+const FLAG_RAW: u8 = 0;
+const FLAG_DEFLATE: u8 = 1;
+
+/// Upper bound on decompressed size, matching the transport frame limit
+const MAX_DECOMPRESSED_LEN: u64 = 100 * 1024 * 1024;
+
+/// Codec wrapper that adaptively deflate-compresses encoded frames
+///
+/// Every frame starts with a one-byte flag: `0` for raw, `1` for deflate.
+/// A frame is only compressed when that makes it smaller, so incompressible
+/// data costs a single byte instead of wasted CPU on the receiving side.
+/// Frames below [`with_min_compress_size`](Self::with_min_compress_size)
+/// are sent raw without trying.
+///
+/// The flag byte changes the wire format, so both peers must use this codec.
+#[derive(Debug, Clone, Copy)]
+pub struct CompressedCodec<C> {
+    inner: C,
+    level: Compression,
+    min_compress_size: usize,
+}
+
+impl<C: Codec> CompressedCodec<C> {
+    /// Wrap a codec with the default compression level
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            level: Compression::default(),
+            min_compress_size: 0,
+        }
+    }
+
+    /// Set the compression level (0-9)
+    pub fn with_level(mut self, level: u32) -> Self {
+        self.level = Compression::new(level);
+        self
+    }
+
+    /// Send encoded frames shorter than `len` bytes raw, without compressing
+    ///
+    /// Saves CPU on small control messages that deflate rarely shrinks.
+    /// Only the sender needs it: the flag byte tells the receiver either way.
+    pub fn with_min_compress_size(mut self, len: usize) -> Self {
+        self.min_compress_size = len;
+        self
+    }
+
+    /// Get a reference to the wrapped codec
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn compress(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(bytes.len() / 2 + 1);
+        out.push(FLAG_DEFLATE);
+        let mut encoder = DeflateEncoder::new(out, self.level);
+        encoder.write_all(bytes)?;
+        encoder.finish().map_err(Into::into)
+    }
+}
+
+impl<C: Codec + Default> Default for CompressedCodec<C> {
+    fn default() -> Self {
+        Self::new(C::default())
+    }
+}
+
+impl<C: Codec> Codec for CompressedCodec<C> {
+    fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        let bytes = self.inner.encode(value)?;
+
+        if bytes.len() >= self.min_compress_size {
+            let compressed = self.compress(&bytes)?;
+            if compressed.len() < bytes.len() + 1 {
+                return Ok(compressed);
+            }
+        }
+
+        let mut raw = Vec::with_capacity(bytes.len() + 1);
+        raw.push(FLAG_RAW);
+        raw.extend_from_slice(&bytes);
+        Ok(raw)
+    }
+
+    fn decode<T: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<T> {
+        let (flag, payload) = bytes
+            .split_first()
+            .ok_or_else(|| Error::Codec("missing compression flag".to_string()))?;
+
+        match *flag {
+            FLAG_RAW => self.inner.decode(payload),
+            FLAG_DEFLATE => {
+                let mut decompressed = Vec::new();
+                DeflateDecoder::new(payload)
+                    .take(MAX_DECOMPRESSED_LEN + 1)
+                    .read_to_end(&mut decompressed)
+                    .map_err(|e| Error::Codec(format!("decompression failed: {}", e)))?;
+
+                if decompressed.len() as u64 > MAX_DECOMPRESSED_LEN {
+                    return Err(Error::Codec("decompressed frame too large".to_string()));
+                }
+
+                self.inner.decode(&decompressed)
+            }
+            other => Err(Error::Codec(format!("unknown compression flag: {}", other))),
+        }
+    }
+}
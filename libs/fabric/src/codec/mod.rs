@@ -1,18 +1,99 @@
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 
 use crate::error::Result;
 
 pub mod bincode;
+pub mod checksum;
+pub mod compressed;
+mod depth;
+#[cfg(feature = "prost")]
+pub mod prost;
 pub mod raw;
+pub mod versioned;
 
-pub use self::bincode::BincodeCodec;
+pub use self::bincode::{BincodeCodec, DepthLimitedBincodeCodec, LenientBincodeCodec};
+pub use self::checksum::ChecksummedCodec;
+pub use self::compressed::CompressedCodec;
+#[cfg(feature = "prost")]
+pub use self::prost::{ProstChannel, ProstCodec};
 pub use self::raw::RawCodec;
+pub use self::versioned::VersionedCodec;
 
 /// Codec trait for serializing and deserializing messages
 pub trait Codec: Send + Sync {
     /// Encode a value into bytes
     fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>>;
 
+    /// Encode a value into `buf`, replacing its contents
+    ///
+    /// Lets callers reuse one allocation across messages. The default clears
+    /// `buf` and copies in the result of [`encode`](Self::encode); codecs that
+    /// can write in place should override it.
+    fn encode_into<T: Serialize>(&self, value: &T, buf: &mut Vec<u8>) -> Result<()> {
+        let bytes = self.encode(value)?;
+        buf.clear();
+        buf.extend_from_slice(&bytes);
+        Ok(())
+    }
+
     /// Decode bytes into a value
     fn decode<T: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<T>;
+
+    /// MIME type of the encoded bytes, e.g. for HTTP or WebSocket bridges
+    ///
+    /// Defaults to `application/octet-stream`, which also fits wrappers such
+    /// as [`CompressedCodec`] whose output is no longer the inner format.
+    fn content_type(&self) -> &'static str {
+        "application/octet-stream"
+    }
+}
+
+/// Shares one codec between many channels
+///
+/// `Channel<C>` owns its codec, so codecs holding keys or compression
+/// dictionaries would otherwise be cloned per connection.
+impl<C: Codec> Codec for Arc<C> {
+    fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        (**self).encode(value)
+    }
+
+    fn encode_into<T: Serialize>(&self, value: &T, buf: &mut Vec<u8>) -> Result<()> {
+        (**self).encode_into(value, buf)
+    }
+
+    fn decode<T: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<T> {
+        (**self).decode(bytes)
+    }
+
+    fn content_type(&self) -> &'static str {
+        (**self).content_type()
+    }
+}
+
+/// Run a decode, converting panics into codec errors when `panic-safe-decode` is enabled
+///
+/// Some `Deserialize` impls panic on malformed input instead of returning an
+/// error; with the feature enabled such a frame fails like any other bad frame.
+#[cfg(feature = "panic-safe-decode")]
+pub(crate) fn guard_decode<T>(decode: impl FnOnce() -> Result<T>) -> Result<T> {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    catch_unwind(AssertUnwindSafe(decode)).unwrap_or_else(|payload| {
+        let reason = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(crate::error::Error::Codec(format!(
+            "decode panicked: {}",
+            reason
+        )))
+    })
+}
+
+#[cfg(not(feature = "panic-safe-decode"))]
+pub(crate) fn guard_decode<T>(decode: impl FnOnce() -> Result<T>) -> Result<T> {
+    decode()
 }
@@ -0,0 +1,144 @@
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::Path;
+
+use ::prost::Message;
+
+use crate::channel::Channel;
+use crate::codec::{guard_decode, RawCodec};
+use crate::error::{Error, Result};
+use crate::transport::Transport;
+
+/// Protocol Buffers codec for `prost` messages
+///
+/// Prost messages implement [`prost::Message`] rather than serde's traits,
+/// so this is not a [`Codec`](crate::codec::Codec); use it directly or
+/// through [`ProstChannel`].
+pub struct ProstCodec<M> {
+    _message: PhantomData<fn() -> M>,
+}
+
+impl<M: Message + Default> ProstCodec<M> {
+    /// Create a codec for messages of type `M`
+    pub fn new() -> Self {
+        Self {
+            _message: PhantomData,
+        }
+    }
+
+    /// Encode a message into its protobuf wire format
+    pub fn encode(&self, message: &M) -> Vec<u8> {
+        message.encode_to_vec()
+    }
+
+    /// Decode a message from its protobuf wire format
+    pub fn decode(&self, bytes: &[u8]) -> Result<M> {
+        guard_decode(|| M::decode(bytes).map_err(|e| Error::Codec(e.to_string())))
+    }
+
+    /// MIME type of the encoded bytes
+    pub fn content_type(&self) -> &'static str {
+        "application/x-protobuf"
+    }
+}
+
+impl<M: Message + Default> Default for ProstCodec<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> Clone for ProstCodec<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for ProstCodec<M> {}
+
+impl<M> std::fmt::Debug for ProstCodec<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProstCodec")
+            .field("message", &std::any::type_name::<M>())
+            .finish()
+    }
+}
+
+/// Channel that sends `Req` and receives `Res` protobuf messages
+///
+/// The prost counterpart of [`TypedChannel`](crate::TypedChannel): frames
+/// carry the plain protobuf encoding, so the peer can be any protobuf
+/// implementation speaking the same framing.
+pub struct ProstChannel<Req, Res> {
+    channel: Channel<RawCodec>,
+    _types: PhantomData<fn(&Req) -> Res>,
+}
+
+impl<Req, Res> ProstChannel<Req, Res>
+where
+    Req: Message + Default,
+    Res: Message + Default,
+{
+    /// Wrap an existing channel, ignoring its codec
+    pub fn new(channel: Channel<RawCodec>) -> Self {
+        Self {
+            channel,
+            _types: PhantomData,
+        }
+    }
+
+    /// Create a protobuf channel from an existing transport
+    pub fn from_transport(transport: impl Transport + 'static) -> Self {
+        Self::new(Channel::from_transport(transport, RawCodec))
+    }
+
+    /// Open a protobuf TCP channel
+    pub async fn tcp(addr: SocketAddr) -> Result<Self> {
+        Ok(Self::new(Channel::tcp(addr, RawCodec).await?))
+    }
+
+    /// Open a protobuf Unix socket channel
+    #[cfg(unix)]
+    pub async fn unix(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(Channel::unix(path, RawCodec).await?))
+    }
+
+    /// Send a message over the channel
+    pub async fn send(&mut self, message: &Req) -> Result<()> {
+        let bytes = ProstCodec::<Req>::new().encode(message);
+        self.channel.send_encoded(&bytes).await
+    }
+
+    /// Receive a message from the channel
+    pub async fn receive(&mut self) -> Result<Res> {
+        let bytes = self.channel.receive_encoded().await?;
+        ProstCodec::<Res>::new().decode(&bytes)
+    }
+
+    /// Send a request and receive its response
+    ///
+    /// See [`Channel::request`] for the ordering this assumes.
+    pub async fn request(&mut self, message: &Req) -> Result<Res> {
+        self.send(message).await?;
+        self.receive().await
+    }
+
+    /// Unwrap into the untyped channel
+    pub fn into_inner(self) -> Channel<RawCodec> {
+        self.channel
+    }
+
+    /// Close the channel
+    pub async fn close(self) -> Result<()> {
+        self.channel.close().await
+    }
+}
+
+impl<Req, Res> std::fmt::Debug for ProstChannel<Req, Res> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProstChannel")
+            .field("channel", &self.channel)
+            .finish()
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::codec::Codec;
+use crate::codec::{guard_decode, Codec};
 use crate::error::{Error, Result};
 
 /// Raw codec that passes through bytes without serialization
@@ -15,8 +15,13 @@ impl Codec for RawCodec {
         bincode::serialize(value).map_err(|e| Error::Codec(e.to_string()))
     }
 
+    fn encode_into<T: Serialize>(&self, value: &T, buf: &mut Vec<u8>) -> Result<()> {
+        buf.clear();
+        bincode::serialize_into(buf, value).map_err(|e| Error::Codec(e.to_string()))
+    }
+
     fn decode<T: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<T> {
-        bincode::deserialize(bytes).map_err(|e| Error::Codec(e.to_string()))
+        guard_decode(|| bincode::deserialize(bytes).map_err(|e| Error::Codec(e.to_string())))
     }
 }
 
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+use crate::codec::Codec;
+use crate::error::{Error, Result};
+
+/// Codec wrapper that prepends a schema version byte
+///
+/// Encoded bytes start with the configured version, and decoding rejects
+/// any version not accepted, so peers on incompatible message formats fail
+/// with a codec error instead of decoding into garbage. During a rolling
+/// upgrade, accept both the old and new version until every peer is updated.
+#[derive(Debug, Clone, Default)]
+pub struct VersionedCodec<C> {
+    inner: C,
+    version: u8,
+    also_accepted: Vec<u8>,
+}
+
+impl<C: Codec> VersionedCodec<C> {
+    /// Wrap a codec, writing and accepting only `version`
+    pub fn new(inner: C, version: u8) -> Self {
+        Self {
+            inner,
+            version,
+            also_accepted: Vec::new(),
+        }
+    }
+
+    /// Also accept frames tagged with any of `versions` on decode
+    ///
+    /// Encoding always uses the version passed to [`new`](Self::new).
+    pub fn accept_versions(mut self, versions: impl IntoIterator<Item = u8>) -> Self {
+        self.also_accepted.extend(versions);
+        self
+    }
+
+    /// The version written on encode
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Whether frames tagged with `version` are decoded
+    pub fn accepts(&self, version: u8) -> bool {
+        version == self.version || self.also_accepted.contains(&version)
+    }
+
+    /// Get a reference to the wrapped codec
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+impl<C: Codec> Codec for VersionedCodec<C> {
+    fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.encode_into(value, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn encode_into<T: Serialize>(&self, value: &T, buf: &mut Vec<u8>) -> Result<()> {
+        self.inner.encode_into(value, buf)?;
+        buf.insert(0, self.version);
+        Ok(())
+    }
+
+    fn decode<T: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<T> {
+        let (&version, payload) = bytes
+            .split_first()
+            .ok_or_else(|| Error::Codec("missing schema version".to_string()))?;
+        if !self.accepts(version) {
+            return Err(Error::Codec(format!(
+                "unsupported schema version {}",
+                version
+            )));
+        }
+
+        self.inner.decode(payload)
+    }
+}
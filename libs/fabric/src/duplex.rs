@@ -0,0 +1,208 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures::{Sink, Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::channel::Channel;
+use crate::codec::Codec;
+use crate::error::{Error, Result};
+
+/// Encoded messages [`DuplexChannel`] buffers before `poll_ready` flushes them
+const SINK_BUFFER: usize = 32;
+
+/// In-flight operation, handing the channel back when done
+type Op<Res, C> = Pin<Box<dyn Future<Output = (Option<Channel<C>>, Outcome<Res>)> + Send>>;
+
+enum Outcome<Res> {
+    Sent(Result<()>),
+    Received(Result<Res>),
+}
+
+enum State<Res, C> {
+    Idle(Channel<C>),
+    Busy(Op<Res, C>),
+    Closed,
+}
+
+/// Channel exposed as a `Stream` of `Res` and a `Sink` of `Req`
+///
+/// Lets a channel be driven by `futures` combinators, e.g.
+/// `SinkExt::send_all` to forward a stream of messages, or
+/// `StreamExt::split` to hand the two directions to different tasks.
+///
+/// `start_send` only encodes and buffers; messages are written on
+/// `poll_flush` (and by `poll_ready` once 32 of them are waiting), which
+/// also flushes the transport. `poll_close` flushes and closes the
+/// channel. The stream ends when the peer closes the connection.
+///
+/// The underlying [`Channel`] does one thing at a time, so writing waits
+/// for an in-flight receive to finish and vice versa. Once `poll_next` is
+/// waiting for a message, a flush does not complete until that message
+/// arrives; don't wait on a flush whose data the peer needs before it
+/// replies.
+pub struct DuplexChannel<Req, Res, C> {
+    state: State<Res, C>,
+    codec: C,
+    outbox: Vec<Vec<u8>>,
+    sent: Option<Result<()>>,
+    received: Option<Result<Res>>,
+    _types: PhantomData<fn(&Req)>,
+}
+
+// Fields are never pinned in place
+impl<Req, Res, C> Unpin for DuplexChannel<Req, Res, C> {}
+
+impl<Req, Res, C> DuplexChannel<Req, Res, C>
+where
+    Req: Serialize,
+    Res: for<'de> Deserialize<'de> + Send + 'static,
+    C: Codec + Clone + 'static,
+{
+    /// Wrap an existing channel
+    pub fn new(channel: Channel<C>) -> Self {
+        Self {
+            codec: channel.codec().clone(),
+            state: State::Idle(channel),
+            outbox: Vec::new(),
+            sent: None,
+            received: None,
+            _types: PhantomData,
+        }
+    }
+
+    /// Drive the in-flight operation to completion, storing its outcome
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let State::Busy(op) = &mut self.state else {
+            return Poll::Ready(());
+        };
+        let (channel, outcome) = ready!(op.as_mut().poll(cx));
+        self.state = match channel {
+            Some(channel) => State::Idle(channel),
+            None => State::Closed,
+        };
+        match outcome {
+            Outcome::Sent(result) => self.sent = Some(result),
+            Outcome::Received(result) => self.received = Some(result),
+        }
+        Poll::Ready(())
+    }
+}
+
+impl<Req, Res, C> Stream for DuplexChannel<Req, Res, C>
+where
+    Req: Serialize,
+    Res: for<'de> Deserialize<'de> + Send + 'static,
+    C: Codec + Clone + 'static,
+{
+    type Item = Result<Res>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(result) = this.received.take() {
+                return Poll::Ready(match result {
+                    Err(Error::ConnectionClosed) => None,
+                    result => Some(result),
+                });
+            }
+            ready!(this.poll_idle(cx));
+            if this.received.is_some() {
+                continue;
+            }
+            match std::mem::replace(&mut this.state, State::Closed) {
+                State::Idle(mut channel) => {
+                    this.state = State::Busy(Box::pin(async move {
+                        let result = channel.receive().await;
+                        (Some(channel), Outcome::Received(result))
+                    }));
+                }
+                State::Closed => return Poll::Ready(None),
+                State::Busy(_) => unreachable!("poll_idle leaves no operation in flight"),
+            }
+        }
+    }
+}
+
+impl<Req, Res, C> Sink<Req> for DuplexChannel<Req, Res, C>
+where
+    Req: Serialize,
+    Res: for<'de> Deserialize<'de> + Send + 'static,
+    C: Codec + Clone + 'static,
+{
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        if self.outbox.len() < SINK_BUFFER {
+            return Poll::Ready(Ok(()));
+        }
+        self.poll_flush(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Req) -> Result<()> {
+        let this = self.get_mut();
+        let bytes = this.codec.encode(&item)?;
+        this.outbox.push(bytes);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let this = self.get_mut();
+        loop {
+            ready!(this.poll_idle(cx));
+            if let Some(result) = this.sent.take() {
+                result?;
+            }
+            match std::mem::replace(&mut this.state, State::Closed) {
+                State::Idle(channel) if this.outbox.is_empty() => {
+                    this.state = State::Idle(channel);
+                    return Poll::Ready(Ok(()));
+                }
+                State::Idle(mut channel) => {
+                    let outbox = std::mem::take(&mut this.outbox);
+                    this.state = State::Busy(Box::pin(async move {
+                        let result = async {
+                            for bytes in &outbox {
+                                channel.send_encoded(bytes).await?;
+                            }
+                            channel.flush().await
+                        }
+                        .await;
+                        (Some(channel), Outcome::Sent(result))
+                    }));
+                }
+                State::Closed if this.outbox.is_empty() => return Poll::Ready(Ok(())),
+                State::Closed => return Poll::Ready(Err(Error::ConnectionClosed)),
+                State::Busy(_) => unreachable!("poll_idle leaves no operation in flight"),
+            }
+        }
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        let this = self.get_mut();
+        if let State::Idle(channel) = std::mem::replace(&mut this.state, State::Closed) {
+            this.state = State::Busy(Box::pin(async move {
+                (None, Outcome::Sent(channel.close().await))
+            }));
+        }
+        ready!(this.poll_idle(cx));
+        Poll::Ready(this.sent.take().unwrap_or(Ok(())))
+    }
+}
+
+impl<Req, Res, C> std::fmt::Debug for DuplexChannel<Req, Res, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = match self.state {
+            State::Idle(_) => "idle",
+            State::Busy(_) => "busy",
+            State::Closed => "closed",
+        };
+        f.debug_struct("DuplexChannel")
+            .field("state", &state)
+            .field("buffered", &self.outbox.len())
+            .finish_non_exhaustive()
+    }
+}
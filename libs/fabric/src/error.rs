@@ -14,6 +14,21 @@ pub enum Error {
     #[error("Invalid frame: {0}")]
     InvalidFrame(String),
 
+    #[error("Proxy error: {0}")]
+    Proxy(String),
+
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("receive too slow")]
+    ReceiveTooSlow,
+
+    #[error("sequence gap: expected {expected} got {got}")]
+    SequenceGap { expected: u64, got: u64 },
+
     #[error("{0}")]
     Custom(String),
 }
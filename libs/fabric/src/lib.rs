@@ -29,12 +29,22 @@
 //! # }
 //! ```
 
+pub mod breaker;
 pub mod channel;
 pub mod codec;
+pub mod compat;
+pub mod duplex;
 pub mod error;
+pub mod pool;
+pub mod priority;
 pub mod request;
+pub mod sequenced;
+pub mod server;
+pub mod subscription;
 pub mod transport;
+pub mod typed;
 
 // Re-exports for convenience
-pub use channel::Channel;
+pub use channel::{Channel, ChannelStats};
 pub use error::{Error, Result};
+pub use typed::TypedChannel;
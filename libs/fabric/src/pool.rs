@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+use crate::channel::Channel;
+use crate::codec::Codec;
+use crate::error::{Error, Result};
+
+/// Idle TCP channels kept open per host for reuse
+///
+/// [`get`](Self::get) hands out a parked channel to the host if there is one
+/// and connects otherwise; [`put`](Self::put) parks it again once the caller
+/// is done. At most `max_idle` channels are parked per host, and closed
+/// channels are never parked or handed out. Share one pool, e.g. in an `Arc`.
+#[derive(Debug)]
+pub struct ChannelPool<C> {
+    codec: C,
+    max_idle: usize,
+    idle: Mutex<HashMap<SocketAddr, Vec<Channel<C>>>>,
+}
+
+impl<C: Codec + Clone> ChannelPool<C> {
+    /// Create an empty pool parking at most `max_idle` channels per host
+    pub fn new(codec: C, max_idle: usize) -> Self {
+        Self {
+            codec,
+            max_idle,
+            idle: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a parked channel to `addr`, or connect a new one
+    pub async fn get(&self, addr: SocketAddr) -> Result<Channel<C>> {
+        if let Some(channel) = self.take_idle(addr) {
+            return Ok(channel);
+        }
+        Channel::tcp(addr, self.codec.clone()).await
+    }
+
+    /// Park `channel` for reuse by later [`get`](Self::get)s to `addr`
+    ///
+    /// Returns `false` and drops the channel if it is closed or the host
+    /// already has `max_idle` channels parked.
+    pub fn put(&self, addr: SocketAddr, channel: Channel<C>) -> bool {
+        if channel.is_closed() {
+            return false;
+        }
+        let mut idle = self.lock();
+        let parked = idle.entry(addr).or_default();
+        if parked.len() >= self.max_idle {
+            return false;
+        }
+        parked.push(channel);
+        true
+    }
+
+    /// Connect up to `count` channels to `addr` ahead of time and park them
+    ///
+    /// For hosts known to be needed soon, so the first requests to them skip
+    /// the connect latency. Connects run concurrently, and no more are made
+    /// than it takes to fill the host's `max_idle` slots. A failed connect
+    /// does not stop the others: the errors are returned, and the channels
+    /// that did connect stay parked.
+    pub async fn warmup(&self, addr: SocketAddr, count: usize) -> Vec<Error> {
+        let count = count.min(self.max_idle.saturating_sub(self.idle_count(addr)));
+        let connects = (0..count).map(|_| Channel::tcp(addr, self.codec.clone()));
+
+        let mut errors = Vec::new();
+        for result in futures::future::join_all(connects).await {
+            match result {
+                Ok(channel) => {
+                    self.put(addr, channel);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        errors
+    }
+
+    /// Number of channels parked for `addr`
+    pub fn idle_count(&self, addr: SocketAddr) -> usize {
+        self.lock().get(&addr).map_or(0, Vec::len)
+    }
+
+    fn take_idle(&self, addr: SocketAddr) -> Option<Channel<C>> {
+        let mut idle = self.lock();
+        let parked = idle.get_mut(&addr)?;
+        while let Some(channel) = parked.pop() {
+            if !channel.is_closed() {
+                return Some(channel);
+            }
+        }
+        None
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<SocketAddr, Vec<Channel<C>>>> {
+        self.idle.lock().expect("pool lock poisoned")
+    }
+}
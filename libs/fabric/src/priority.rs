@@ -0,0 +1,273 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::channel::Channel;
+use crate::codec::Codec;
+use crate::error::{Error, Result};
+
+/// Sending side of a channel that writes urgent messages first
+///
+/// Messages are encoded by the caller and queued at one of `levels`
+/// priorities, 0 being the most urgent. A background task owns the channel
+/// and always writes the oldest message of the most urgent non-empty queue
+/// next, so a cancel or shutdown message overtakes a backlog of bulk data.
+///
+/// Priority only reorders frames that have not been written yet: a frame
+/// already being written, however large, is finished before the next one
+/// starts. The writer task owns the channel, so nothing can be received
+/// until [`shutdown`](Self::shutdown) hands it back.
+///
+/// Messages queued with a time-to-live are discarded instead of written
+/// late if it runs out while they wait, e.g. behind a large frame.
+///
+/// A [send deadline](Channel::with_send_deadline) on the channel starts when
+/// a message is queued and spans its wait in the queue as well as its write.
+/// A message still queued at its deadline is discarded like an expired one;
+/// one whose write runs past it fails the writer.
+///
+/// Dropping it without [`shutdown`](Self::shutdown) lets the writer finish
+/// what is already queued in the background, then drops the channel.
+pub struct PriorityChannel<C> {
+    codec: C,
+    send_deadline: Option<Duration>,
+    queues: Arc<SendQueues>,
+    writer: JoinHandle<(Channel<C>, Result<()>)>,
+}
+
+impl<C> PriorityChannel<C>
+where
+    C: Codec + Clone + 'static,
+{
+    /// Start writing `channel` with `levels` priority levels
+    ///
+    /// Must be called within a Tokio runtime. Panics if `levels` is 0.
+    pub fn new(channel: Channel<C>, levels: usize) -> Self {
+        assert!(levels > 0, "priority channel needs at least one level");
+        let codec = channel.codec().clone();
+        let send_deadline = channel.send_deadline();
+        let queues = Arc::new(SendQueues::new(levels));
+        let writer = tokio::spawn(write_loop(channel, queues.clone()));
+        Self {
+            codec,
+            send_deadline,
+            queues,
+            writer,
+        }
+    }
+
+    /// Call `callback` with the priority and encoded bytes of every expired message
+    ///
+    /// Runs on the writer task as it skips the message, so it should not block.
+    pub fn with_on_expired(self, callback: impl Fn(usize, &[u8]) + Send + Sync + 'static) -> Self {
+        *self.queues.on_expired.lock().expect("queue lock poisoned") = Some(Arc::new(callback));
+        self
+    }
+
+    /// Number of priority levels
+    pub fn levels(&self) -> usize {
+        self.queues.levels
+    }
+
+    /// Queue a message at the least urgent priority
+    pub fn send<T: Serialize>(&self, message: &T) -> Result<()> {
+        self.send_priority(message, self.queues.levels - 1)
+    }
+
+    /// Queue a message at `priority`, 0 being the most urgent
+    ///
+    /// Returns once the message is queued, not when it is written; errors
+    /// from the writer surface through [`shutdown`](Self::shutdown), and
+    /// queuing fails with [`Error::ConnectionClosed`] once the writer has
+    /// stopped. Priorities past the last level are clamped to it.
+    pub fn send_priority<T: Serialize>(&self, message: &T, priority: usize) -> Result<()> {
+        let bytes = self.codec.encode(message)?;
+        self.queues.push(bytes, priority, None, self.send_by())
+    }
+
+    /// Queue a message at `priority` that is dropped unless written within `ttl`
+    ///
+    /// A message still queued when `ttl` has passed is discarded and
+    /// reported to the [`with_on_expired`](Self::with_on_expired) callback.
+    /// Once the writer has started on a message it is always finished.
+    pub fn send_with_ttl<T: Serialize>(
+        &self,
+        message: &T,
+        priority: usize,
+        ttl: Duration,
+    ) -> Result<()> {
+        let bytes = self.codec.encode(message)?;
+        self.queues
+            .push(bytes, priority, Some(Instant::now() + ttl), self.send_by())
+    }
+
+    /// When a message queued now must be written by, per the channel's send deadline
+    fn send_by(&self) -> Option<Instant> {
+        self.send_deadline.map(|deadline| Instant::now() + deadline)
+    }
+
+    /// Number of messages queued and not yet being written
+    pub fn queued(&self) -> usize {
+        self.queues.len()
+    }
+
+    /// Write everything still queued, then stop the writer and return the channel
+    ///
+    /// Returns the first send error instead if the writer failed; messages
+    /// queued behind the failure are dropped.
+    pub async fn shutdown(mut self) -> Result<Channel<C>> {
+        self.queues.close();
+        let (channel, result) = (&mut self.writer)
+            .await
+            .map_err(|e| Error::Custom(format!("priority writer failed: {}", e)))?;
+        result.map(|()| channel)
+    }
+}
+
+impl<C> Drop for PriorityChannel<C> {
+    fn drop(&mut self) {
+        // Otherwise the writer waits for more messages forever
+        self.queues.close();
+    }
+}
+
+impl<C> std::fmt::Debug for PriorityChannel<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PriorityChannel")
+            .field("codec", &std::any::type_name::<C>())
+            .field("levels", &self.queues.levels)
+            .field("queued", &self.queues.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Drain the queues into `channel` until closed and empty, or a send fails
+async fn write_loop<C: Codec>(
+    mut channel: Channel<C>,
+    queues: Arc<SendQueues>,
+) -> (Channel<C>, Result<()>) {
+    while let Some(queued) = queues.pop().await {
+        if let Err(e) = channel.send_encoded_by(&queued.bytes, queued.send_by).await {
+            queues.close();
+            return (channel, Err(e));
+        }
+    }
+    (channel, Ok(()))
+}
+
+/// Callback receiving the priority and bytes of an expired message
+type ExpiredCallback = Arc<dyn Fn(usize, &[u8]) + Send + Sync>;
+
+/// Encoded messages waiting for the writer, one queue per priority
+struct SendQueues {
+    state: Mutex<QueueState>,
+    levels: usize,
+    item_ready: Notify,
+    on_expired: Mutex<Option<ExpiredCallback>>,
+}
+
+struct QueueState {
+    queues: Vec<VecDeque<Queued>>,
+    closed: bool,
+}
+
+struct Queued {
+    bytes: Vec<u8>,
+    /// Discard the message if it is still queued by then
+    deadline: Option<Instant>,
+    /// Discard the message if still queued, or fail its write if still running, by then
+    send_by: Option<Instant>,
+}
+
+impl SendQueues {
+    fn new(levels: usize) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                queues: (0..levels).map(|_| VecDeque::new()).collect(),
+                closed: false,
+            }),
+            levels,
+            item_ready: Notify::new(),
+            on_expired: Mutex::new(None),
+        }
+    }
+
+    fn push(
+        &self,
+        bytes: Vec<u8>,
+        priority: usize,
+        deadline: Option<Instant>,
+        send_by: Option<Instant>,
+    ) -> Result<()> {
+        let mut state = self.state.lock().expect("queue lock poisoned");
+        if state.closed {
+            return Err(Error::ConnectionClosed);
+        }
+        state.queues[priority.min(self.levels - 1)].push_back(Queued {
+            bytes,
+            deadline,
+            send_by,
+        });
+        drop(state);
+        self.item_ready.notify_one();
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        let state = self.state.lock().expect("queue lock poisoned");
+        state.queues.iter().map(VecDeque::len).sum()
+    }
+
+    /// Take the most urgent unexpired message, or `None` once closed and empty
+    async fn pop(&self) -> Option<Queued> {
+        loop {
+            let notified = self.item_ready.notified();
+            let next = {
+                let mut state = self.state.lock().expect("queue lock poisoned");
+                match state.pop_front() {
+                    None if state.closed => return None,
+                    next => next,
+                }
+            };
+            let Some((priority, queued)) = next else {
+                notified.await;
+                continue;
+            };
+
+            let now = Instant::now();
+            if [queued.deadline, queued.send_by]
+                .into_iter()
+                .flatten()
+                .any(|deadline| now >= deadline)
+            {
+                // Clone it out so user code never runs under the lock
+                let on_expired = self.on_expired.lock().expect("queue lock poisoned").clone();
+                if let Some(callback) = on_expired {
+                    callback(priority, &queued.bytes);
+                }
+                continue;
+            }
+            return Some(queued);
+        }
+    }
+
+    fn close(&self) {
+        self.state.lock().expect("queue lock poisoned").closed = true;
+        self.item_ready.notify_one();
+    }
+}
+
+impl QueueState {
+    /// Take the oldest message of the most urgent non-empty queue
+    fn pop_front(&mut self) -> Option<(usize, Queued)> {
+        self.queues
+            .iter_mut()
+            .enumerate()
+            .find_map(|(priority, queue)| Some((priority, queue.pop_front()?)))
+    }
+}
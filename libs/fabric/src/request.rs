@@ -1,11 +1,83 @@
+use std::future::Future;
 use std::net::SocketAddr;
+#[cfg(unix)]
 use std::path::Path;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
 use crate::channel::Channel;
 use crate::codec::Codec;
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::subscription::Backoff;
+use crate::transport::{with_timeout, TransportListener};
+
+/// Request envelope carrying an idempotency key
+///
+/// Sent by [`request_tcp_idempotent`]. A server that may receive retried
+/// requests decodes `Idempotent<Req>` and remembers the keys it has
+/// completed, answering a repeated key with the stored response instead of
+/// running the request again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Idempotent<T> {
+    /// Caller-chosen key, the same for every retry of one logical request
+    pub key: String,
+    /// The request itself
+    pub request: T,
+}
+
+/// Error returned to the client in place of a response
+///
+/// `code` is application defined; `message` is meant for humans.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("RPC error {code}: {message}")]
+pub struct RpcError {
+    /// Application-defined error code
+    pub code: u32,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl RpcError {
+    /// Create an error with the given code and message
+    pub fn new(code: u32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Response envelope carrying either a success payload or an [`RpcError`]
+///
+/// Sent by [`Channel::send_response`] and decoded by
+/// [`Channel::receive_response`], so every service reports failures over the
+/// wire the same way.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Envelope<T> {
+    /// The request succeeded
+    Ok(T),
+    /// The request failed
+    Err(RpcError),
+}
+
+impl<T> From<std::result::Result<T, RpcError>> for Envelope<T> {
+    fn from(result: std::result::Result<T, RpcError>) -> Self {
+        match result {
+            Ok(value) => Self::Ok(value),
+            Err(e) => Self::Err(e),
+        }
+    }
+}
+
+impl<T> From<Envelope<T>> for std::result::Result<T, RpcError> {
+    fn from(envelope: Envelope<T>) -> Self {
+        match envelope {
+            Envelope::Ok(value) => Ok(value),
+            Envelope::Err(e) => Err(e),
+        }
+    }
+}
 
 /// Perform a one-off TCP request/response
 ///
@@ -23,7 +95,124 @@ where
     Ok(response)
 }
 
+/// Send a batch of TCP requests over one connection and collect their responses
+///
+/// Opens a connection, sends every request back-to-back, then reads exactly
+/// one response per request and closes, so the batch costs one round trip
+/// instead of one per request. Responses are matched to requests by
+/// position: the server must answer each request, in the order received,
+/// as e.g. [`serve_requests`](crate::server::serve_requests) does.
+///
+/// Responses are only read once all requests are sent, so a batch whose
+/// responses overflow the socket buffers of both ends can stall the server
+/// and this call; keep batches to what those buffers can hold.
+pub async fn request_tcp_pipelined<Req, Res, C>(
+    addr: SocketAddr,
+    requests: &[Req],
+    codec: C,
+) -> Result<Vec<Res>>
+where
+    Req: Serialize,
+    Res: for<'de> Deserialize<'de>,
+    C: Codec,
+{
+    let mut channel = Channel::tcp(addr, codec).await?;
+    for request in requests {
+        channel.send(request).await?;
+    }
+    let mut responses = Vec::with_capacity(requests.len());
+    for _ in requests {
+        responses.push(channel.receive().await?);
+    }
+    channel.close().await?;
+    Ok(responses)
+}
+
+/// Perform a one-off TCP request/response, retrying connection failures
+///
+/// Each attempt opens a fresh connection and sends the whole request again.
+/// Attempts that fail at the connection level (connect refused, I/O errors,
+/// connection closed before the response) or run past
+/// [`Backoff::attempt_timeout`] are retried after a delay following
+/// `policy`; once [`Backoff::max_attempts`] is reached the last
+/// error is returned, and without a limit the request is retried forever.
+/// A response that is received is returned as is, even if it is an
+/// application-level error, and so are codec errors.
+///
+/// Retrying trades at-most-once for at-least-once delivery: a connection
+/// that drops after the request was sent gives no way to tell whether the
+/// server ran it, so a retry may run it twice. That is harmless for
+/// idempotent requests (reads, absolute writes); for anything else use
+/// [`request_tcp_idempotent`] with a server that deduplicates keys, or call
+/// [`request_tcp`] and handle failures yourself to stay at-most-once.
+pub async fn request_tcp_resilient<Req, Res, C>(
+    addr: SocketAddr,
+    request: &Req,
+    codec: C,
+    policy: Backoff,
+) -> Result<Res>
+where
+    Req: Serialize,
+    Res: for<'de> Deserialize<'de>,
+    C: Codec + Clone,
+{
+    let mut delay = policy.initial;
+    let mut attempts = 0;
+    loop {
+        let attempt = async {
+            let mut channel = Channel::tcp(addr, codec.clone()).await?;
+            channel.send(request).await?;
+            let response = channel.receive().await?;
+            // The response is in hand, so a failed close must not trigger a retry
+            let _ = channel.close().await;
+            Ok(response)
+        };
+        let outcome = match policy.attempt_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, attempt).await.ok(),
+            None => Some(attempt.await),
+        };
+        let error = match outcome {
+            None => Error::Custom("Attempt timeout exceeded".to_string()),
+            Some(Err(e @ (Error::Io(_) | Error::ConnectionClosed))) => e,
+            Some(result) => return result,
+        };
+        attempts += 1;
+        if policy.max_attempts.is_some_and(|max| attempts >= max) {
+            return Err(error);
+        }
+
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(policy.max);
+    }
+}
+
+/// Like [`request_tcp_resilient`], wrapping the request in an [`Idempotent`] envelope
+///
+/// Every attempt carries the same `key`, so a server that deduplicates keys
+/// runs the request at most once however often it is retried, giving
+/// exactly-once effects on top of at-least-once delivery. The server must
+/// decode `Idempotent<Req>`.
+pub async fn request_tcp_idempotent<Req, Res, C>(
+    addr: SocketAddr,
+    key: impl Into<String>,
+    request: &Req,
+    codec: C,
+    policy: Backoff,
+) -> Result<Res>
+where
+    Req: Serialize,
+    Res: for<'de> Deserialize<'de>,
+    C: Codec + Clone,
+{
+    let envelope = Idempotent {
+        key: key.into(),
+        request,
+    };
+    request_tcp_resilient(addr, &envelope, codec, policy).await
+}
+
 /// Perform a one-off Unix socket request/response
+#[cfg(unix)]
 pub async fn request_unix<Req, Res, C>(
     path: impl AsRef<Path>,
     request: &Req,
@@ -41,6 +230,31 @@ where
     Ok(response)
 }
 
+/// Serve a single request/response on the next accepted connection
+///
+/// The server-side dual of [`request_tcp`] and [`request_unix`]: accepts one
+/// connection from `listener`, decodes one request, passes it to `handler`,
+/// sends the response back, and closes the connection. The listener stays
+/// open, so calling this in a loop serves requests one at a time.
+pub async fn respond_once<L, Req, Res, C, F, Fut>(listener: &L, codec: C, handler: F) -> Result<()>
+where
+    L: TransportListener,
+    L::Transport: 'static,
+    Req: for<'de> Deserialize<'de>,
+    Res: Serialize,
+    C: Codec,
+    F: FnOnce(Req) -> Fut,
+    Fut: Future<Output = Res>,
+{
+    let transport = listener.accept().await?;
+    let mut channel = Channel::from_transport(transport, codec);
+    let request = channel.receive().await?;
+    let response = handler(request).await;
+    channel.send(&response).await?;
+    channel.close().await?;
+    Ok(())
+}
+
 /// Send a message over TCP without waiting for a response (fire-and-forget)
 pub async fn send_tcp<T, C>(addr: SocketAddr, message: &T, codec: C) -> Result<()>
 where
@@ -54,6 +268,7 @@ where
 }
 
 /// Send a message over Unix socket without waiting for a response (fire-and-forget)
+#[cfg(unix)]
 pub async fn send_unix<T, C>(path: impl AsRef<Path>, message: &T, codec: C) -> Result<()>
 where
     T: Serialize,
@@ -64,3 +279,85 @@ where
     channel.close().await?;
     Ok(())
 }
+
+/// Send a message to several TCP destinations at once (fire-and-forget)
+///
+/// Connects and sends to every address concurrently, like [`send_tcp`] on
+/// each. Returns one result per address, in the same order, so a
+/// destination that is down does not stop delivery to the others.
+pub async fn broadcast_tcp<T, C>(addrs: &[SocketAddr], message: &T, codec: C) -> Vec<Result<()>>
+where
+    T: Serialize,
+    C: Codec + Clone,
+{
+    let sends = addrs
+        .iter()
+        .map(|&addr| send_tcp(addr, message, codec.clone()));
+    futures::future::join_all(sends).await
+}
+
+/// Like [`broadcast_tcp`], giving each destination at most `timeout`
+///
+/// The timeout covers connecting, sending and closing, so a host that is
+/// unreachable or stops reading cannot hold up the whole broadcast; its
+/// result is a "Broadcast timeout exceeded" error instead.
+pub async fn broadcast_tcp_with_timeout<T, C>(
+    addrs: &[SocketAddr],
+    message: &T,
+    codec: C,
+    timeout: Duration,
+) -> Vec<Result<()>>
+where
+    T: Serialize,
+    C: Codec + Clone,
+{
+    let sends = addrs.iter().map(|&addr| {
+        with_timeout(
+            Some(timeout),
+            "Broadcast",
+            send_tcp(addr, message, codec.clone()),
+        )
+    });
+    futures::future::join_all(sends).await
+}
+
+/// Send a message to several Unix socket destinations at once (fire-and-forget)
+///
+/// The Unix socket counterpart of [`broadcast_tcp`].
+#[cfg(unix)]
+pub async fn broadcast_unix<P, T, C>(paths: &[P], message: &T, codec: C) -> Vec<Result<()>>
+where
+    P: AsRef<Path>,
+    T: Serialize,
+    C: Codec + Clone,
+{
+    let sends = paths
+        .iter()
+        .map(|path| send_unix(path, message, codec.clone()));
+    futures::future::join_all(sends).await
+}
+
+/// Like [`broadcast_unix`], giving each destination at most `timeout`
+///
+/// See [`broadcast_tcp_with_timeout`].
+#[cfg(unix)]
+pub async fn broadcast_unix_with_timeout<P, T, C>(
+    paths: &[P],
+    message: &T,
+    codec: C,
+    timeout: Duration,
+) -> Vec<Result<()>>
+where
+    P: AsRef<Path>,
+    T: Serialize,
+    C: Codec + Clone,
+{
+    let sends = paths.iter().map(|path| {
+        with_timeout(
+            Some(timeout),
+            "Broadcast",
+            send_unix(path, message, codec.clone()),
+        )
+    });
+    futures::future::join_all(sends).await
+}
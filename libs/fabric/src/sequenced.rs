@@ -0,0 +1,98 @@
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::channel::Channel;
+use crate::codec::Codec;
+use crate::error::Result;
+
+/// Typestate: the next call must be a send
+#[derive(Debug)]
+pub struct Sending;
+
+/// Typestate: the next call must be a receive
+#[derive(Debug)]
+pub struct Receiving;
+
+/// Channel for strictly alternating protocols, checked at compile time
+///
+/// `send` consumes a `SequencedChannel<C, Sending>` and returns one in the
+/// [`Receiving`] state, and `receive` does the reverse, so sending twice in
+/// a row does not compile:
+///
+/// ```compile_fail
+/// # use constellation_fabric::{codec::BincodeCodec, sequenced::SequencedChannel, Channel};
+/// # async fn run(channel: Channel<BincodeCodec>) -> constellation_fabric::Result<()> {
+/// let client = SequencedChannel::client(channel);
+/// let client = client.send(&1u32).await?;
+/// client.send(&2u32).await?; // only `receive` is available here
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The wire format is that of the wrapped [`Channel`]. A failed call
+/// consumes the channel, since where the peer stands in the sequence is
+/// then unknown.
+pub struct SequencedChannel<C, S> {
+    channel: Channel<C>,
+    _state: PhantomData<S>,
+}
+
+impl<C: Codec> SequencedChannel<C, Sending> {
+    /// Wrap a channel whose side speaks first
+    pub fn client(channel: Channel<C>) -> Self {
+        Self::wrap(channel)
+    }
+
+    /// Send a message, after which only `receive` is allowed
+    pub async fn send<T: Serialize>(
+        mut self,
+        message: &T,
+    ) -> Result<SequencedChannel<C, Receiving>> {
+        self.channel.send(message).await?;
+        Ok(SequencedChannel::wrap(self.channel))
+    }
+}
+
+impl<C: Codec> SequencedChannel<C, Receiving> {
+    /// Wrap a channel whose side waits for the peer first
+    pub fn server(channel: Channel<C>) -> Self {
+        Self::wrap(channel)
+    }
+
+    /// Receive a message, after which only `send` is allowed
+    pub async fn receive<T: for<'de> Deserialize<'de>>(
+        mut self,
+    ) -> Result<(T, SequencedChannel<C, Sending>)> {
+        let message = self.channel.receive().await?;
+        Ok((message, SequencedChannel::wrap(self.channel)))
+    }
+}
+
+impl<C: Codec, S> SequencedChannel<C, S> {
+    fn wrap(channel: Channel<C>) -> Self {
+        Self {
+            channel,
+            _state: PhantomData,
+        }
+    }
+
+    /// Unwrap into the unchecked channel
+    pub fn into_inner(self) -> Channel<C> {
+        self.channel
+    }
+
+    /// Close the channel
+    pub async fn close(self) -> Result<()> {
+        self.channel.close().await
+    }
+}
+
+impl<C, S> std::fmt::Debug for SequencedChannel<C, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SequencedChannel")
+            .field("state", &std::any::type_name::<S>())
+            .field("channel", &self.channel)
+            .finish()
+    }
+}
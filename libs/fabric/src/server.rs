@@ -0,0 +1,545 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Notify, Semaphore};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use crate::channel::Channel;
+use crate::codec::Codec;
+use crate::error::Error;
+use crate::transport::{Transport, TransportListener};
+
+/// Callback invoked whenever a [`QueueConfig`] queue overflows
+type OverflowHook = Box<dyn Fn(OverflowPolicy) + Send + Sync>;
+
+/// Callback invoked when accepting fails for lack of file descriptors
+type ExhaustedHook = Box<dyn Fn(&std::io::Error) + Send + Sync>;
+
+/// Accept connections in the background, spawning `handler` for each one
+///
+/// Accept errors are treated as transient and the loop keeps going, pausing
+/// with the default [`AcceptBackoff`] when file descriptors run out. The
+/// returned [`ListenerHandle`] controls shutdown.
+pub fn serve<L, F, Fut>(listener: L, handler: F) -> ListenerHandle
+where
+    L: TransportListener + 'static,
+    F: Fn(L::Transport) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    serve_with_backoff(listener, handler, AcceptBackoff::default())
+}
+
+/// Like [`serve`], with the given pause when file descriptors run out
+pub fn serve_with_backoff<L, F, Fut>(
+    listener: L,
+    handler: F,
+    backoff: AcceptBackoff,
+) -> ListenerHandle
+where
+    L: TransportListener + 'static,
+    F: Fn(L::Transport) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let stop = CancellationToken::new();
+    let tracker = TaskTracker::new();
+
+    let accept_loop = tokio::spawn(accept_loop(
+        listener,
+        handler,
+        backoff,
+        stop.clone(),
+        tracker.clone(),
+    ));
+
+    ListenerHandle {
+        stop,
+        tracker,
+        accept_loop,
+        overflows: Arc::new(AtomicU64::new(0)),
+    }
+}
+
+/// Serve request/response connections, answering each request with `handler`
+///
+/// Built on [`serve`]: every accepted connection gets its own task that
+/// decodes a request, awaits `handler` for the response, sends it back, and
+/// repeats until the peer closes the connection. Any error on a connection,
+/// including a request that fails to decode, closes that connection only;
+/// other connections and the accept loop carry on. Requests on one
+/// connection are answered in order, one at a time.
+pub fn serve_requests<L, Req, Res, C, F, Fut>(listener: L, codec: C, handler: F) -> ListenerHandle
+where
+    L: TransportListener + 'static,
+    L::Transport: 'static,
+    Req: for<'de> Deserialize<'de> + Send + 'static,
+    Res: Serialize + Send + Sync + 'static,
+    C: Codec + Clone + 'static,
+    F: Fn(Req) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Res> + Send + 'static,
+{
+    serve_requests_with_error_hook(listener, codec, handler, |_| {})
+}
+
+/// Like [`serve_requests`], calling `on_error` with the error that ends a connection
+///
+/// Runs before the connection is closed, for every error but the peer
+/// closing it, which is how a connection normally ends. The place to log
+/// the error or bump a metric; it runs on the connection's task.
+pub fn serve_requests_with_error_hook<L, Req, Res, C, F, Fut>(
+    listener: L,
+    codec: C,
+    handler: F,
+    on_error: impl Fn(&Error) + Send + Sync + 'static,
+) -> ListenerHandle
+where
+    L: TransportListener + 'static,
+    L::Transport: 'static,
+    Req: for<'de> Deserialize<'de> + Send + 'static,
+    Res: Serialize + Send + Sync + 'static,
+    C: Codec + Clone + 'static,
+    F: Fn(Req) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Res> + Send + 'static,
+{
+    let handler = Arc::new(handler);
+    let on_error = Arc::new(on_error);
+    serve(listener, move |transport| {
+        let mut channel = Channel::from_transport(transport, codec.clone());
+        let handler = handler.clone();
+        let on_error = on_error.clone();
+        async move {
+            match answer_requests(&mut channel, &*handler).await {
+                Ok(()) | Err(Error::ConnectionClosed) => {}
+                Err(e) => on_error(&e),
+            }
+            let _ = channel.close().await;
+        }
+    })
+}
+
+/// Answer requests on `channel` until receiving or sending fails
+async fn answer_requests<Req, Res, C, F, Fut>(
+    channel: &mut Channel<C>,
+    handler: &F,
+) -> crate::error::Result<()>
+where
+    Req: for<'de> Deserialize<'de>,
+    Res: Serialize,
+    C: Codec,
+    F: Fn(Req) -> Fut,
+    Fut: Future<Output = Res>,
+{
+    loop {
+        let request = channel.receive().await?;
+        let response = handler(request).await;
+        channel.send(&response).await?;
+    }
+}
+
+/// How the accept loops of [`serve`] and [`serve_queued`] react to file descriptor exhaustion
+///
+/// Once the process (EMFILE) or system (ENFILE) runs out of file
+/// descriptors, every `accept` fails straight away until one is freed, so
+/// retrying at once would spin a core. Instead the loop pauses for `delay`,
+/// 100ms by default, and calls the `on_exhausted` hook, if any, each time.
+pub struct AcceptBackoff {
+    delay: Duration,
+    on_exhausted: Option<ExhaustedHook>,
+}
+
+impl Default for AcceptBackoff {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(100),
+            on_exhausted: None,
+        }
+    }
+}
+
+impl AcceptBackoff {
+    /// Create the default backoff: a 100ms pause and no hook
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how long to pause accepting after running out of file descriptors
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Call `hook` with the accept error each time accepting pauses
+    ///
+    /// The place to log a warning or bump a metric; runs on the accept loop,
+    /// so it should be quick.
+    pub fn on_exhausted(mut self, hook: impl Fn(&std::io::Error) + Send + Sync + 'static) -> Self {
+        self.on_exhausted = Some(Box::new(hook));
+        self
+    }
+
+    /// Pause after a failed accept if it failed for lack of file descriptors
+    async fn pause(&self, error: &Error, stop: &CancellationToken) {
+        let Error::Io(error) = error else {
+            return;
+        };
+        if !is_fd_exhausted(error) {
+            return;
+        }
+        if let Some(hook) = &self.on_exhausted {
+            hook(error);
+        }
+        tokio::select! {
+            _ = stop.cancelled() => {}
+            _ = tokio::time::sleep(self.delay) => {}
+        }
+    }
+}
+
+/// Whether `error` is EMFILE or ENFILE
+fn is_fd_exhausted(error: &std::io::Error) -> bool {
+    // Same values on Linux, macOS and the BSDs
+    #[cfg(unix)]
+    const CODES: [i32; 2] = [24, 23];
+    // WSAEMFILE; Windows has no system-wide limit
+    #[cfg(windows)]
+    const CODES: [i32; 1] = [10024];
+    #[cfg(not(any(unix, windows)))]
+    const CODES: [i32; 0] = [];
+
+    error
+        .raw_os_error()
+        .is_some_and(|code| CODES.contains(&code))
+}
+
+/// What [`serve_queued`] does with a new connection when its queue is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Stop accepting until the queue has room, leaving connections in the kernel backlog
+    #[default]
+    Block,
+    /// Close the new connection
+    Reject,
+    /// Close the connection that has waited longest and queue the new one
+    DropOldest,
+}
+
+/// Limits for [`serve_queued`]
+pub struct QueueConfig {
+    max_handlers: usize,
+    capacity: usize,
+    overflow: OverflowPolicy,
+    on_overflow: Option<OverflowHook>,
+    backoff: AcceptBackoff,
+}
+
+impl QueueConfig {
+    /// Run at most `max_handlers` handlers at once, queueing up to `capacity` more connections
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_handlers` or `capacity` is zero.
+    pub fn new(max_handlers: usize, capacity: usize) -> Self {
+        assert!(max_handlers > 0, "max_handlers must be at least 1");
+        assert!(capacity > 0, "accept queue capacity must be at least 1");
+        Self {
+            max_handlers,
+            capacity,
+            overflow: OverflowPolicy::default(),
+            on_overflow: None,
+            backoff: AcceptBackoff::default(),
+        }
+    }
+
+    /// Set what happens when the queue is full
+    pub fn overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    /// Call `hook` each time the queue overflows
+    ///
+    /// Runs on the accept loop, so it should be quick, e.g. bumping a metric.
+    /// With [`OverflowPolicy::Block`] it runs each time accepting pauses.
+    pub fn on_overflow(mut self, hook: impl Fn(OverflowPolicy) + Send + Sync + 'static) -> Self {
+        self.on_overflow = Some(Box::new(hook));
+        self
+    }
+
+    /// Set how accepting pauses when file descriptors run out
+    pub fn accept_backoff(mut self, backoff: AcceptBackoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+}
+
+/// Like [`serve`], but with a bounded number of handlers and an explicit accept queue
+///
+/// At most `max_handlers` handlers run at once; further accepted connections
+/// wait in a queue of `capacity` until a handler frees up. When the queue is
+/// full the configured [`OverflowPolicy`] applies, and every overflow is
+/// counted in [`ListenerHandle::overflow_count`]. Connections still queued
+/// when accepting stops are handled before the listener counts as drained.
+pub fn serve_queued<L, F, Fut>(listener: L, handler: F, config: QueueConfig) -> ListenerHandle
+where
+    L: TransportListener + 'static,
+    F: Fn(L::Transport) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let stop = CancellationToken::new();
+    let tracker = TaskTracker::new();
+    let overflows = Arc::new(AtomicU64::new(0));
+    let queue = Arc::new(AcceptQueue::new(config.capacity));
+    let handlers = Arc::new(Semaphore::new(config.max_handlers));
+
+    let accept = queued_accept_loop(
+        listener,
+        queue.clone(),
+        config,
+        stop.clone(),
+        overflows.clone(),
+    );
+    let dispatch = dispatch_loop(queue, handler, handlers, tracker.clone());
+    let accept_loop = tokio::spawn(async move {
+        tokio::join!(accept, dispatch);
+    });
+
+    ListenerHandle {
+        stop,
+        tracker,
+        accept_loop,
+        overflows,
+    }
+}
+
+async fn accept_loop<L, F, Fut>(
+    mut listener: L,
+    handler: F,
+    backoff: AcceptBackoff,
+    stop: CancellationToken,
+    tracker: TaskTracker,
+) where
+    L: TransportListener,
+    F: Fn(L::Transport) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    loop {
+        let accepted = tokio::select! {
+            biased;
+            _ = stop.cancelled() => break,
+            accepted = listener.accept() => accepted,
+        };
+
+        match accepted {
+            Ok(transport) => {
+                tracker.spawn(handler(transport));
+            }
+            Err(e) => backoff.pause(&e, &stop).await,
+        }
+    }
+
+    tracker.close();
+    let _ = listener.close().await;
+}
+
+async fn queued_accept_loop<L>(
+    mut listener: L,
+    queue: Arc<AcceptQueue<L::Transport>>,
+    config: QueueConfig,
+    stop: CancellationToken,
+    overflows: Arc<AtomicU64>,
+) where
+    L: TransportListener,
+{
+    let overflowed = |policy| {
+        overflows.fetch_add(1, Ordering::Relaxed);
+        if let Some(hook) = &config.on_overflow {
+            hook(policy);
+        }
+    };
+
+    loop {
+        if config.overflow == OverflowPolicy::Block && queue.is_full() {
+            overflowed(OverflowPolicy::Block);
+            tokio::select! {
+                biased;
+                _ = stop.cancelled() => break,
+                _ = queue.wait_for_space() => {}
+            }
+        }
+
+        let accepted = tokio::select! {
+            biased;
+            _ = stop.cancelled() => break,
+            accepted = listener.accept() => accepted,
+        };
+        let transport = match accepted {
+            Ok(transport) => transport,
+            Err(e) => {
+                config.backoff.pause(&e, &stop).await;
+                continue;
+            }
+        };
+
+        let evicted = match queue.push(transport, config.overflow) {
+            Ok(evicted) => evicted,
+            Err(rejected) => Some(rejected),
+        };
+        if let Some(mut transport) = evicted {
+            overflowed(config.overflow);
+            let _ = transport.close().await;
+        }
+    }
+
+    queue.close();
+    let _ = listener.close().await;
+}
+
+async fn dispatch_loop<T, F, Fut>(
+    queue: Arc<AcceptQueue<T>>,
+    handler: F,
+    handlers: Arc<Semaphore>,
+    tracker: TaskTracker,
+) where
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    loop {
+        let permit = handlers
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        let Some(transport) = queue.pop().await else {
+            break;
+        };
+        let handling = handler(transport);
+        tracker.spawn(async move {
+            handling.await;
+            drop(permit);
+        });
+    }
+
+    tracker.close();
+}
+
+/// Accepted connections waiting for a free handler
+///
+/// One producer (the accept loop) and one consumer (the dispatcher).
+struct AcceptQueue<T> {
+    items: Mutex<VecDeque<T>>,
+    capacity: usize,
+    closed: AtomicBool,
+    item_ready: Notify,
+    space_ready: Notify,
+}
+
+impl<T> AcceptQueue<T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            items: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            closed: AtomicBool::new(false),
+            item_ready: Notify::new(),
+            space_ready: Notify::new(),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.items.lock().expect("queue lock poisoned").len() >= self.capacity
+    }
+
+    /// Queue `item`, returning the connection that was turned away if full
+    ///
+    /// `Err` hands back `item` itself, `Ok(Some)` an evicted older one.
+    fn push(&self, item: T, policy: OverflowPolicy) -> Result<Option<T>, T> {
+        let mut items = self.items.lock().expect("queue lock poisoned");
+        let evicted = if items.len() < self.capacity {
+            None
+        } else if policy == OverflowPolicy::DropOldest {
+            items.pop_front()
+        } else {
+            // Reject, or Block racing a pop; the loop only accepts with room
+            return Err(item);
+        };
+        items.push_back(item);
+        drop(items);
+        self.item_ready.notify_one();
+        Ok(evicted)
+    }
+
+    /// Take the oldest connection, or `None` once closed and empty
+    async fn pop(&self) -> Option<T> {
+        loop {
+            let notified = self.item_ready.notified();
+            {
+                let mut items = self.items.lock().expect("queue lock poisoned");
+                if let Some(item) = items.pop_front() {
+                    self.space_ready.notify_one();
+                    return Some(item);
+                }
+                if self.closed.load(Ordering::Acquire) {
+                    return None;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    async fn wait_for_space(&self) {
+        while self.is_full() {
+            self.space_ready.notified().await;
+        }
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.item_ready.notify_one();
+    }
+}
+
+/// Handle to a listener started with [`serve`] or [`serve_queued`]
+///
+/// Supports graceful draining: stop accepting new connections while letting
+/// in-flight handlers run to completion.
+pub struct ListenerHandle {
+    stop: CancellationToken,
+    tracker: TaskTracker,
+    accept_loop: JoinHandle<()>,
+    overflows: Arc<AtomicU64>,
+}
+
+impl ListenerHandle {
+    /// Stop accepting new connections
+    ///
+    /// The accept loop exits and the listener is closed. Connections that
+    /// were already accepted keep being served.
+    pub fn stop_accepting(&self) {
+        self.stop.cancel();
+    }
+
+    /// Number of connection handlers still running
+    pub fn active_connections(&self) -> usize {
+        self.tracker.len()
+    }
+
+    /// Number of times the accept queue of [`serve_queued`] has overflowed
+    ///
+    /// Always zero for [`serve`].
+    pub fn overflow_count(&self) -> u64 {
+        self.overflows.load(Ordering::Relaxed)
+    }
+
+    /// Wait until the accept loop has exited and every handler has finished
+    ///
+    /// This only resolves after [`stop_accepting`](Self::stop_accepting) has
+    /// been called.
+    pub async fn await_drained(self) {
+        let _ = self.accept_loop.await;
+        self.tracker.wait().await;
+    }
+}
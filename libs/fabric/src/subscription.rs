@@ -0,0 +1,234 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::channel::Channel;
+use crate::codec::Codec;
+use crate::error::{Error, Result};
+use crate::transport::with_timeout;
+
+/// Item yielded by a [`SubscriptionStream`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamItem<T> {
+    /// An event received from the server
+    Event(T),
+    /// The connection was re-established and the subscription re-sent
+    ///
+    /// Events sent by the server while disconnected are lost, so consumers
+    /// that need every event should resynchronise here.
+    Reconnected,
+}
+
+/// Delay between connection attempts of a [`SubscriptionStream`]
+///
+/// Starts at `initial` and doubles after every failed attempt up to `max`.
+/// Also the retry policy of
+/// [`request_tcp_resilient`](crate::request::request_tcp_resilient).
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    pub(crate) initial: Duration,
+    pub(crate) max: Duration,
+    pub(crate) max_attempts: Option<u32>,
+    pub(crate) attempt_timeout: Option<Duration>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(30),
+            max_attempts: None,
+            attempt_timeout: None,
+        }
+    }
+}
+
+impl Backoff {
+    /// Create the default backoff: 100ms doubling up to 30s, retrying forever
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the delay before the first retry
+    pub fn initial(mut self, delay: Duration) -> Self {
+        self.initial = delay;
+        self
+    }
+
+    /// Set the longest delay between retries
+    pub fn max(mut self, delay: Duration) -> Self {
+        self.max = delay;
+        self
+    }
+
+    /// Give up after `attempts` consecutive failed connection attempts
+    ///
+    /// The stream then yields the last error and ends.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    /// Fail an attempt that has not finished within `timeout`
+    ///
+    /// The attempt is then retried like any other failure. For a stream an
+    /// attempt is connecting and sending the subscription; for
+    /// [`request_tcp_resilient`](crate::request::request_tcp_resilient) it
+    /// runs from connecting until the response arrives. Unbounded by default.
+    pub fn attempt_timeout(mut self, timeout: Duration) -> Self {
+        self.attempt_timeout = Some(timeout);
+        self
+    }
+}
+
+/// Stream of decoded events that survives disconnects
+///
+/// Connects with the `connect` closure and sends the subscribe message, then
+/// yields every received event. When the connection is lost, or a receive
+/// fails in a way that leaves it unusable (see
+/// [`Channel::is_reusable_after`]), it reconnects with [`Backoff`], re-sends
+/// the subscription and yields [`StreamItem::Reconnected`] before resuming.
+/// Errors the connection survives, such as an event that fails to decode,
+/// are yielded without dropping it.
+pub struct SubscriptionStream<T> {
+    inner: Pin<Box<dyn Stream<Item = Result<StreamItem<T>>> + Send>>,
+}
+
+impl<T> SubscriptionStream<T>
+where
+    T: for<'de> Deserialize<'de> + Send + 'static,
+{
+    /// Subscribe with the default [`Backoff`]
+    pub fn new<F, Fut, C, S>(connect: F, subscribe: S) -> Self
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Channel<C>>> + Send + 'static,
+        C: Codec + 'static,
+        S: Serialize + Send + Sync + 'static,
+    {
+        Self::with_backoff(connect, subscribe, Backoff::default())
+    }
+
+    /// Subscribe, reconnecting according to `backoff`
+    pub fn with_backoff<F, Fut, C, S>(connect: F, subscribe: S, backoff: Backoff) -> Self
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Channel<C>>> + Send + 'static,
+        C: Codec + 'static,
+        S: Serialize + Send + Sync + 'static,
+    {
+        let state = Subscription {
+            connect,
+            subscribe,
+            backoff,
+            channel: None,
+            connected_before: false,
+            done: false,
+        };
+        Self {
+            inner: Box::pin(stream::unfold(state, Subscription::next)),
+        }
+    }
+}
+
+impl<T> Stream for SubscriptionStream<T> {
+    type Item = Result<StreamItem<T>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+/// State driven by [`SubscriptionStream`]
+struct Subscription<F, S, C> {
+    connect: F,
+    subscribe: S,
+    backoff: Backoff,
+    channel: Option<Channel<C>>,
+    connected_before: bool,
+    done: bool,
+}
+
+impl<F, Fut, S, C> Subscription<F, S, C>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Channel<C>>>,
+    S: Serialize,
+    C: Codec,
+{
+    async fn next<T>(mut self) -> Option<(Result<StreamItem<T>>, Self)>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            let received = match &mut self.channel {
+                Some(channel) => channel.receive().await,
+                None => match self.establish().await {
+                    Ok(channel) => {
+                        self.channel = Some(channel);
+                        if std::mem::replace(&mut self.connected_before, true) {
+                            return Some((Ok(StreamItem::Reconnected), self));
+                        }
+                        continue;
+                    }
+                    Err(e) => {
+                        self.done = true;
+                        return Some((Err(e), self));
+                    }
+                },
+            };
+
+            match received {
+                Ok(event) => return Some((Ok(StreamItem::Event(event)), self)),
+                Err(e) => {
+                    let reusable = self
+                        .channel
+                        .as_ref()
+                        .is_some_and(|channel| channel.is_reusable_after(&e));
+                    if reusable {
+                        return Some((Err(e), self));
+                    }
+                    self.channel = None;
+                }
+            }
+        }
+    }
+
+    /// Connect and subscribe, retrying with backoff
+    async fn establish(&mut self) -> Result<Channel<C>> {
+        let mut delay = self.backoff.initial;
+        let mut attempts = 0;
+        // The first connection is attempted immediately, reconnects wait first
+        if self.connected_before {
+            tokio::time::sleep(delay).await;
+        }
+
+        loop {
+            let attempt = async {
+                let mut channel = (self.connect)().await?;
+                channel.send(&self.subscribe).await?;
+                Ok::<_, Error>(channel)
+            };
+            match with_timeout(self.backoff.attempt_timeout, "Attempt", attempt).await {
+                Ok(channel) => return Ok(channel),
+                Err(e) => {
+                    attempts += 1;
+                    if self.backoff.max_attempts.is_some_and(|max| attempts >= max) {
+                        return Err(e);
+                    }
+                }
+            }
+
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(self.backoff.max);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::error::Result;
+use crate::transport::{
+    ConnectionState, FrameType, TcpTransport, TcpTransportListener, Transport, TransportKind,
+    TransportListener, UnixTransport, UnixTransportListener,
+};
+
+/// Transport that is either a TCP or a Unix socket connection
+///
+/// Lets a single handler serve both local and remote clients.
+pub enum AnyTransport {
+    Tcp(TcpTransport),
+    Unix(UnixTransport),
+}
+
+impl From<TcpTransport> for AnyTransport {
+    fn from(transport: TcpTransport) -> Self {
+        Self::Tcp(transport)
+    }
+}
+
+impl From<UnixTransport> for AnyTransport {
+    fn from(transport: UnixTransport) -> Self {
+        Self::Unix(transport)
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for AnyTransport {
+    async fn send(&mut self, bytes: &[u8]) -> Result<()> {
+        match self {
+            Self::Tcp(transport) => transport.send(bytes).await,
+            Self::Unix(transport) => transport.send(bytes).await,
+        }
+    }
+
+    async fn send_vectored(&mut self, parts: &[&[u8]]) -> Result<()> {
+        match self {
+            Self::Tcp(transport) => transport.send_vectored(parts).await,
+            Self::Unix(transport) => transport.send_vectored(parts).await,
+        }
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        match self {
+            Self::Tcp(transport) => transport.receive().await,
+            Self::Unix(transport) => transport.receive().await,
+        }
+    }
+
+    async fn receive_into(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Self::Tcp(transport) => transport.receive_into(buf).await,
+            Self::Unix(transport) => transport.receive_into(buf).await,
+        }
+    }
+
+    async fn receive_ready(&mut self) -> Result<Vec<Vec<u8>>> {
+        match self {
+            Self::Tcp(transport) => transport.receive_ready().await,
+            Self::Unix(transport) => transport.receive_ready().await,
+        }
+    }
+
+    async fn readable(&mut self) -> Result<()> {
+        match self {
+            Self::Tcp(transport) => transport.readable().await,
+            Self::Unix(transport) => transport.readable().await,
+        }
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        match self {
+            Self::Tcp(transport) => transport.flush().await,
+            Self::Unix(transport) => transport.flush().await,
+        }
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        match self {
+            Self::Tcp(transport) => transport.close().await,
+            Self::Unix(transport) => transport.close().await,
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        match self {
+            Self::Tcp(transport) => transport.is_closed(),
+            Self::Unix(transport) => transport.is_closed(),
+        }
+    }
+
+    fn state(&self) -> ConnectionState {
+        match self {
+            Self::Tcp(transport) => transport.state(),
+            Self::Unix(transport) => transport.state(),
+        }
+    }
+
+    fn kind(&self) -> TransportKind {
+        match self {
+            Self::Tcp(transport) => transport.kind(),
+            Self::Unix(transport) => transport.kind(),
+        }
+    }
+
+    fn describe(&self) -> Option<&dyn std::fmt::Debug> {
+        match self {
+            Self::Tcp(transport) => transport.describe(),
+            Self::Unix(transport) => transport.describe(),
+        }
+    }
+
+    fn frame_overhead(&self) -> usize {
+        match self {
+            Self::Tcp(transport) => transport.frame_overhead(),
+            Self::Unix(transport) => transport.frame_overhead(),
+        }
+    }
+
+    async fn send_control(&mut self, frame_type: FrameType, payload: &[u8]) -> Result<()> {
+        match self {
+            Self::Tcp(transport) => transport.send_control(frame_type, payload).await,
+            Self::Unix(transport) => transport.send_control(frame_type, payload).await,
+        }
+    }
+
+    async fn receive_frame(&mut self) -> Result<(FrameType, Vec<u8>)> {
+        match self {
+            Self::Tcp(transport) => transport.receive_frame().await,
+            Self::Unix(transport) => transport.receive_frame().await,
+        }
+    }
+
+    async fn send_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        len: usize,
+    ) -> Result<()> {
+        match self {
+            Self::Tcp(transport) => transport.send_from_reader(reader, len).await,
+            Self::Unix(transport) => transport.send_from_reader(reader, len).await,
+        }
+    }
+
+    async fn receive_to_writer(
+        &mut self,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<usize> {
+        match self {
+            Self::Tcp(transport) => transport.receive_to_writer(writer).await,
+            Self::Unix(transport) => transport.receive_to_writer(writer).await,
+        }
+    }
+}
+
+/// Listener that is either a TCP or a Unix socket listener
+///
+/// Accepted connections are returned as [`AnyTransport`].
+pub enum AnyListener {
+    Tcp(TcpTransportListener),
+    Unix(UnixTransportListener),
+}
+
+impl From<TcpTransportListener> for AnyListener {
+    fn from(listener: TcpTransportListener) -> Self {
+        Self::Tcp(listener)
+    }
+}
+
+impl From<UnixTransportListener> for AnyListener {
+    fn from(listener: UnixTransportListener) -> Self {
+        Self::Unix(listener)
+    }
+}
+
+#[async_trait::async_trait]
+impl TransportListener for AnyListener {
+    type Transport = AnyTransport;
+
+    async fn accept(&self) -> Result<Self::Transport> {
+        match self {
+            Self::Tcp(listener) => TransportListener::accept(listener).await.map(Into::into),
+            Self::Unix(listener) => TransportListener::accept(listener).await.map(Into::into),
+        }
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        match self {
+            Self::Tcp(listener) => listener.close().await,
+            Self::Unix(listener) => listener.close().await,
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+
+use crate::error::{Error, Result};
+
+/// Process-wide ceiling on memory held by in-flight receive buffers
+///
+/// Share one budget (in an `Arc`) between transports. Each receive reserves
+/// the frame's payload size before allocating it and releases the
+/// reservation once the frame has been read, waiting while the budget is
+/// exhausted.
+///
+/// Only reads in flight are bounded: a payload handed back to the caller no
+/// longer counts against the budget, however long it is kept. Bound what
+/// the application holds on to separately, e.g. with a queue limit.
+///
+/// A frame larger than the whole budget fails the receive with
+/// [`Error::InvalidFrame`] before any of its payload is read. The rest of
+/// the frame is still on the stream, so the connection is closed.
+#[derive(Debug)]
+pub struct MemoryBudget {
+    bytes: Semaphore,
+    capacity: usize,
+}
+
+impl MemoryBudget {
+    /// Create a budget of `bytes`
+    ///
+    /// Capped at [`Semaphore::MAX_PERMITS`].
+    pub fn new(bytes: usize) -> Self {
+        let capacity = bytes.min(Semaphore::MAX_PERMITS);
+        Self {
+            bytes: Semaphore::new(capacity),
+            capacity,
+        }
+    }
+
+    /// Total size of the budget in bytes
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Bytes not currently reserved by a receive
+    pub fn available(&self) -> usize {
+        self.bytes.available_permits()
+    }
+
+    /// Reserve `bytes`, waiting until enough of the budget is free
+    pub(crate) async fn reserve(self: &Arc<Self>, bytes: usize) -> Result<Reservation> {
+        let permits = u32::try_from(bytes)
+            .ok()
+            .filter(|_| bytes <= self.capacity)
+            .ok_or_else(|| {
+                Error::InvalidFrame(format!(
+                    "Frame of {} bytes exceeds the memory budget of {} bytes",
+                    bytes, self.capacity
+                ))
+            })?;
+        self.bytes
+            .acquire_many(permits)
+            .await
+            .expect("semaphore is never closed")
+            .forget();
+        Ok(Reservation {
+            budget: self.clone(),
+            bytes: permits,
+        })
+    }
+}
+
+/// Part of a [`MemoryBudget`] held for one frame, given back when dropped
+pub(crate) struct Reservation {
+    budget: Arc<MemoryBudget>,
+    bytes: u32,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.budget.bytes.add_permits(self.bytes as usize);
+    }
+}
@@ -0,0 +1,210 @@
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::error::Result;
+use crate::transport::{ConnectionState, FrameType, Transport, TransportKind};
+
+/// Transport wrapper that simulates a bad network for tests
+///
+/// Delays every data frame sent and received by a random latency, and
+/// silently drops a fraction of them: a dropped send reports success
+/// without writing anything, a dropped receive is discarded and the next
+/// frame awaited instead. Randomness comes from a seeded generator, so a
+/// given seed perturbs the same sequence of frames the same way on every
+/// run. Control frames and streamed payloads pass through untouched.
+///
+/// Requires the `test-util` feature.
+pub struct ChaosTransport<T> {
+    inner: T,
+    rng: SplitMix64,
+    latency: (Duration, Duration),
+    drop_rate: f64,
+}
+
+impl<T: Transport> ChaosTransport<T> {
+    /// Wrap a transport, with randomness seeded by `seed`
+    ///
+    /// Adds no latency and drops nothing until configured.
+    pub fn new(inner: T, seed: u64) -> Self {
+        Self {
+            inner,
+            rng: SplitMix64(seed),
+            latency: (Duration::ZERO, Duration::ZERO),
+            drop_rate: 0.0,
+        }
+    }
+
+    /// Delay each frame by a latency drawn uniformly from `min..=max`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is greater than `max`.
+    pub fn with_latency(mut self, min: Duration, max: Duration) -> Self {
+        assert!(min <= max, "minimum latency exceeds maximum");
+        self.latency = (min, max);
+        self
+    }
+
+    /// Drop each frame with probability `rate`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is not between 0 and 1.
+    pub fn with_drop_rate(mut self, rate: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&rate),
+            "drop rate must be within 0..=1"
+        );
+        self.drop_rate = rate;
+        self
+    }
+
+    /// Get a reference to the wrapped transport
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwrap the chaos, returning the inner transport
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Wait out a random latency, then decide whether the frame is dropped
+    async fn perturb(&mut self) -> bool {
+        let (min, max) = self.latency;
+        let delay = min + (max - min).mul_f64(self.rng.next_f64());
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        self.rng.next_f64() < self.drop_rate
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Transport> Transport for ChaosTransport<T> {
+    async fn send(&mut self, bytes: &[u8]) -> Result<()> {
+        if self.perturb().await {
+            return Ok(());
+        }
+        self.inner.send(bytes).await
+    }
+
+    async fn send_vectored(&mut self, parts: &[&[u8]]) -> Result<()> {
+        if self.perturb().await {
+            return Ok(());
+        }
+        self.inner.send_vectored(parts).await
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        loop {
+            let bytes = self.inner.receive().await?;
+            if !self.perturb().await {
+                return Ok(bytes);
+            }
+        }
+    }
+
+    async fn receive_into(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        loop {
+            self.inner.receive_into(buf).await?;
+            if !self.perturb().await {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn receive_ready(&mut self) -> Result<Vec<Vec<u8>>> {
+        let mut delivered = Vec::new();
+        for bytes in self.inner.receive_ready().await? {
+            if !self.perturb().await {
+                delivered.push(bytes);
+            }
+        }
+        Ok(delivered)
+    }
+
+    async fn readable(&mut self) -> Result<()> {
+        self.inner.readable().await
+    }
+
+    #[cfg(feature = "bytes")]
+    async fn receive_shared(&mut self) -> Result<bytes::Bytes> {
+        loop {
+            let bytes = self.inner.receive_shared().await?;
+            if !self.perturb().await {
+                return Ok(bytes);
+            }
+        }
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.inner.flush().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    fn state(&self) -> ConnectionState {
+        self.inner.state()
+    }
+
+    fn kind(&self) -> TransportKind {
+        self.inner.kind()
+    }
+
+    fn describe(&self) -> Option<&dyn std::fmt::Debug> {
+        self.inner.describe()
+    }
+
+    fn frame_overhead(&self) -> usize {
+        self.inner.frame_overhead()
+    }
+
+    async fn send_control(&mut self, frame_type: FrameType, payload: &[u8]) -> Result<()> {
+        self.inner.send_control(frame_type, payload).await
+    }
+
+    async fn receive_frame(&mut self) -> Result<(FrameType, Vec<u8>)> {
+        self.inner.receive_frame().await
+    }
+
+    async fn send_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        len: usize,
+    ) -> Result<()> {
+        self.inner.send_from_reader(reader, len).await
+    }
+
+    async fn receive_to_writer(
+        &mut self,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<usize> {
+        self.inner.receive_to_writer(writer).await
+    }
+}
+
+/// Small seeded generator; quality is ample for picking delays and drops
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0.0..1.0`
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,88 @@
+//! [`tokio_util::codec`] adapter for the default frame format
+//!
+//! Lets the wire format be used from a [`Framed`](tokio_util::codec::Framed)
+//! pipeline, talking to a transport with default framing on the other end.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::error::{Error, Result};
+use crate::transport::framing::MAX_FRAME_SIZE;
+
+/// Length of the big-endian length prefix
+const PREFIX_LEN: usize = 4;
+
+/// Encoder/decoder for 4-byte big-endian length-prefixed frames
+///
+/// Decodes to the payload of each frame and rejects frames larger than
+/// [`MAX_FRAME_SIZE`] in either direction, matching
+/// [`framing::read_frame`](crate::transport::framing::read_frame).
+///
+/// ```ignore
+/// let mut framed = Framed::new(stream, FabricCodec::new());
+/// framed.send(Bytes::from_static(b"hello")).await?;
+/// let reply = framed.next().await.transpose()?;
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FabricCodec {
+    _priv: (),
+}
+
+impl FabricCodec {
+    /// Create a codec for the default frame format
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Decoder for FabricCodec {
+    type Item = BytesMut;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>> {
+        let Some(prefix) = src.get(..PREFIX_LEN) else {
+            return Ok(None);
+        };
+        let len = u32::from_be_bytes(prefix.try_into().expect("prefix is 4 bytes")) as usize;
+        if len > MAX_FRAME_SIZE {
+            return Err(Error::InvalidFrame(format!(
+                "Message too large: {} bytes",
+                len
+            )));
+        }
+
+        if src.len() < PREFIX_LEN + len {
+            src.reserve(PREFIX_LEN + len - src.len());
+            return Ok(None);
+        }
+        src.advance(PREFIX_LEN);
+        Ok(Some(src.split_to(len)))
+    }
+}
+
+impl Encoder<&[u8]> for FabricCodec {
+    type Error = Error;
+
+    fn encode(&mut self, payload: &[u8], dst: &mut BytesMut) -> Result<()> {
+        // The peer's decoder would reject the frame anyway
+        if payload.len() > MAX_FRAME_SIZE {
+            return Err(Error::InvalidFrame(format!(
+                "Payload of {} bytes exceeds the {} byte frame limit",
+                payload.len(),
+                MAX_FRAME_SIZE
+            )));
+        }
+        dst.reserve(PREFIX_LEN + payload.len());
+        dst.put_u32(payload.len() as u32);
+        dst.put_slice(payload);
+        Ok(())
+    }
+}
+
+impl Encoder<Bytes> for FabricCodec {
+    type Error = Error;
+
+    fn encode(&mut self, payload: Bytes, dst: &mut BytesMut) -> Result<()> {
+        self.encode(&payload[..], dst)
+    }
+}
@@ -0,0 +1,752 @@
+//! Length-prefix framing for stream transports
+//!
+//! Byte-stream transports (TCP, Unix sockets) have no message boundaries of
+//! their own, so every frame is written as a length prefix (4 bytes,
+//! big-endian by default) followed by the payload. These helpers implement that format
+//! for any tokio stream, so new stream transports share one wire format.
+//!
+//! [`FrameConfig`] describes optional extensions to the default format, and
+//! [`FramingMode`] alternative layouts such as delimiter-terminated lines.
+//! Both peers must use the same configuration.
+
+use std::io::IoSlice;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::error::{Error, Result};
+
+/// Largest frame payload accepted on receive (100MB)
+pub const MAX_FRAME_SIZE: usize = 100 * 1024 * 1024;
+
+/// Kind of frame, carried in a type byte when frame types are enabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FrameType {
+    /// Application data
+    Data = 0,
+    /// Liveness probe; the receiver answers with a [`FrameType::Pong`] echoing the payload
+    Ping = 1,
+    /// Answer to a [`FrameType::Ping`]
+    Pong = 2,
+    /// The sender is closing the connection and will send nothing more
+    ///
+    /// Sent by `close` when frame types are enabled, so the receiver can tell
+    /// a graceful close from a dropped connection.
+    Close = 3,
+    /// Application-defined control message
+    ///
+    /// Only delivered by `receive_frame`; data receives skip it.
+    Control = 4,
+}
+
+impl TryFrom<u8> for FrameType {
+    type Error = Error;
+
+    fn try_from(byte: u8) -> Result<Self> {
+        match byte {
+            0 => Ok(Self::Data),
+            1 => Ok(Self::Ping),
+            2 => Ok(Self::Pong),
+            3 => Ok(Self::Close),
+            4 => Ok(Self::Control),
+            other => Err(Error::InvalidFrame(format!(
+                "Unknown frame type: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Byte order of the length prefix
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endian {
+    /// Network byte order, the default
+    #[default]
+    Big,
+    /// Little-endian, for peers that write the prefix in host order on x86
+    Little,
+}
+
+/// Width of the length prefix
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PrefixWidth {
+    /// 2 bytes, for channels that only carry small messages
+    U16,
+    /// 4 bytes, the default
+    #[default]
+    U32,
+    /// 8 bytes, for frames over 4GB
+    U64,
+}
+
+impl PrefixWidth {
+    /// Number of bytes the prefix takes on the wire
+    pub fn bytes(self) -> usize {
+        match self {
+            Self::U16 => 2,
+            Self::U32 => 4,
+            Self::U64 => 8,
+        }
+    }
+
+    /// Largest length the prefix can express
+    pub fn max_len(self) -> u64 {
+        match self {
+            Self::U16 => u16::MAX.into(),
+            Self::U32 => u32::MAX.into(),
+            Self::U64 => u64::MAX,
+        }
+    }
+}
+
+/// Overall layout of a frame on the wire
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FramingMode {
+    /// This crate's format: length prefix, then the options of [`FrameConfig`]
+    #[default]
+    LengthPrefixed,
+    /// gRPC message framing: a compression flag byte, a 4-byte big-endian
+    /// payload length, then the payload
+    ///
+    /// The flag is the frame's one-byte header, so it is sent with
+    /// `send_with_header::<1>` and read with `receive_with_header::<1>`;
+    /// plain sends clear it. Requires a header length of 1 and none of the
+    /// other [`FrameConfig`] options, which the gRPC format has no room for.
+    GrpcLengthPrefixed,
+    /// Frames end with the given delimiter byte instead of starting with a length
+    ///
+    /// For line protocols such as newline-delimited JSON: `Delimited(b'\n')`
+    /// sends each payload followed by a newline and receives up to the next
+    /// one. There is no escaping, so payloads must not contain the delimiter;
+    /// sends reject payloads that do, except streamed ones, which are the
+    /// caller's responsibility. Use the length-prefixed format for arbitrary
+    /// bytes. A frame that has not ended within [`MAX_FRAME_SIZE`] bytes
+    /// fails the receive, and sends reject longer payloads. Carries data
+    /// frames only and allows none of the other [`FrameConfig`] options,
+    /// which transport builders check when they connect or bind; the memory
+    /// budget and minimum receive rate do not apply.
+    Delimited(u8),
+}
+
+/// Error for a payload source that ran dry after `copied` of `len` bytes
+pub(crate) fn source_ended(copied: u64, len: usize) -> Error {
+    Error::InvalidFrame(format!("Source ended after {} of {} bytes", copied, len))
+}
+
+/// A decoded frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Frame type, always [`FrameType::Data`] unless frame types are enabled
+    pub frame_type: FrameType,
+    /// Metadata header, empty unless a header length is configured
+    pub header: Vec<u8>,
+    /// Frame payload
+    pub payload: Vec<u8>,
+}
+
+/// Wire format options for length-prefixed frames
+///
+/// The default is the plain format: a 4-byte big-endian length followed by
+/// the payload. Transport builders expose each option as a setter.
+#[derive(Debug, Clone, Default)]
+pub struct FrameConfig {
+    /// Length of a fixed metadata header carried before every payload
+    ///
+    /// The length prefix covers header and payload together, so a peer
+    /// without the header configured sees the header as part of the payload.
+    pub header_len: usize,
+
+    /// Carry a one-byte [`FrameType`] after the length prefix
+    ///
+    /// Required for control frames such as ping/pong and close. Adds one
+    /// byte per frame and is not understood by peers without it enabled.
+    pub frame_types: bool,
+
+    /// Carry an 8-byte big-endian sequence number after the frame type
+    ///
+    /// [`StreamTransport`](crate::transport::StreamTransport) numbers the
+    /// frames it sends from 0 and fails a receive with
+    /// [`Error::SequenceGap`] when a frame's number is not the one after the
+    /// last, catching lost or reordered frames. Adds 8 bytes per frame and
+    /// is not understood by peers without it enabled.
+    pub sequence_numbers: bool,
+
+    /// Byte order of the length prefix
+    pub length_endian: Endian,
+
+    /// Width of the length prefix
+    ///
+    /// Frames longer than the width can express fail to send. Receives are
+    /// still capped at [`MAX_FRAME_SIZE`] unless streamed.
+    pub length_width: PrefixWidth,
+
+    /// Follow the length prefix with a CRC32 of its bytes
+    ///
+    /// The length is only trusted once the checksum matches, which guards
+    /// against a corrupted prefix causing a huge allocation or a desync at
+    /// the cost of 4 bytes per frame and no payload hashing.
+    pub length_checksum: bool,
+
+    /// Magic bytes written before every length prefix
+    ///
+    /// Lets the reader detect a stream that has lost frame alignment. Pick a
+    /// value unlikely to appear in payloads.
+    pub sync_marker: Option<[u8; 4]>,
+
+    /// Recover from corrupt frames by scanning ahead for the next sync marker
+    ///
+    /// Requires [`sync_marker`](Self::sync_marker). Frames that fail
+    /// validation (bad marker, impossible length, unknown type) are skipped
+    /// instead of failing the receive. A corrupted length that still looks
+    /// valid cannot be detected and is read as a normal frame.
+    pub resync: bool,
+
+    /// Overall frame layout
+    ///
+    /// [`FramingMode::GrpcLengthPrefixed`] replaces the format described
+    /// above for interop with gRPC-framed peers, and
+    /// [`FramingMode::Delimited`] for line-oriented ones.
+    pub mode: FramingMode,
+}
+
+impl FrameConfig {
+    /// Write one frame and flush the stream
+    ///
+    /// `header` must be exactly [`header_len`](Self::header_len) bytes, and
+    /// non-data frames require [`frame_types`](Self::frame_types). With
+    /// [`sequence_numbers`](Self::sequence_numbers) the frame is numbered 0.
+    pub async fn write<S>(
+        &self,
+        stream: &mut S,
+        frame_type: FrameType,
+        header: &[u8],
+        payload: &[u8],
+    ) -> Result<()>
+    where
+        S: AsyncWrite + Unpin + ?Sized,
+    {
+        self.check_delimited()?;
+        self.check_frame(frame_type, header.len(), payload.len())?;
+        self.check_delimiter_free(payload)?;
+        self.write_numbered(stream, frame_type, header, 0, payload)
+            .await
+    }
+
+    /// Like [`write`](Self::write), numbering the frame `sequence`
+    ///
+    /// The frame must already have passed [`check_frame`](Self::check_frame)
+    /// and [`check_delimiter_free`](Self::check_delimiter_free).
+    pub(crate) async fn write_numbered<S>(
+        &self,
+        stream: &mut S,
+        frame_type: FrameType,
+        header: &[u8],
+        sequence: u64,
+        payload: &[u8],
+    ) -> Result<()>
+    where
+        S: AsyncWrite + Unpin + ?Sized,
+    {
+        self.write_head(stream, frame_type, header, sequence, payload.len())
+            .await?;
+        stream.write_all(payload).await?;
+        stream.write_all(self.tail()).await?;
+        stream.flush().await?;
+
+        Ok(())
+    }
+
+    /// Write one data frame whose payload is `parts` joined, and flush the stream
+    ///
+    /// The prefix covers the sum of all parts, and the prefix and parts are
+    /// handed to the stream in vectored writes, so the parts are never
+    /// concatenated in memory. The frame must already have been checked as
+    /// for [`write_numbered`](Self::write_numbered).
+    pub(crate) async fn write_vectored<S>(
+        &self,
+        stream: &mut S,
+        header: &[u8],
+        sequence: u64,
+        parts: &[&[u8]],
+    ) -> Result<()>
+    where
+        S: AsyncWrite + Unpin + ?Sized,
+    {
+        let len = parts.iter().map(|part| part.len()).sum();
+        let mut head = Vec::new();
+        self.write_head(&mut head, FrameType::Data, header, sequence, len)
+            .await?;
+
+        let mut slices: Vec<IoSlice<'_>> = std::iter::once(&head[..])
+            .chain(parts.iter().copied())
+            .chain(std::iter::once(self.tail()))
+            .filter(|part| !part.is_empty())
+            .map(IoSlice::new)
+            .collect();
+        let mut remaining = &mut slices[..];
+        while !remaining.is_empty() {
+            let written = stream.write_vectored(remaining).await?;
+            if written == 0 {
+                return Err(std::io::Error::from(std::io::ErrorKind::WriteZero).into());
+            }
+            IoSlice::advance_slices(&mut remaining, written);
+        }
+        stream.flush().await?;
+
+        Ok(())
+    }
+
+    /// Write a data frame whose `len`-byte payload is copied from `reader`
+    ///
+    /// The payload is streamed rather than buffered. Fails with
+    /// [`Error::InvalidFrame`] if `reader` ends early, in which case a
+    /// truncated frame is left on the wire. The frame must already have
+    /// passed [`check_frame`](Self::check_frame).
+    pub(crate) async fn write_from_reader<S, R>(
+        &self,
+        stream: &mut S,
+        reader: &mut R,
+        sequence: u64,
+        len: usize,
+    ) -> Result<()>
+    where
+        S: AsyncWrite + Unpin + ?Sized,
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        let header = vec![0u8; self.header_len];
+        self.write_head(stream, FrameType::Data, &header, sequence, len)
+            .await?;
+        let copied = tokio::io::copy(&mut reader.take(len as u64), stream).await?;
+        if copied < len as u64 {
+            return Err(source_ended(copied, len));
+        }
+        stream.write_all(self.tail()).await?;
+        stream.flush().await?;
+
+        Ok(())
+    }
+
+    async fn write_head<S>(
+        &self,
+        stream: &mut S,
+        frame_type: FrameType,
+        header: &[u8],
+        sequence: u64,
+        payload_len: usize,
+    ) -> Result<()>
+    where
+        S: AsyncWrite + Unpin + ?Sized,
+    {
+        if self.delimiter().is_some() {
+            // The frame is the payload and its trailing delimiter
+            return Ok(());
+        }
+        let len = (self.type_len() + self.sequence_len() + header.len() + payload_len) as u64;
+
+        if self.mode == FramingMode::GrpcLengthPrefixed {
+            // Compression flag, then the length of the payload alone
+            stream.write_all(header).await?;
+            stream.write_u32(len as u32 - 1).await?;
+            return Ok(());
+        }
+
+        if let Some(marker) = &self.sync_marker {
+            stream.write_all(marker).await?;
+        }
+
+        // Write length prefix
+        let mut prefix = [0u8; 8];
+        let prefix = &mut prefix[..self.length_width.bytes()];
+        self.encode_prefix(len, prefix);
+        stream.write_all(prefix).await?;
+        if self.length_checksum {
+            stream.write_u32(crc32fast::hash(prefix)).await?;
+        }
+
+        // Write data
+        if self.frame_types {
+            stream.write_u8(frame_type as u8).await?;
+        }
+        if self.sequence_numbers {
+            stream.write_u64(sequence).await?;
+        }
+        stream.write_all(header).await?;
+
+        Ok(())
+    }
+
+    /// Read one frame
+    ///
+    /// Returns [`Error::ConnectionClosed`] if the stream ends mid-frame and
+    /// [`Error::InvalidFrame`] if the prefix exceeds [`MAX_FRAME_SIZE`].
+    /// [Delimited](FramingMode::Delimited) frames are read one byte at a
+    /// time, so give it a buffered stream.
+    pub async fn read<S>(&self, stream: &mut S) -> Result<Frame>
+    where
+        S: AsyncRead + Unpin + ?Sized,
+    {
+        if let Some(delimiter) = self.delimiter() {
+            self.check_delimited()?;
+            let mut payload = Vec::new();
+            loop {
+                let byte = stream.read_u8().await.map_err(map_eof)?;
+                if byte == delimiter {
+                    break;
+                }
+                if payload.len() == MAX_FRAME_SIZE {
+                    return Err(self.unterminated());
+                }
+                payload.push(byte);
+            }
+            return Ok(Frame {
+                frame_type: FrameType::Data,
+                header: Vec::new(),
+                payload,
+            });
+        }
+
+        let head = self.read_head(stream, MAX_FRAME_SIZE).await?;
+        let mut payload = vec![0u8; head.payload_len];
+        stream.read_exact(&mut payload).await.map_err(map_eof)?;
+
+        Ok(Frame {
+            frame_type: head.frame_type,
+            header: head.header,
+            payload,
+        })
+    }
+
+    /// Read everything up to the payload, leaving the payload on the stream
+    ///
+    /// Frames whose length prefix exceeds `max_len` are rejected.
+    pub(crate) async fn read_head<S>(&self, stream: &mut S, max_len: usize) -> Result<FrameHead>
+    where
+        S: AsyncRead + Unpin + ?Sized,
+    {
+        loop {
+            match self.read_head_once(stream, max_len).await {
+                Err(Error::InvalidFrame(_)) if self.resync && self.sync_marker.is_some() => {}
+                result => return result,
+            }
+        }
+    }
+
+    async fn read_head_once<S>(&self, stream: &mut S, max_len: usize) -> Result<FrameHead>
+    where
+        S: AsyncRead + Unpin + ?Sized,
+    {
+        if self.mode == FramingMode::GrpcLengthPrefixed {
+            return self.read_grpc_head(stream, max_len).await;
+        }
+        if self.delimiter().is_some() {
+            return Err(Error::InvalidFrame(
+                "Delimited frames have no head to read".to_string(),
+            ));
+        }
+        if let Some(marker) = &self.sync_marker {
+            let mut window = [0u8; 4];
+            stream.read_exact(&mut window).await.map_err(map_eof)?;
+            if self.resync {
+                // Slide forward one byte at a time until the marker lines up
+                while window != *marker {
+                    window.copy_within(1.., 0);
+                    window[3] = stream.read_u8().await.map_err(map_eof)?;
+                }
+            } else if window != *marker {
+                return Err(Error::InvalidFrame("Missing sync marker".to_string()));
+            }
+        }
+
+        // Read length prefix
+        let mut prefix = [0u8; 8];
+        let prefix = &mut prefix[..self.length_width.bytes()];
+        stream.read_exact(prefix).await.map_err(map_eof)?;
+        if self.length_checksum {
+            let checksum = stream.read_u32().await.map_err(map_eof)?;
+            if crc32fast::hash(prefix) != checksum {
+                return Err(Error::InvalidFrame(
+                    "Length prefix checksum mismatch".to_string(),
+                ));
+            }
+        }
+        let len = self.decode_prefix(prefix);
+
+        // Validate length (max 100MB to prevent DOS)
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|&len| len <= max_len)
+            .ok_or_else(|| Error::InvalidFrame(format!("Message too large: {} bytes", len)))?;
+        let overhead = self.type_len() + self.sequence_len() + self.header_len;
+        if len < overhead {
+            return Err(Error::InvalidFrame(format!(
+                "Frame of {} bytes is shorter than its {} byte header",
+                len, overhead
+            )));
+        }
+
+        // Read type, sequence number and header
+        let frame_type = if self.frame_types {
+            FrameType::try_from(stream.read_u8().await.map_err(map_eof)?)?
+        } else {
+            FrameType::Data
+        };
+        let sequence = if self.sequence_numbers {
+            stream.read_u64().await.map_err(map_eof)?
+        } else {
+            0
+        };
+        let mut header = vec![0u8; self.header_len];
+        stream.read_exact(&mut header).await.map_err(map_eof)?;
+
+        Ok(FrameHead {
+            frame_type,
+            sequence,
+            header,
+            payload_len: len - overhead,
+        })
+    }
+
+    /// Write `len` into `prefix`, whose length is the configured width
+    fn encode_prefix(&self, len: u64, prefix: &mut [u8]) {
+        let width = prefix.len();
+        match self.length_endian {
+            Endian::Big => prefix.copy_from_slice(&len.to_be_bytes()[8 - width..]),
+            Endian::Little => prefix.copy_from_slice(&len.to_le_bytes()[..width]),
+        }
+    }
+
+    fn decode_prefix(&self, prefix: &[u8]) -> u64 {
+        let width = prefix.len();
+        let mut bytes = [0u8; 8];
+        match self.length_endian {
+            Endian::Big => {
+                bytes[8 - width..].copy_from_slice(prefix);
+                u64::from_be_bytes(bytes)
+            }
+            Endian::Little => {
+                bytes[..width].copy_from_slice(prefix);
+                u64::from_le_bytes(bytes)
+            }
+        }
+    }
+
+    /// Bytes each frame adds around its payload
+    ///
+    /// Sync marker, length prefix, checksum, frame type, sequence number and
+    /// metadata header.
+    pub fn overhead(&self) -> usize {
+        if self.mode == FramingMode::GrpcLengthPrefixed {
+            return self.header_len + 4;
+        }
+        if self.delimiter().is_some() {
+            return 1;
+        }
+        let marker = if self.sync_marker.is_some() { 4 } else { 0 };
+        let checksum = if self.length_checksum { 4 } else { 0 };
+        marker
+            + self.length_width.bytes()
+            + checksum
+            + self.type_len()
+            + self.sequence_len()
+            + self.header_len
+    }
+
+    fn type_len(&self) -> usize {
+        usize::from(self.frame_types)
+    }
+
+    fn sequence_len(&self) -> usize {
+        if self.sequence_numbers {
+            8
+        } else {
+            0
+        }
+    }
+
+    /// The byte ending every frame, for [`FramingMode::Delimited`]
+    pub(crate) fn delimiter(&self) -> Option<u8> {
+        match self.mode {
+            FramingMode::Delimited(delimiter) => Some(delimiter),
+            _ => None,
+        }
+    }
+
+    /// Bytes written after the payload
+    fn tail(&self) -> &[u8] {
+        match &self.mode {
+            FramingMode::Delimited(delimiter) => std::slice::from_ref(delimiter),
+            _ => &[],
+        }
+    }
+
+    /// Error for a delimited frame that has not ended within [`MAX_FRAME_SIZE`]
+    pub(crate) fn unterminated(&self) -> Error {
+        Error::InvalidFrame(format!("No delimiter within {} bytes", MAX_FRAME_SIZE))
+    }
+
+    /// Reject a frame this format cannot carry, before any of it is written
+    ///
+    /// Writing validates nothing further, so a frame that passes either goes
+    /// out whole or fails on the stream itself.
+    pub(crate) fn check_frame(
+        &self,
+        frame_type: FrameType,
+        header_len: usize,
+        payload_len: usize,
+    ) -> Result<()> {
+        if self.mode == FramingMode::GrpcLengthPrefixed {
+            self.check_grpc()?;
+            if frame_type != FrameType::Data {
+                return Err(Error::InvalidFrame(
+                    "gRPC framing only carries data frames".to_string(),
+                ));
+            }
+        }
+        if self.delimiter().is_some() {
+            if frame_type != FrameType::Data {
+                return Err(Error::InvalidFrame(
+                    "Delimited framing only carries data frames".to_string(),
+                ));
+            }
+            if payload_len > MAX_FRAME_SIZE {
+                return Err(Error::InvalidFrame(format!(
+                    "Payload of {} bytes exceeds the {} byte frame limit",
+                    payload_len, MAX_FRAME_SIZE
+                )));
+            }
+            return Ok(());
+        }
+        if header_len != self.header_len {
+            return Err(Error::InvalidFrame(format!(
+                "Header is {} bytes, expected {}",
+                header_len, self.header_len
+            )));
+        }
+        if !self.frame_types && frame_type != FrameType::Data {
+            return Err(Error::InvalidFrame(
+                "Control frames require frame types to be enabled".to_string(),
+            ));
+        }
+        u64::try_from(self.type_len() + self.sequence_len() + header_len + payload_len)
+            .ok()
+            .filter(|&len| len <= self.length_width.max_len())
+            .ok_or_else(|| {
+                Error::InvalidFrame(format!(
+                    "Payload of {} bytes does not fit a frame",
+                    payload_len
+                ))
+            })?;
+        Ok(())
+    }
+
+    /// Reject payloads that would end a delimited frame early
+    pub(crate) fn check_delimiter_free(&self, payload: &[u8]) -> Result<()> {
+        match self.delimiter() {
+            Some(delimiter) if payload.contains(&delimiter) => Err(Error::InvalidFrame(
+                "Payload contains the frame delimiter".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reject options the delimited layout cannot carry
+    ///
+    /// Transport builders run this once when they connect or bind; other
+    /// framing modes always pass.
+    pub(crate) fn check_delimited(&self) -> Result<()> {
+        if self.delimiter().is_none() {
+            return Ok(());
+        }
+        if self.header_len != 0
+            || self.frame_types
+            || self.sequence_numbers
+            || self.length_checksum
+            || self.sync_marker.is_some()
+        {
+            return Err(Error::InvalidFrame(
+                "Delimited framing needs no header and no other frame options".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn read_grpc_head<S>(&self, stream: &mut S, max_len: usize) -> Result<FrameHead>
+    where
+        S: AsyncRead + Unpin + ?Sized,
+    {
+        self.check_grpc()?;
+        let flag = stream.read_u8().await.map_err(map_eof)?;
+        let len = stream.read_u32().await.map_err(map_eof)?;
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|&len| len <= max_len)
+            .ok_or_else(|| Error::InvalidFrame(format!("Message too large: {} bytes", len)))?;
+        Ok(FrameHead {
+            frame_type: FrameType::Data,
+            sequence: 0,
+            header: vec![flag],
+            payload_len: len,
+        })
+    }
+
+    /// Reject options the gRPC layout cannot carry
+    fn check_grpc(&self) -> Result<()> {
+        let plain = FrameConfig {
+            header_len: 1,
+            mode: FramingMode::GrpcLengthPrefixed,
+            ..Default::default()
+        };
+        if self.header_len != plain.header_len
+            || self.frame_types
+            || self.sequence_numbers
+            || self.length_endian != plain.length_endian
+            || self.length_width != plain.length_width
+            || self.length_checksum
+            || self.sync_marker.is_some()
+        {
+            return Err(Error::InvalidFrame(
+                "gRPC framing needs a 1-byte header and no other frame options".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Type, sequence number, header and payload length of a frame whose payload is still unread
+pub(crate) struct FrameHead {
+    pub(crate) frame_type: FrameType,
+    /// 0 unless sequence numbers are enabled
+    pub(crate) sequence: u64,
+    pub(crate) header: Vec<u8>,
+    pub(crate) payload_len: usize,
+}
+
+/// Write one frame in the default format and flush the stream
+pub async fn write_frame<S>(stream: &mut S, bytes: &[u8]) -> Result<()>
+where
+    S: AsyncWrite + Unpin + ?Sized,
+{
+    FrameConfig::default()
+        .write(stream, FrameType::Data, &[], bytes)
+        .await
+}
+
+/// Read one frame in the default format
+///
+/// Returns [`Error::ConnectionClosed`] if the stream ends mid-frame and
+/// [`Error::InvalidFrame`] if the prefix exceeds [`MAX_FRAME_SIZE`].
+pub async fn read_frame<S>(stream: &mut S) -> Result<Vec<u8>>
+where
+    S: AsyncRead + Unpin + ?Sized,
+{
+    let frame = FrameConfig::default().read(stream).await?;
+    Ok(frame.payload)
+}
+
+pub(crate) fn map_eof(e: std::io::Error) -> Error {
+    if e.kind() == std::io::ErrorKind::UnexpectedEof {
+        Error::ConnectionClosed
+    } else {
+        e.into()
+    }
+}
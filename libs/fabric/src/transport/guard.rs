@@ -0,0 +1,188 @@
+use std::ops::{Deref, DerefMut};
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::runtime::Handle;
+
+use crate::error::Result;
+use crate::transport::{ConnectionState, FrameType, Transport, TransportKind};
+
+/// Transport wrapper that closes the connection when dropped
+///
+/// `Transport::close` is async while `Drop` is not, so a transport dropped
+/// on an error path (`?`, a panic, a cancelled task) only releases its
+/// socket and the peer may lose frames it has not read yet. `CloseOnDrop`
+/// remembers the runtime it was created on and, if the transport was not
+/// closed explicitly, spawns its `close` there when dropped.
+///
+/// The spawned close runs after the drop returns and its error is
+/// discarded, so prefer calling [`close`](Self::close) on the success path
+/// and keep the guard as the fallback:
+///
+/// ```no_run
+/// # use constellation_fabric::transport::{CloseOnDrop, TcpTransport, Transport};
+/// # async fn run(addr: std::net::SocketAddr) -> constellation_fabric::error::Result<()> {
+/// let mut transport = CloseOnDrop::new(TcpTransport::connect(addr).await?);
+/// transport.send(b"hello").await?; // an error here still closes the connection
+/// transport.close().await
+/// # }
+/// ```
+///
+/// Created outside a Tokio runtime, the guard cannot spawn and dropping it
+/// behaves like dropping the transport.
+pub struct CloseOnDrop<T: Transport + 'static> {
+    inner: Option<T>,
+    handle: Option<Handle>,
+    closed: bool,
+}
+
+impl<T: Transport + 'static> CloseOnDrop<T> {
+    /// Guard `transport`, closing it on the current runtime when dropped
+    pub fn new(transport: T) -> Self {
+        Self {
+            inner: Some(transport),
+            handle: Handle::try_current().ok(),
+            closed: false,
+        }
+    }
+
+    /// Close the transport now and report the result
+    pub async fn close(mut self) -> Result<()> {
+        self.closed = true;
+        self.get_mut().close().await
+    }
+
+    /// Take the transport back without closing it
+    pub fn into_inner(mut self) -> T {
+        self.inner.take().expect("transport is present until drop")
+    }
+
+    /// Get a reference to the wrapped transport
+    pub fn get_ref(&self) -> &T {
+        self.inner
+            .as_ref()
+            .expect("transport is present until drop")
+    }
+
+    /// Get a mutable reference to the wrapped transport
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner
+            .as_mut()
+            .expect("transport is present until drop")
+    }
+}
+
+impl<T: Transport + 'static> Deref for CloseOnDrop<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.get_ref()
+    }
+}
+
+impl<T: Transport + 'static> DerefMut for CloseOnDrop<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.get_mut()
+    }
+}
+
+impl<T: Transport + 'static> Drop for CloseOnDrop<T> {
+    fn drop(&mut self) {
+        let (Some(mut transport), Some(handle)) = (self.inner.take(), self.handle.take()) else {
+            return;
+        };
+        if self.closed || transport.is_closed() {
+            return;
+        }
+        handle.spawn(async move {
+            let _ = transport.close().await;
+        });
+    }
+}
+
+impl<T: Transport + std::fmt::Debug + 'static> std::fmt::Debug for CloseOnDrop<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CloseOnDrop")
+            .field("inner", &self.inner)
+            .field("closed", &self.closed)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Transport + 'static> Transport for CloseOnDrop<T> {
+    async fn send(&mut self, bytes: &[u8]) -> Result<()> {
+        self.get_mut().send(bytes).await
+    }
+
+    async fn send_vectored(&mut self, parts: &[&[u8]]) -> Result<()> {
+        self.get_mut().send_vectored(parts).await
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        self.get_mut().receive().await
+    }
+
+    async fn receive_into(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        self.get_mut().receive_into(buf).await
+    }
+
+    async fn receive_ready(&mut self) -> Result<Vec<Vec<u8>>> {
+        self.get_mut().receive_ready().await
+    }
+
+    async fn readable(&mut self) -> Result<()> {
+        self.get_mut().readable().await
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.get_mut().flush().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.closed = true;
+        self.get_mut().close().await
+    }
+
+    fn is_closed(&self) -> bool {
+        self.get_ref().is_closed()
+    }
+
+    fn state(&self) -> ConnectionState {
+        self.get_ref().state()
+    }
+
+    fn kind(&self) -> TransportKind {
+        self.get_ref().kind()
+    }
+
+    fn describe(&self) -> Option<&dyn std::fmt::Debug> {
+        self.get_ref().describe()
+    }
+
+    fn frame_overhead(&self) -> usize {
+        self.get_ref().frame_overhead()
+    }
+
+    async fn send_control(&mut self, frame_type: FrameType, payload: &[u8]) -> Result<()> {
+        self.get_mut().send_control(frame_type, payload).await
+    }
+
+    async fn receive_frame(&mut self) -> Result<(FrameType, Vec<u8>)> {
+        self.get_mut().receive_frame().await
+    }
+
+    async fn send_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        len: usize,
+    ) -> Result<()> {
+        self.get_mut().send_from_reader(reader, len).await
+    }
+
+    async fn receive_to_writer(
+        &mut self,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<usize> {
+        self.get_mut().receive_to_writer(writer).await
+    }
+}
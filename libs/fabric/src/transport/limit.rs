@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::error::Result;
+use crate::transport::{ConnectionState, FrameType, Transport, TransportKind, TransportListener};
+
+/// What a [`LimitedListener`] does when all connection slots are in use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LimitMode {
+    /// Stop accepting until a slot frees up, leaving new connections in the kernel backlog
+    #[default]
+    Queue,
+    /// Keep accepting, but close new connections immediately while at capacity
+    Reject,
+}
+
+/// Listener wrapper that caps the number of concurrently open connections
+///
+/// Each accepted transport holds a slot until it is dropped, so the cap
+/// covers the full lifetime of the per-connection handler.
+pub struct LimitedListener<L> {
+    inner: L,
+    permits: Arc<Semaphore>,
+    mode: LimitMode,
+}
+
+impl<L: TransportListener> LimitedListener<L> {
+    /// Wrap a listener, allowing at most `max_connections` open at once
+    pub fn new(inner: L, max_connections: usize) -> Self {
+        Self {
+            inner,
+            permits: Arc::new(Semaphore::new(max_connections)),
+            mode: LimitMode::default(),
+        }
+    }
+
+    /// Set the behaviour when at capacity
+    pub fn mode(mut self, mode: LimitMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Number of connection slots currently free
+    pub fn available(&self) -> usize {
+        self.permits.available_permits()
+    }
+
+    /// Get a reference to the wrapped listener
+    pub fn get_ref(&self) -> &L {
+        &self.inner
+    }
+}
+
+#[async_trait::async_trait]
+impl<L: TransportListener> TransportListener for LimitedListener<L> {
+    type Transport = LimitedTransport<L::Transport>;
+
+    async fn accept(&self) -> Result<Self::Transport> {
+        match self.mode {
+            LimitMode::Queue => {
+                let permit = self
+                    .permits
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("semaphore is never closed");
+                let inner = self.inner.accept().await?;
+                Ok(LimitedTransport {
+                    inner,
+                    _permit: permit,
+                })
+            }
+            LimitMode::Reject => loop {
+                let mut inner = self.inner.accept().await?;
+                match self.permits.clone().try_acquire_owned() {
+                    Ok(permit) => {
+                        return Ok(LimitedTransport {
+                            inner,
+                            _permit: permit,
+                        })
+                    }
+                    Err(_) => {
+                        let _ = inner.close().await;
+                    }
+                }
+            },
+        }
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+}
+
+/// Transport accepted through a [`LimitedListener`]
+///
+/// Releases its connection slot when dropped.
+pub struct LimitedTransport<T> {
+    inner: T,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl<T> LimitedTransport<T> {
+    /// Get a reference to the wrapped transport
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Transport> Transport for LimitedTransport<T> {
+    async fn send(&mut self, bytes: &[u8]) -> Result<()> {
+        self.inner.send(bytes).await
+    }
+
+    async fn send_vectored(&mut self, parts: &[&[u8]]) -> Result<()> {
+        self.inner.send_vectored(parts).await
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        self.inner.receive().await
+    }
+
+    async fn receive_into(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        self.inner.receive_into(buf).await
+    }
+
+    async fn receive_ready(&mut self) -> Result<Vec<Vec<u8>>> {
+        self.inner.receive_ready().await
+    }
+
+    async fn readable(&mut self) -> Result<()> {
+        self.inner.readable().await
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.inner.flush().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    fn state(&self) -> ConnectionState {
+        self.inner.state()
+    }
+
+    fn kind(&self) -> TransportKind {
+        self.inner.kind()
+    }
+
+    fn describe(&self) -> Option<&dyn std::fmt::Debug> {
+        self.inner.describe()
+    }
+
+    fn frame_overhead(&self) -> usize {
+        self.inner.frame_overhead()
+    }
+
+    async fn send_control(&mut self, frame_type: FrameType, payload: &[u8]) -> Result<()> {
+        self.inner.send_control(frame_type, payload).await
+    }
+
+    async fn receive_frame(&mut self) -> Result<(FrameType, Vec<u8>)> {
+        self.inner.receive_frame().await
+    }
+
+    async fn send_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        len: usize,
+    ) -> Result<()> {
+        self.inner.send_from_reader(reader, len).await
+    }
+
+    async fn receive_to_writer(
+        &mut self,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<usize> {
+        self.inner.receive_to_writer(writer).await
+    }
+}
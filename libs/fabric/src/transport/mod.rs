@@ -1,14 +1,91 @@
-use crate::error::Result;
+use std::future::Future;
+use std::time::Duration;
 
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::{Error, Result};
+
+#[cfg(unix)]
+pub mod any;
+pub mod budget;
+#[cfg(feature = "test-util")]
+pub mod chaos;
+#[cfg(feature = "tokio-codec")]
+pub mod framed;
+pub mod framing;
+pub mod guard;
+pub mod limit;
+#[cfg(windows)]
+pub mod pipe;
+pub mod rate_limit;
+pub mod socks5;
+pub mod stream;
 pub mod tcp;
+#[cfg(unix)]
 pub mod unix;
 
-pub use self::tcp::{TcpTransport, TcpTransportBuilder, TcpTransportListener};
-pub use self::unix::{UnixTransport, UnixTransportBuilder, UnixTransportListener};
+#[cfg(unix)]
+pub use self::any::{AnyListener, AnyTransport};
+pub use self::budget::MemoryBudget;
+#[cfg(feature = "test-util")]
+pub use self::chaos::ChaosTransport;
+#[cfg(feature = "tokio-codec")]
+pub use self::framed::FabricCodec;
+pub use self::framing::{Endian, FrameType, FramingMode, PrefixWidth};
+pub use self::guard::CloseOnDrop;
+pub use self::limit::{LimitMode, LimitedListener, LimitedTransport};
+#[cfg(windows)]
+pub use self::pipe::{NamedPipeTransport, NamedPipeTransportBuilder, NamedPipeTransportListener};
+pub use self::rate_limit::RateLimited;
+pub use self::socks5::Socks5Auth;
+pub use self::stream::StreamTransport;
+pub use self::tcp::{
+    TcpTransport, TcpTransportBuilder, TcpTransportListener, TcpTransportListenerBuilder,
+};
+#[cfg(unix)]
+pub use self::unix::{
+    ExistingSocket, UnixTransport, UnixTransportBuilder, UnixTransportListener,
+    UnixTransportListenerBuilder,
+};
+
+/// Error message of the default [`Transport::receive_ready`]
+pub(crate) const RECEIVE_READY_UNSUPPORTED: &str =
+    "receive_ready is not supported by this transport";
+
+/// How a transport delimits messages on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportKind {
+    /// Byte stream without intrinsic boundaries (TCP, Unix sockets)
+    ///
+    /// Message boundaries come from the length-prefix format in [`framing`].
+    Stream,
+    /// Message-oriented protocol that preserves boundaries itself (UDP, SCTP)
+    ///
+    /// Each `send` maps to exactly one protocol message and no extra framing
+    /// is added.
+    Message,
+}
+
+/// Where a connection is in its lifecycle, see [`Transport::state`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Open for sending and receiving
+    Connected,
+    /// `close` has started but not finished, e.g. because it was cancelled
+    Closing,
+    /// Closed locally, by the peer, or by a fatal error
+    Closed,
+}
 
 /// Transport trait for sending and receiving raw bytes
 ///
-/// Each transport instance represents a single connection.
+/// Each transport instance represents a single connection. Regardless of
+/// [`TransportKind`], one `send` always corresponds to one `receive` on the
+/// peer; the kind only describes where that guarantee comes from.
+///
+/// Zero-length frames are valid: sending an empty slice delivers an empty
+/// `Vec` to the receiver, so raw transports can carry empty heartbeats.
 #[async_trait::async_trait]
 pub trait Transport: Send + Sync {
     /// Send bytes over the transport
@@ -17,8 +94,262 @@ pub trait Transport: Send + Sync {
     /// Receive bytes from the transport
     async fn receive(&mut self) -> Result<Vec<u8>>;
 
+    /// Send `parts` as the payload of one frame, as if they were concatenated
+    ///
+    /// The peer receives a single payload. Stream transports write the length
+    /// prefix and the parts with vectored writes, so a small header and a
+    /// large body can be sent without copying the body into a joined buffer.
+    /// The default concatenates the parts and calls [`send`](Self::send).
+    async fn send_vectored(&mut self, parts: &[&[u8]]) -> Result<()> {
+        self.send(&parts.concat()).await
+    }
+
+    /// Receive the next payload into `buf`, replacing its contents
+    ///
+    /// Lets callers reuse one allocation across frames. The default replaces
+    /// `buf` with the result of [`receive`](Self::receive); stream transports
+    /// read straight into it.
+    async fn receive_into(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        *buf = self.receive().await?;
+        Ok(())
+    }
+
+    /// Receive every frame that has already arrived, without waiting
+    ///
+    /// Reads whatever the connection can deliver right now and returns the
+    /// complete data frames in it as a batch, or an empty `Vec` if none has
+    /// arrived. A frame that has only partly arrived stays buffered and is
+    /// completed by a later call or `receive`. Pings are answered along the
+    /// way. Payloads are not reserved against a memory budget, since they are
+    /// already in memory.
+    ///
+    /// Only stream transports support this; the default returns an error.
+    async fn receive_ready(&mut self) -> Result<Vec<Vec<u8>>> {
+        Err(Error::Custom(RECEIVE_READY_UNSUPPORTED.to_string()))
+    }
+
+    /// Wait until a whole frame has arrived, without consuming any of it
+    ///
+    /// Cancel-safe: dropping the future at any point leaves every received
+    /// byte to be read by the next receive, which makes it the point to race
+    /// against other futures. Once it returns, the next receive of one frame
+    /// does not wait. Stream transports buffer the frame in memory, and also
+    /// return at end of stream; receive and inactivity timeouts apply to the
+    /// wait, and the frame is reserved against the memory budget once its
+    /// length is known. The default returns at once, for transports that
+    /// cannot wait without reading.
+    async fn readable(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Receive the next payload as a cheaply clonable [`bytes::Bytes`]
+    ///
+    /// Clones share one refcounted allocation, so a frame fanned out to many
+    /// subscribers is not copied per subscriber. The received buffer is
+    /// handed over without copying.
+    #[cfg(feature = "bytes")]
+    async fn receive_shared(&mut self) -> Result<bytes::Bytes> {
+        Ok(self.receive().await?.into())
+    }
+
     /// Close the transport connection
+    ///
+    /// This is the recommended way to end a connection. For stream
+    /// transports it flushes pending writes and shuts down the write half,
+    /// so the peer receives every frame sent before `close` followed by a
+    /// clean end of stream.
+    ///
+    /// Dropping a transport without calling `close` only releases the
+    /// socket. If unread data is still queued on our side, the OS may answer
+    /// with a reset (RST) that discards frames the peer has not read yet.
+    /// Wrap the transport in a [`CloseOnDrop`] to get an orderly close on
+    /// early returns as well.
     async fn close(&mut self) -> Result<()>;
+
+    /// Write out any frames the transport has batched instead of sending
+    ///
+    /// Only needed with write batching enabled, e.g.
+    /// [`TcpTransportBuilder::throughput_mode`]; batched frames also go out
+    /// before the transport waits to receive and on close. The default does
+    /// nothing.
+    async fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether message boundaries are added by framing or intrinsic to the protocol
+    ///
+    /// Defaults to [`TransportKind::Stream`]; datagram-style transports
+    /// should override this to return [`TransportKind::Message`].
+    fn kind(&self) -> TransportKind {
+        TransportKind::Stream
+    }
+
+    /// Connection details for the `Debug` output of types holding this transport
+    ///
+    /// Lets a [`Channel`](crate::channel::Channel), which only sees a
+    /// `dyn Transport`, show where it is connected. TCP, Unix socket and named
+    /// pipe transports return their own `Debug` output; the default returns
+    /// `None`.
+    fn describe(&self) -> Option<&dyn std::fmt::Debug> {
+        None
+    }
+
+    /// Send bytes, aborting with [`Error::Cancelled`] if the token fires first
+    ///
+    /// A send cancelled part-way may leave a partial frame on the wire, so the
+    /// connection should be discarded afterwards.
+    async fn send_cancellable(&mut self, bytes: &[u8], token: &CancellationToken) -> Result<()> {
+        tokio::select! {
+            biased;
+            _ = token.cancelled() => Err(Error::Cancelled),
+            result = self.send(bytes) => result,
+        }
+    }
+
+    /// Receive bytes, aborting with [`Error::Cancelled`] if the token fires first
+    ///
+    /// A receive cancelled part-way may leave a partially read frame, so the
+    /// connection should be discarded afterwards.
+    async fn receive_cancellable(&mut self, token: &CancellationToken) -> Result<Vec<u8>> {
+        tokio::select! {
+            biased;
+            _ = token.cancelled() => Err(Error::Cancelled),
+            result = self.receive() => result,
+        }
+    }
+
+    /// Check whether the connection is known to be closed
+    ///
+    /// This is a best-effort liveness check that does not perform a round-trip.
+    /// It may return `false` for a connection that is about to fail, so callers
+    /// must still handle errors from `send` and `receive`.
+    fn is_closed(&self) -> bool {
+        false
+    }
+
+    /// Where the connection is in its lifecycle
+    ///
+    /// Stream transports move to [`ConnectionState::Closed`] when closed and
+    /// after a fatal send or receive error (I/O errors, end of stream,
+    /// malformed frames), so the state can be shown without probing the
+    /// connection. The default derives it from [`is_closed`](Self::is_closed).
+    fn state(&self) -> ConnectionState {
+        if self.is_closed() {
+            ConnectionState::Closed
+        } else {
+            ConnectionState::Connected
+        }
+    }
+
+    /// Whether the connection can still be used after `error` from one of its operations
+    ///
+    /// Only errors that leave the stream at a frame boundary keep it usable:
+    /// [`Error::Codec`], where the whole frame was read before decoding
+    /// failed or nothing was written because encoding did,
+    /// [`Error::Unauthorized`], and [`Error::SequenceGap`], raised once the
+    /// out-of-order frame has been read whole. Everything else may have
+    /// stopped part-way through a frame, including timeouts and
+    /// cancellations, so carrying on risks reading the rest of a frame as the
+    /// start of the next one. Always `false` once the connection
+    /// [is closed](Self::is_closed).
+    fn is_reusable_after(&self, error: &Error) -> bool {
+        matches!(
+            error,
+            Error::Codec(_) | Error::Unauthorized(_) | Error::SequenceGap { .. }
+        ) && !self.is_closed()
+    }
+
+    /// Bytes of framing added to every data frame on the wire
+    ///
+    /// Length prefix, frame type, sync marker and the like, so a frame
+    /// carrying an `n`-byte payload takes `n + frame_overhead()` bytes.
+    /// Defaults to 0 for transports that add no framing of their own.
+    fn frame_overhead(&self) -> usize {
+        0
+    }
+
+    /// Send a control frame of the given type
+    ///
+    /// Only supported by transports with frame types enabled; the default
+    /// returns an error.
+    async fn send_control(&mut self, frame_type: FrameType, payload: &[u8]) -> Result<()> {
+        let _ = payload;
+        Err(Error::Custom(format!(
+            "{:?} frames are not supported by this transport",
+            frame_type
+        )))
+    }
+
+    /// Receive the next frame of any type, without answering pings
+    ///
+    /// The default wraps [`receive`](Self::receive) and reports every frame as
+    /// [`FrameType::Data`].
+    async fn receive_frame(&mut self) -> Result<(FrameType, Vec<u8>)> {
+        Ok((FrameType::Data, self.receive().await?))
+    }
+
+    /// Send one frame whose `len`-byte payload is read from `reader`
+    ///
+    /// Stream transports copy the payload straight to the socket, so large
+    /// files can be sent without holding them in memory. The default buffers
+    /// the payload and calls [`send`](Self::send). Fails with
+    /// [`Error::InvalidFrame`] if `reader` ends before `len` bytes; a stream
+    /// transport has written part of the frame by then and is closed.
+    async fn send_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        len: usize,
+    ) -> Result<()> {
+        let mut payload = Vec::with_capacity(len);
+        reader.take(len as u64).read_to_end(&mut payload).await?;
+        if payload.len() < len {
+            return Err(framing::source_ended(payload.len() as u64, len));
+        }
+        self.send(&payload).await
+    }
+
+    /// Receive one frame, writing its payload to `writer`, and return its length
+    ///
+    /// Stream transports copy the payload straight from the socket without
+    /// buffering it; since nothing is allocated, the [`MAX_FRAME_SIZE`](framing::MAX_FRAME_SIZE)
+    /// cap does not apply. The default calls [`receive`](Self::receive).
+    async fn receive_to_writer(
+        &mut self,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<usize> {
+        let payload = self.receive().await?;
+        writer.write_all(&payload).await?;
+        writer.flush().await?;
+        Ok(payload.len())
+    }
+}
+
+/// Run `op`, failing with a "<what> timeout exceeded" error if it takes longer than `timeout`
+pub(crate) async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    what: &str,
+    op: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, op)
+            .await
+            .map_err(|_| Error::Custom(format!("{} timeout exceeded", what)))?,
+        None => op.await,
+    }
+}
+
+/// Run `op`, failing with a "<what> deadline exceeded" error if it is still running at `deadline`
+pub(crate) async fn with_deadline<T>(
+    deadline: Option<tokio::time::Instant>,
+    what: &str,
+    op: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, op)
+            .await
+            .map_err(|_| Error::Custom(format!("{} deadline exceeded", what)))?,
+        None => op.await,
+    }
 }
 
 /// Listener trait for accepting incoming connections
@@ -26,6 +357,10 @@ pub trait Transport: Send + Sync {
 /// Provides a unified interface for server-side transport listeners.
 /// Each listener produces its own transport type with transport-specific
 /// peer information accessible via methods on the transport itself.
+///
+/// `accept` takes `&self`, so several tasks can accept on one listener
+/// concurrently by sharing it in an [`Arc`](std::sync::Arc), which is a
+/// listener too.
 #[async_trait::async_trait]
 pub trait TransportListener: Send + Sync {
     /// The transport type this listener produces
@@ -37,3 +372,24 @@ pub trait TransportListener: Send + Sync {
     /// Close the listener gracefully
     async fn close(&mut self) -> Result<()>;
 }
+
+/// A listener shared between accepting tasks
+///
+/// Each clone accepts independently. `close` only closes the listener when
+/// called on the last clone; on the others it is a no-op, and the listener
+/// is released once every clone is gone.
+#[async_trait::async_trait]
+impl<L: TransportListener> TransportListener for std::sync::Arc<L> {
+    type Transport = L::Transport;
+
+    async fn accept(&self) -> Result<Self::Transport> {
+        L::accept(self).await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        match std::sync::Arc::get_mut(self) {
+            Some(listener) => listener.close().await,
+            None => Ok(()),
+        }
+    }
+}
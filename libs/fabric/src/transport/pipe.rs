@@ -0,0 +1,384 @@
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::windows::named_pipe::{
+    ClientOptions, NamedPipeClient, NamedPipeServer, ServerOptions,
+};
+use tokio::sync::Mutex;
+
+use crate::error::{Error, Result};
+use crate::transport::framing::{FrameConfig, FrameType};
+use crate::transport::stream::StreamTransport;
+use crate::transport::{ConnectionState, Transport, TransportListener};
+
+/// `ERROR_PIPE_BUSY`: every server instance is taken, retry shortly
+const ERROR_PIPE_BUSY: i32 = 231;
+
+/// How long to wait before retrying a busy pipe
+const BUSY_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Windows named pipe transport with length-prefix framing
+///
+/// Messages are sent with a 4-byte big-endian length prefix, see [`framing`](crate::transport::framing).
+/// Pipe names take the form `\\.\pipe\name`.
+pub struct NamedPipeTransport {
+    inner: StreamTransport<Pipe>,
+}
+
+impl NamedPipeTransport {
+    /// Connect to a named pipe with no timeouts
+    pub async fn connect(name: impl AsRef<OsStr>) -> Result<Self> {
+        Self::builder().name(name).connect().await
+    }
+
+    /// Connect with a connect timeout
+    pub async fn connect_timeout(name: impl AsRef<OsStr>, timeout: Duration) -> Result<Self> {
+        Self::builder()
+            .name(name)
+            .connect_timeout(timeout)
+            .connect()
+            .await
+    }
+
+    /// Create a builder for configuring the transport
+    pub fn builder() -> NamedPipeTransportBuilder {
+        NamedPipeTransportBuilder::new()
+    }
+
+    /// Create from a connected client end of a pipe
+    pub fn from_client(client: NamedPipeClient) -> Self {
+        Self {
+            inner: StreamTransport::new(Pipe::Client(client)),
+        }
+    }
+
+    /// Create from a connected server end of a pipe
+    pub fn from_server(server: NamedPipeServer) -> Self {
+        Self {
+            inner: StreamTransport::new(Pipe::Server(server)),
+        }
+    }
+
+    /// Get the send timeout
+    pub fn send_timeout(&self) -> Option<Duration> {
+        self.inner.send_timeout()
+    }
+
+    /// Set the send timeout, or `None` to disable it
+    ///
+    /// Takes effect from the next `send`.
+    pub fn set_send_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_send_timeout(timeout);
+    }
+
+    /// Get the receive timeout
+    pub fn receive_timeout(&self) -> Option<Duration> {
+        self.inner.receive_timeout()
+    }
+
+    /// Set the receive timeout, or `None` to disable it
+    ///
+    /// Takes effect from the next `receive`.
+    pub fn set_receive_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_receive_timeout(timeout);
+    }
+}
+
+impl std::fmt::Debug for NamedPipeTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let end = match self.inner.get_ref() {
+            Pipe::Client(_) => "client",
+            Pipe::Server(_) => "server",
+        };
+        f.debug_struct("NamedPipeTransport")
+            .field("end", &end)
+            .field("send_timeout", &self.send_timeout())
+            .field("receive_timeout", &self.receive_timeout())
+            .field("state", &self.inner.state())
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for NamedPipeTransport {
+    async fn send(&mut self, bytes: &[u8]) -> Result<()> {
+        self.inner.send(bytes).await
+    }
+
+    async fn send_vectored(&mut self, parts: &[&[u8]]) -> Result<()> {
+        self.inner.send_vectored(parts).await
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        self.inner.receive().await
+    }
+
+    async fn receive_into(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        self.inner.receive_into(buf).await
+    }
+
+    async fn receive_ready(&mut self) -> Result<Vec<Vec<u8>>> {
+        self.inner.receive_ready().await
+    }
+
+    async fn readable(&mut self) -> Result<()> {
+        self.inner.readable().await
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.inner.flush().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    fn state(&self) -> ConnectionState {
+        self.inner.state()
+    }
+
+    fn describe(&self) -> Option<&dyn std::fmt::Debug> {
+        Some(self)
+    }
+
+    fn frame_overhead(&self) -> usize {
+        self.inner.frame_overhead()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    async fn send_control(&mut self, frame_type: FrameType, payload: &[u8]) -> Result<()> {
+        self.inner.send_control(frame_type, payload).await
+    }
+
+    async fn receive_frame(&mut self) -> Result<(FrameType, Vec<u8>)> {
+        self.inner.receive_frame().await
+    }
+
+    async fn send_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        len: usize,
+    ) -> Result<()> {
+        self.inner.send_from_reader(reader, len).await
+    }
+
+    async fn receive_to_writer(
+        &mut self,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<usize> {
+        self.inner.receive_to_writer(writer).await
+    }
+}
+
+/// Named pipe listener for accepting incoming connections
+///
+/// Keeps one unconnected pipe instance waiting at all times; each accepted
+/// client takes that instance and a fresh one is created for the next.
+pub struct NamedPipeTransportListener {
+    name: OsString,
+    next: Mutex<NamedPipeServer>,
+}
+
+impl NamedPipeTransportListener {
+    /// Create the first instance of a named pipe
+    ///
+    /// Fails if a pipe with this name already exists.
+    pub async fn bind(name: impl AsRef<OsStr>) -> Result<Self> {
+        let name = name.as_ref().to_os_string();
+        let server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&name)?;
+        Ok(Self {
+            name,
+            next: Mutex::new(server),
+        })
+    }
+
+    /// Accept an incoming connection
+    pub async fn accept(&self) -> Result<NamedPipeTransport> {
+        let mut next = self.next.lock().await;
+        next.connect().await?;
+        let fresh = ServerOptions::new().create(&self.name)?;
+        let connected = std::mem::replace(&mut *next, fresh);
+        Ok(NamedPipeTransport::from_server(connected))
+    }
+
+    /// Get the pipe name this listener serves
+    pub fn name(&self) -> &OsStr {
+        &self.name
+    }
+
+    /// Close the listener
+    ///
+    /// The waiting pipe instance is released on drop. This is a no-op for
+    /// compatibility.
+    pub async fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for NamedPipeTransportListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NamedPipeTransportListener")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl TransportListener for NamedPipeTransportListener {
+    type Transport = NamedPipeTransport;
+
+    async fn accept(&self) -> Result<Self::Transport> {
+        NamedPipeTransportListener::accept(self).await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.close().await
+    }
+}
+
+/// Builder for configuring named pipe transport
+#[derive(Default)]
+pub struct NamedPipeTransportBuilder {
+    name: Option<OsString>,
+    connect_timeout: Option<Duration>,
+    send_timeout: Option<Duration>,
+    receive_timeout: Option<Duration>,
+    frame: FrameConfig,
+}
+
+impl NamedPipeTransportBuilder {
+    /// Create a new builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the pipe name to connect to
+    pub fn name(mut self, name: impl AsRef<OsStr>) -> Self {
+        self.name = Some(name.as_ref().to_os_string());
+        self
+    }
+
+    /// Set the connection timeout
+    ///
+    /// Covers waiting for a free pipe instance while the server is busy.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the send timeout
+    pub fn send_timeout(mut self, timeout: Duration) -> Self {
+        self.send_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the receive timeout
+    pub fn receive_timeout(mut self, timeout: Duration) -> Self {
+        self.receive_timeout = Some(timeout);
+        self
+    }
+
+    /// Prefix every frame with a type byte so control frames can be sent
+    ///
+    /// Required for [`Channel::ping`](crate::Channel::ping); both peers must agree.
+    pub fn frame_types(mut self, enabled: bool) -> Self {
+        self.frame.frame_types = enabled;
+        self
+    }
+
+    /// Number every frame and fail `receive` when one is missing or out of order
+    ///
+    /// The receive fails with [`Error::SequenceGap`]. See
+    /// [`FrameConfig::sequence_numbers`]; both peers must agree.
+    pub fn sequence_numbers(mut self, enabled: bool) -> Self {
+        self.frame.sequence_numbers = enabled;
+        self
+    }
+
+    /// Connect with the configured settings
+    pub async fn connect(self) -> Result<NamedPipeTransport> {
+        let name = self
+            .name
+            .ok_or_else(|| Error::Custom("Pipe name not set".to_string()))?;
+
+        let connect_op = async {
+            loop {
+                match ClientOptions::new().open(&name) {
+                    Ok(client) => return Ok::<NamedPipeClient, Error>(client),
+                    Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) => {
+                        tokio::time::sleep(BUSY_RETRY_DELAY).await
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        };
+
+        let client = if let Some(timeout) = self.connect_timeout {
+            tokio::time::timeout(timeout, connect_op)
+                .await
+                .map_err(|_| Error::Custom("Connect timeout exceeded".to_string()))??
+        } else {
+            connect_op.await?
+        };
+
+        let mut inner = StreamTransport::with_config(Pipe::Client(client), self.frame);
+        inner.set_send_timeout(self.send_timeout);
+        inner.set_receive_timeout(self.receive_timeout);
+        Ok(NamedPipeTransport { inner })
+    }
+}
+
+/// Either end of a connected named pipe
+enum Pipe {
+    Client(NamedPipeClient),
+    Server(NamedPipeServer),
+}
+
+impl AsyncRead for Pipe {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Pipe::Client(pipe) => Pin::new(pipe).poll_read(cx, buf),
+            Pipe::Server(pipe) => Pin::new(pipe).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Pipe {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Pipe::Client(pipe) => Pin::new(pipe).poll_write(cx, buf),
+            Pipe::Server(pipe) => Pin::new(pipe).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Pipe::Client(pipe) => Pin::new(pipe).poll_flush(cx),
+            Pipe::Server(pipe) => Pin::new(pipe).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Pipe::Client(pipe) => Pin::new(pipe).poll_shutdown(cx),
+            Pipe::Server(pipe) => Pin::new(pipe).poll_shutdown(cx),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::Instant;
+
+use crate::error::{Error, Result};
+use crate::transport::{ConnectionState, FrameType, Transport, TransportKind};
+
+/// Transport wrapper that limits the rate of outgoing messages
+///
+/// Uses a token bucket: each `send` consumes one token, tokens refill at
+/// `rate` per second, and up to `burst` tokens can accumulate while idle.
+/// A token is only consumed once it is available, so dropping a pending
+/// `send` future never loses capacity.
+pub struct RateLimited<T> {
+    inner: T,
+    bucket: TokenBucket,
+    send_timeout: Option<Duration>,
+}
+
+impl<T: Transport> RateLimited<T> {
+    /// Wrap a transport, allowing `rate` messages per second with bursts of up to `burst`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is not a positive number.
+    pub fn new(inner: T, rate: f64, burst: u32) -> Self {
+        assert!(rate > 0.0, "rate must be positive");
+        Self {
+            inner,
+            bucket: TokenBucket::new(rate, burst),
+            send_timeout: None,
+        }
+    }
+
+    /// Fail a send immediately if waiting for a token would exceed this timeout
+    ///
+    /// Only the wait for a token is bounded. The write that follows is up to
+    /// the inner transport's own send timeout; to bound both together, use
+    /// [`Channel::with_send_deadline`](crate::channel::Channel::with_send_deadline).
+    pub fn with_send_timeout(mut self, timeout: Duration) -> Self {
+        self.send_timeout = Some(timeout);
+        self
+    }
+
+    /// Get a reference to the wrapped transport
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Unwrap the rate limiter, returning the inner transport
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    async fn acquire(&mut self) -> Result<()> {
+        let deadline = self.send_timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            let now = Instant::now();
+            let wait = self.bucket.time_until_available(now);
+            if wait.is_zero() {
+                self.bucket.take();
+                return Ok(());
+            }
+
+            if let Some(deadline) = deadline {
+                if now + wait > deadline {
+                    return Err(Error::Custom("Send timeout exceeded".to_string()));
+                }
+            }
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Transport> Transport for RateLimited<T> {
+    async fn send(&mut self, bytes: &[u8]) -> Result<()> {
+        self.acquire().await?;
+        self.inner.send(bytes).await
+    }
+
+    async fn send_vectored(&mut self, parts: &[&[u8]]) -> Result<()> {
+        self.acquire().await?;
+        self.inner.send_vectored(parts).await
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        self.inner.receive().await
+    }
+
+    async fn receive_into(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        self.inner.receive_into(buf).await
+    }
+
+    async fn receive_ready(&mut self) -> Result<Vec<Vec<u8>>> {
+        self.inner.receive_ready().await
+    }
+
+    async fn readable(&mut self) -> Result<()> {
+        self.inner.readable().await
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.inner.flush().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    fn state(&self) -> ConnectionState {
+        self.inner.state()
+    }
+
+    fn kind(&self) -> TransportKind {
+        self.inner.kind()
+    }
+
+    fn describe(&self) -> Option<&dyn std::fmt::Debug> {
+        self.inner.describe()
+    }
+
+    fn frame_overhead(&self) -> usize {
+        self.inner.frame_overhead()
+    }
+
+    async fn send_control(&mut self, frame_type: FrameType, payload: &[u8]) -> Result<()> {
+        self.inner.send_control(frame_type, payload).await
+    }
+
+    async fn receive_frame(&mut self) -> Result<(FrameType, Vec<u8>)> {
+        self.inner.receive_frame().await
+    }
+
+    async fn send_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        len: usize,
+    ) -> Result<()> {
+        self.acquire().await?;
+        self.inner.send_from_reader(reader, len).await
+    }
+
+    async fn receive_to_writer(
+        &mut self,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<usize> {
+        self.inner.receive_to_writer(writer).await
+    }
+}
+
+struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate,
+            burst,
+            tokens: burst,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last_refill = now;
+    }
+
+    fn time_until_available(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.rate)
+        }
+    }
+
+    fn take(&mut self) {
+        self.tokens -= 1.0;
+    }
+}
@@ -0,0 +1,156 @@
+use std::net::SocketAddr;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::error::{Error, Result};
+
+const VERSION: u8 = 0x05;
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_USER_PASS: u8 = 0x02;
+const METHOD_NONE_ACCEPTABLE: u8 = 0xff;
+const USER_PASS_VERSION: u8 = 0x01;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+/// Username/password credentials for a SOCKS5 proxy (RFC 1929)
+#[derive(Clone)]
+pub struct Socks5Auth {
+    username: String,
+    password: String,
+}
+
+impl Socks5Auth {
+    /// Create credentials from a username and password
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Socks5Auth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Socks5Auth")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Perform a SOCKS5 CONNECT to `target` over an established proxy stream
+pub(crate) async fn handshake(
+    stream: &mut TcpStream,
+    target: SocketAddr,
+    auth: Option<&Socks5Auth>,
+) -> Result<()> {
+    // Method negotiation
+    let offered = if auth.is_some() {
+        METHOD_USER_PASS
+    } else {
+        METHOD_NO_AUTH
+    };
+    stream.write_all(&[VERSION, 1, offered]).await?;
+
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != VERSION {
+        return Err(Error::Proxy(format!(
+            "unexpected SOCKS version {}",
+            reply[0]
+        )));
+    }
+
+    match (reply[1], auth) {
+        (METHOD_NO_AUTH, _) => {}
+        (METHOD_USER_PASS, Some(auth)) => authenticate(stream, auth).await?,
+        (METHOD_NONE_ACCEPTABLE, _) => {
+            return Err(Error::Proxy("no acceptable auth method".to_string()))
+        }
+        (method, _) => {
+            return Err(Error::Proxy(format!(
+                "proxy selected unsupported auth method {}",
+                method
+            )))
+        }
+    }
+
+    // Connect request
+    let mut request = vec![VERSION, CMD_CONNECT, 0x00];
+    match target {
+        SocketAddr::V4(addr) => {
+            request.push(ATYP_IPV4);
+            request.extend_from_slice(&addr.ip().octets());
+        }
+        SocketAddr::V6(addr) => {
+            request.push(ATYP_IPV6);
+            request.extend_from_slice(&addr.ip().octets());
+        }
+    }
+    request.extend_from_slice(&target.port().to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    if header[1] != 0x00 {
+        return Err(Error::Proxy(format!(
+            "connect failed: {}",
+            reply_message(header[1])
+        )));
+    }
+
+    // Discard the bound address
+    let addr_len = match header[3] {
+        ATYP_IPV4 => 4,
+        ATYP_IPV6 => 16,
+        ATYP_DOMAIN => stream.read_u8().await? as usize,
+        other => return Err(Error::Proxy(format!("unknown address type {}", other))),
+    };
+    let mut bound = vec![0u8; addr_len + 2];
+    stream.read_exact(&mut bound).await?;
+
+    Ok(())
+}
+
+async fn authenticate(stream: &mut TcpStream, auth: &Socks5Auth) -> Result<()> {
+    let username = auth.username.as_bytes();
+    let password = auth.password.as_bytes();
+    if username.len() > 255 || password.len() > 255 {
+        return Err(Error::Proxy(
+            "credentials longer than 255 bytes".to_string(),
+        ));
+    }
+
+    let mut request = Vec::with_capacity(3 + username.len() + password.len());
+    request.push(USER_PASS_VERSION);
+    request.push(username.len() as u8);
+    request.extend_from_slice(username);
+    request.push(password.len() as u8);
+    request.extend_from_slice(password);
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0x00 {
+        return Err(Error::Proxy("authentication rejected".to_string()));
+    }
+
+    Ok(())
+}
+
+fn reply_message(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
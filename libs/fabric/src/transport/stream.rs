@@ -0,0 +1,1136 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::FutureExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::time::{Instant, Sleep};
+
+use crate::error::{Error, Result};
+use crate::transport::budget::{MemoryBudget, Reservation};
+use crate::transport::framing::{
+    map_eof, Frame, FrameConfig, FrameHead, FrameType, MAX_FRAME_SIZE,
+};
+use crate::transport::{with_timeout, ConnectionState, Transport};
+
+/// Framed transport over any tokio byte stream
+///
+/// Implements the length-prefix format from
+/// [`framing`](crate::transport::framing) together with send/receive
+/// timeouts. [`TcpTransport`](crate::transport::TcpTransport) and
+/// [`UnixTransport`](crate::transport::UnixTransport) are built on it, and it
+/// can wrap other streams (e.g. TLS) to give them the same wire format.
+pub struct StreamTransport<S> {
+    stream: S,
+    send_timeout: Option<Duration>,
+    receive_timeout: Option<Duration>,
+    inactivity_timeout: Option<Duration>,
+    min_receive_rate: Option<u64>,
+    memory_budget: Option<Arc<MemoryBudget>>,
+    max_frames: Option<usize>,
+    frames_received: usize,
+    frame: FrameConfig,
+    send_sequence: u64,
+    receive_sequence: u64,
+    read_ahead: Vec<u8>,
+    buffered_reservation: Option<Reservation>,
+    write_batch: Option<usize>,
+    write_buf: Vec<u8>,
+    receive_buf: Vec<u8>,
+    receive_ring: Option<usize>,
+    oversized_receives: u64,
+    deferred_error: Option<Error>,
+    state: ConnectionState,
+}
+
+/// Most bytes [`receive_ready`](Transport::receive_ready) reads from the stream per call
+const READY_READ_LIMIT: usize = 1024 * 1024;
+
+impl<S> StreamTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + Sync,
+{
+    /// Wrap a stream using the default frame format and no timeouts
+    pub fn new(stream: S) -> Self {
+        Self::with_config(stream, FrameConfig::default())
+    }
+
+    /// Wrap a stream using the given frame format
+    pub fn with_config(stream: S, frame: FrameConfig) -> Self {
+        Self {
+            stream,
+            send_timeout: None,
+            receive_timeout: None,
+            inactivity_timeout: None,
+            min_receive_rate: None,
+            memory_budget: None,
+            max_frames: None,
+            frames_received: 0,
+            frame,
+            send_sequence: 0,
+            receive_sequence: 0,
+            read_ahead: Vec::new(),
+            buffered_reservation: None,
+            write_batch: None,
+            write_buf: Vec::new(),
+            receive_buf: Vec::new(),
+            receive_ring: None,
+            oversized_receives: 0,
+            deferred_error: None,
+            state: ConnectionState::Connected,
+        }
+    }
+
+    /// Get a reference to the underlying stream
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Get a mutable reference to the underlying stream
+    ///
+    /// Reading or writing the stream directly bypasses framing, and any
+    /// bytes already buffered by [`receive_ready`](Transport::receive_ready).
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Unwrap the underlying stream
+    ///
+    /// Batched frames are written out first, so none are lost. Fails if
+    /// [`receive_ready`](Transport::receive_ready) or
+    /// [`readable`](Transport::readable) has buffered bytes, which the raw
+    /// stream would no longer return.
+    pub async fn into_inner(mut self) -> Result<S> {
+        if !self.read_ahead.is_empty() {
+            return Err(Error::Custom(
+                "Cannot unwrap with unread bytes buffered".to_string(),
+            ));
+        }
+        let result = self.flush_writes().await;
+        self.track(result)?;
+        Ok(self.stream)
+    }
+
+    /// Swap the stream for one built on top of it, keeping all settings
+    ///
+    /// For STARTTLS-style protocols that negotiate encryption in plaintext
+    /// and then run a TLS handshake on the same socket: `upgrade` receives
+    /// the raw stream and returns the new one, e.g.
+    /// `transport.upgrade(|tcp| connector.connect(server_name, tcp))` on the
+    /// client and `transport.upgrade(|tcp| acceptor.accept(tcp))` on the
+    /// server with `tokio-rustls`. Frame format, timeouts, memory budget and
+    /// frame limit carry over to the returned transport.
+    ///
+    /// Batched frames are written out first. Fails without calling `upgrade`
+    /// if [`receive_ready`](Transport::receive_ready) has buffered bytes,
+    /// since they belong to the upgraded stream but were read as plaintext.
+    pub async fn upgrade<T, F, Fut>(mut self, upgrade: F) -> Result<StreamTransport<T>>
+    where
+        F: FnOnce(S) -> Fut,
+        Fut: Future<Output = io::Result<T>>,
+    {
+        if !self.read_ahead.is_empty() {
+            return Err(Error::Custom(
+                "Cannot upgrade with unread bytes buffered".to_string(),
+            ));
+        }
+        let result = self.flush_writes().await;
+        self.track(result)?;
+
+        let stream = upgrade(self.stream).await?;
+        Ok(StreamTransport {
+            stream,
+            send_timeout: self.send_timeout,
+            receive_timeout: self.receive_timeout,
+            inactivity_timeout: self.inactivity_timeout,
+            min_receive_rate: self.min_receive_rate,
+            memory_budget: self.memory_budget,
+            max_frames: self.max_frames,
+            frames_received: self.frames_received,
+            frame: self.frame,
+            send_sequence: self.send_sequence,
+            receive_sequence: self.receive_sequence,
+            read_ahead: Vec::new(),
+            buffered_reservation: None,
+            write_batch: self.write_batch,
+            write_buf: Vec::new(),
+            receive_buf: self.receive_buf,
+            receive_ring: self.receive_ring,
+            oversized_receives: self.oversized_receives,
+            deferred_error: self.deferred_error,
+            state: self.state,
+        })
+    }
+
+    /// Get the frame format
+    pub fn frame_config(&self) -> &FrameConfig {
+        &self.frame
+    }
+
+    /// Get the send timeout
+    pub fn send_timeout(&self) -> Option<Duration> {
+        self.send_timeout
+    }
+
+    /// Set the send timeout, or `None` to disable it
+    ///
+    /// Takes effect from the next `send`.
+    pub fn set_send_timeout(&mut self, timeout: Option<Duration>) {
+        self.send_timeout = timeout;
+    }
+
+    /// Get the receive timeout
+    pub fn receive_timeout(&self) -> Option<Duration> {
+        self.receive_timeout
+    }
+
+    /// Set the receive timeout, or `None` to disable it
+    ///
+    /// Takes effect from the next `receive`.
+    pub fn set_receive_timeout(&mut self, timeout: Option<Duration>) {
+        self.receive_timeout = timeout;
+    }
+
+    /// Get the receive inactivity timeout
+    pub fn receive_inactivity_timeout(&self) -> Option<Duration> {
+        self.inactivity_timeout
+    }
+
+    /// Set the receive inactivity timeout, or `None` to disable it
+    ///
+    /// Unlike the receive timeout, which bounds the whole frame, this fails a
+    /// receive only when no bytes arrive for `timeout`, so slow but steady
+    /// transfers of large frames succeed. The wait for the first byte of a
+    /// frame counts as inactivity too. Both timeouts can be combined.
+    pub fn set_receive_inactivity_timeout(&mut self, timeout: Option<Duration>) {
+        self.inactivity_timeout = timeout;
+    }
+
+    /// Get the minimum receive rate in bytes per second
+    pub fn min_receive_rate(&self) -> Option<u64> {
+        self.min_receive_rate
+    }
+
+    /// Fail receives whose payload arrives slower than `bytes_per_sec`, or `None` to disable
+    ///
+    /// Guards against slowloris-style peers that send a length prefix and
+    /// then trickle the payload to tie up a handler. Once a frame's payload
+    /// starts, its average rate is checked every second, and a receive that
+    /// falls below the minimum fails with [`Error::ReceiveTooSlow`] and
+    /// leaves the connection closed. Waiting for the next frame, or for room
+    /// in the [memory budget](Self::set_memory_budget), does not count, so
+    /// idle connections are unaffected. Takes effect from the next `receive`.
+    pub fn set_min_receive_rate(&mut self, bytes_per_sec: Option<u64>) {
+        self.min_receive_rate = bytes_per_sec;
+    }
+
+    /// Reserve each received payload against a shared memory budget
+    ///
+    /// Takes effect from the next `receive`; see [`MemoryBudget`].
+    pub fn set_memory_budget(&mut self, budget: Option<Arc<MemoryBudget>>) {
+        self.memory_budget = budget;
+    }
+
+    /// Limit how many data frames this connection may receive
+    ///
+    /// The data frame over the limit fails with "frame limit exceeded" and
+    /// shuts the connection down. Control frames do not count. `None`
+    /// removes the limit.
+    pub fn set_max_frames(&mut self, max: Option<usize>) {
+        self.max_frames = max;
+    }
+
+    /// Mark the connection closed if `result` failed in a way it cannot recover from
+    ///
+    /// I/O errors, end of stream, malformed frames and payloads aborted for
+    /// being too slow leave the stream at an unknown position, so nothing
+    /// more can be read from it. Timeouts and
+    /// codec errors are left to the caller. Frames refused by
+    /// [`check_outgoing`](Self::check_outgoing) never reach the stream, so
+    /// senders check them before tracking the write.
+    pub(crate) fn track<T>(&mut self, result: Result<T>) -> Result<T> {
+        if let Err(
+            Error::Io(_) | Error::ConnectionClosed | Error::InvalidFrame(_) | Error::ReceiveTooSlow,
+        ) = &result
+        {
+            self.state = ConnectionState::Closed;
+        }
+        result
+    }
+
+    /// Batch sent data frames into a buffer of `size` bytes instead of writing each one
+    ///
+    /// Without batching every `send` is written and flushed on its own. With
+    /// it, data frames are appended to a buffer that is written once it holds
+    /// `size` bytes, on [`flush`](Transport::flush), before waiting to
+    /// receive, and on close, trading latency for fewer syscalls. Frames of
+    /// at least `size` bytes and control frames are written straight away,
+    /// after anything already batched. `None` turns batching off; frames
+    /// already batched go out with the next write.
+    pub fn set_write_batch(&mut self, size: Option<usize>) {
+        self.write_batch = size;
+    }
+
+    /// Give [`receive_borrowed`](Self::receive_borrowed) a fixed buffer of `capacity` bytes
+    ///
+    /// The buffer is allocated here, once, so receiving frames that fit in
+    /// it never allocates. A larger frame is still received into a buffer
+    /// grown to fit, counted in
+    /// [`oversized_receives`](Self::oversized_receives), and the buffer is
+    /// shrunk back to `capacity` on the next `receive_borrowed`. `None`
+    /// lets the buffer keep whatever size the largest frame needed.
+    pub fn set_receive_ring(&mut self, capacity: Option<usize>) {
+        self.receive_ring = capacity;
+        self.receive_buf = Vec::with_capacity(capacity.unwrap_or(0));
+    }
+
+    /// Number of frames too large for the receive ring, which had to allocate
+    pub fn oversized_receives(&self) -> u64 {
+        self.oversized_receives
+    }
+
+    /// Receive the next data frame into the internal receive buffer and borrow its payload
+    ///
+    /// Unlike [`receive`](Transport::receive) this does not allocate a
+    /// `Vec` per message: with a [receive ring](Self::set_receive_ring)
+    /// sized for the largest expected frame and no timeouts or rate limit
+    /// (which arm timers), a steady stream of receives makes no heap
+    /// allocations at all. The returned slice borrows the transport, so it
+    /// must be dropped, or copied out, before the transport is used again;
+    /// the next call overwrites it.
+    pub async fn receive_borrowed(&mut self) -> Result<&[u8]> {
+        let mut buf = std::mem::take(&mut self.receive_buf);
+        if let Some(capacity) = self.receive_ring {
+            if buf.capacity() > capacity {
+                buf.clear();
+                buf.shrink_to(capacity);
+            }
+        }
+        let result = self.receive_data_into(&mut buf).await;
+        if self
+            .receive_ring
+            .is_some_and(|capacity| buf.len() > capacity)
+        {
+            self.oversized_receives += 1;
+        }
+        self.receive_buf = buf;
+        self.track(result)?;
+        Ok(&self.receive_buf)
+    }
+
+    /// Whether bytes read by `receive_ready` are waiting to be parsed
+    #[cfg(unix)]
+    pub(crate) fn has_read_ahead(&self) -> bool {
+        !self.read_ahead.is_empty()
+    }
+
+    /// Hand over bytes read from the stream by other means, to be parsed as if read here
+    pub(crate) fn push_read_ahead(&mut self, bytes: &[u8]) {
+        self.read_ahead.extend_from_slice(bytes);
+    }
+
+    /// Send a payload with an out-of-band metadata header
+    ///
+    /// `N` must match the configured header length.
+    pub async fn send_with_header<const N: usize>(
+        &mut self,
+        header: &[u8; N],
+        payload: &[u8],
+    ) -> Result<()> {
+        self.check_outgoing(FrameType::Data, N, &[payload])?;
+        let result = self
+            .write_frame_with_header(FrameType::Data, header, payload)
+            .await;
+        self.track(result)
+    }
+
+    /// Receive a payload together with its metadata header
+    ///
+    /// `N` must match the configured header length.
+    pub async fn receive_with_header<const N: usize>(&mut self) -> Result<([u8; N], Vec<u8>)> {
+        if N != self.frame.header_len {
+            return Err(Error::InvalidFrame(format!(
+                "Header is {} bytes, expected {}",
+                N, self.frame.header_len
+            )));
+        }
+
+        let result = self.receive_data_frame().await;
+        let frame = self.track(result)?;
+        let header = frame.header.try_into().expect("header length was checked");
+        Ok((header, frame.payload))
+    }
+
+    async fn write_frame(&mut self, frame_type: FrameType, payload: &[u8]) -> Result<()> {
+        let header = vec![0u8; self.frame.header_len];
+        self.write_frame_with_header(frame_type, &header, payload)
+            .await
+    }
+
+    async fn write_frame_with_header(
+        &mut self,
+        frame_type: FrameType,
+        header: &[u8],
+        payload: &[u8],
+    ) -> Result<()> {
+        let sequence = self.send_sequence;
+        if let Some(batch) = self.write_batch {
+            if frame_type == FrameType::Data && payload.len() < batch {
+                // Writing into a Vec never waits
+                let result = self
+                    .frame
+                    .write_numbered(&mut self.write_buf, frame_type, header, sequence, payload)
+                    .await;
+                self.number_sent(result)?;
+                if self.write_buf.len() >= batch {
+                    self.flush_writes().await?;
+                }
+                return Ok(());
+            }
+        }
+
+        self.flush_writes().await?;
+        let send_op =
+            self.frame
+                .write_numbered(&mut self.stream, frame_type, header, sequence, payload);
+        let result = with_timeout(self.send_timeout, "Send", send_op).await;
+        self.number_sent(result)
+    }
+
+    async fn write_vectored(&mut self, parts: &[&[u8]]) -> Result<()> {
+        let header = vec![0u8; self.frame.header_len];
+        let len: usize = parts.iter().map(|part| part.len()).sum();
+        if self.write_batch.is_some_and(|batch| len < batch) {
+            // Batching copies anyway, so join the parts into the batch
+            return self
+                .write_frame_with_header(FrameType::Data, &header, &parts.concat())
+                .await;
+        }
+
+        self.flush_writes().await?;
+        let sequence = self.send_sequence;
+        let send_op = self
+            .frame
+            .write_vectored(&mut self.stream, &header, sequence, parts);
+        let result = with_timeout(self.send_timeout, "Send", send_op).await;
+        self.number_sent(result)
+    }
+
+    /// Reject a frame the configured format cannot carry
+    ///
+    /// Nothing has been written at this point, so the connection stays usable.
+    pub(crate) fn check_outgoing(
+        &self,
+        frame_type: FrameType,
+        header_len: usize,
+        parts: &[&[u8]],
+    ) -> Result<()> {
+        let len = parts.iter().map(|part| part.len()).sum();
+        self.frame.check_frame(frame_type, header_len, len)?;
+        parts
+            .iter()
+            .try_for_each(|part| self.frame.check_delimiter_free(part))
+    }
+
+    /// Sequence number the next frame sent carries
+    pub(crate) fn send_sequence(&self) -> u64 {
+        self.send_sequence
+    }
+
+    /// Move on to the next sequence number once the frame numbered `send_sequence` is written
+    ///
+    /// A frame that failed or was never written leaves its number to the next one.
+    pub(crate) fn number_sent<T>(&mut self, result: Result<T>) -> Result<T> {
+        if result.is_ok() {
+            self.send_sequence = self.send_sequence.wrapping_add(1);
+        }
+        result
+    }
+
+    /// Fail if a frame just consumed is not numbered the one after the last
+    ///
+    /// Numbering carries on from the frame received either way, so one lost
+    /// frame is reported once rather than on every later receive.
+    pub(crate) fn check_sequence(&mut self, head: &FrameHead) -> Result<()> {
+        if !self.frame.sequence_numbers {
+            return Ok(());
+        }
+        let expected = self.receive_sequence;
+        self.receive_sequence = head.sequence.wrapping_add(1);
+        if head.sequence != expected {
+            return Err(Error::SequenceGap {
+                expected,
+                got: head.sequence,
+            });
+        }
+        Ok(())
+    }
+
+    /// Write out the batched frames, if any
+    pub(crate) async fn flush_writes(&mut self) -> Result<()> {
+        if self.write_buf.is_empty() {
+            return Ok(());
+        }
+        let stream = &mut self.stream;
+        let batch = &self.write_buf;
+        let send_op = async move {
+            stream.write_all(batch).await?;
+            stream.flush().await?;
+            Ok(())
+        };
+        let result = with_timeout(self.send_timeout, "Send", send_op).await;
+        self.write_buf.clear();
+        result
+    }
+
+    async fn read_frame(&mut self) -> Result<Frame> {
+        let mut payload = Vec::new();
+        let head = self.read_frame_into(&mut payload).await?;
+        Ok(Frame {
+            frame_type: head.frame_type,
+            header: head.header,
+            payload,
+        })
+    }
+
+    /// Read one frame, replacing the contents of `payload` with its payload
+    async fn read_frame_into(&mut self, payload: &mut Vec<u8>) -> Result<FrameHead> {
+        self.take_deferred_error()?;
+        self.flush_writes().await?;
+        if self.frame.delimiter().is_some() {
+            return self.read_delimited_into(payload).await;
+        }
+        let mut reader = InactivityReader::new(
+            &mut self.stream,
+            &mut self.read_ahead,
+            self.inactivity_timeout,
+        );
+        let frame = &self.frame;
+        let budget = self.memory_budget.as_ref();
+        // Reserved by `buffer_frame` if it buffered this frame
+        let buffered = self.buffered_reservation.take();
+        let min_rate = self.min_receive_rate;
+        let receive_op = async move {
+            let head = frame.read_head(&mut reader, MAX_FRAME_SIZE).await?;
+            let _reservation = match (buffered, budget) {
+                (Some(reservation), _) => Some(reservation),
+                (None, Some(budget)) => Some(budget.reserve(head.payload_len).await?),
+                (None, None) => None,
+            };
+            // Time spent waiting for the budget is not the peer's
+            reader.start_payload(min_rate);
+            payload.clear();
+            payload.resize(head.payload_len, 0);
+            reader.read_exact(payload).await.map_err(map_eof)?;
+            Ok(head)
+        };
+        let head = with_timeout(self.receive_timeout, "Receive", receive_op)
+            .await
+            .map_err(map_inactivity)?;
+        self.check_sequence(&head)?;
+        Ok(head)
+    }
+
+    /// Read up to the next delimiter, replacing the contents of `payload` with what precedes it
+    ///
+    /// Bytes after the delimiter stay in the read-ahead buffer for the next frame.
+    async fn read_delimited_into(&mut self, payload: &mut Vec<u8>) -> Result<FrameHead> {
+        let mut unbuffered = Vec::new();
+        let mut reader =
+            InactivityReader::new(&mut self.stream, &mut unbuffered, self.inactivity_timeout);
+        let frame = &self.frame;
+        let read_ahead = &mut self.read_ahead;
+        let receive_op = async move {
+            let mut searched = 0;
+            loop {
+                if let Some(len) = find_delimiter(frame, read_ahead, searched)? {
+                    payload.clear();
+                    payload.extend_from_slice(&read_ahead[..len]);
+                    read_ahead.drain(..=len);
+                    return Ok(FrameHead {
+                        frame_type: FrameType::Data,
+                        sequence: 0,
+                        header: Vec::new(),
+                        payload_len: len,
+                    });
+                }
+                searched = read_ahead.len();
+                if reader.read_buf(read_ahead).await? == 0 {
+                    return Err(Error::ConnectionClosed);
+                }
+            }
+        };
+        with_timeout(self.receive_timeout, "Receive", receive_op)
+            .await
+            .map_err(map_inactivity)
+    }
+
+    /// Count a received data frame against the frame limit
+    ///
+    /// Over the limit the connection is shut down and an error returned.
+    pub(crate) async fn count_data_frame(&mut self) -> Result<()> {
+        self.frames_received += 1;
+        if self
+            .max_frames
+            .is_some_and(|max| self.frames_received > max)
+        {
+            self.state = ConnectionState::Closed;
+            let _ = self.stream.shutdown().await;
+            return Err(Error::Custom("frame limit exceeded".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Stream the next data frame's payload into `writer`, handling control frames along the way
+    async fn copy_data_frame<W>(&mut self, writer: &mut W) -> Result<usize>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        if self.frame.delimiter().is_some() {
+            // The frame's length is only known once all of it is buffered
+            let mut payload = Vec::new();
+            self.receive_data_into(&mut payload).await?;
+            writer.write_all(&payload).await?;
+            writer.flush().await?;
+            return Ok(payload.len());
+        }
+        self.take_deferred_error()?;
+        self.flush_writes().await?;
+        self.buffered_reservation = None;
+        loop {
+            let mut reader = InactivityReader::new(
+                &mut self.stream,
+                &mut self.read_ahead,
+                self.inactivity_timeout,
+            );
+            let head = self.frame.read_head(&mut reader, usize::MAX).await?;
+            if head.frame_type == FrameType::Data {
+                drop(reader);
+                self.count_data_frame().await?;
+                let mut reader = InactivityReader::new(
+                    &mut self.stream,
+                    &mut self.read_ahead,
+                    self.inactivity_timeout,
+                );
+                reader.start_payload(self.min_receive_rate);
+                let len = head.payload_len;
+                let copied = tokio::io::copy(&mut (&mut reader).take(len as u64), writer).await?;
+                if copied < len as u64 {
+                    return Err(Error::ConnectionClosed);
+                }
+                self.check_sequence(&head)?;
+                writer.flush().await?;
+                return Ok(len);
+            }
+
+            // Control frames are small enough to buffer
+            if head.payload_len > MAX_FRAME_SIZE {
+                return Err(Error::InvalidFrame(format!(
+                    "Control frame too large: {} bytes",
+                    head.payload_len
+                )));
+            }
+            let mut payload = vec![0u8; head.payload_len];
+            reader.read_exact(&mut payload).await.map_err(map_eof)?;
+            self.check_sequence(&head)?;
+            match head.frame_type {
+                FrameType::Ping => self.write_frame(FrameType::Pong, &payload).await?,
+                FrameType::Close => return Err(Error::ConnectionClosed),
+                FrameType::Data | FrameType::Pong | FrameType::Control => {}
+            }
+        }
+    }
+
+    /// Read frames until a data frame arrives, handling control frames along the way
+    ///
+    /// Pings are answered, pongs and application control frames skipped, and
+    /// a close frame ends the receive with [`Error::ConnectionClosed`].
+    async fn receive_data_frame(&mut self) -> Result<Frame> {
+        let mut payload = Vec::new();
+        let head = self.receive_data_into(&mut payload).await?;
+        Ok(Frame {
+            frame_type: head.frame_type,
+            header: head.header,
+            payload,
+        })
+    }
+
+    /// Like [`receive_data_frame`](Self::receive_data_frame), reading the payload into `payload`
+    async fn receive_data_into(&mut self, payload: &mut Vec<u8>) -> Result<FrameHead> {
+        loop {
+            if let Some(head) = self.receive_one_into(payload).await? {
+                return Ok(head);
+            }
+        }
+    }
+
+    /// Read one frame into `payload`, returning its head if it is a data frame
+    ///
+    /// Control frames are handled as by [`receive_data_frame`](Self::receive_data_frame)
+    /// and yield `None`. Nothing is tracked, so callers track the outcome once.
+    pub(crate) async fn receive_one_into(
+        &mut self,
+        payload: &mut Vec<u8>,
+    ) -> Result<Option<FrameHead>> {
+        let head = self.read_frame_into(payload).await?;
+        match head.frame_type {
+            FrameType::Data => {
+                self.count_data_frame().await?;
+                return Ok(Some(head));
+            }
+            FrameType::Ping => self.write_frame(FrameType::Pong, payload).await?,
+            FrameType::Close => return Err(Error::ConnectionClosed),
+            FrameType::Pong | FrameType::Control => {}
+        }
+        Ok(None)
+    }
+
+    /// Parse every complete frame that has arrived, answering pings along the way
+    async fn read_ready(&mut self) -> Result<Vec<Vec<u8>>> {
+        self.take_deferred_error()?;
+        self.flush_writes().await?;
+        // Whatever `buffer_frame` held is accounted for as already in memory
+        self.buffered_reservation = None;
+        let eof = self.fill_read_ahead()?;
+        let mut payloads = Vec::new();
+        loop {
+            // A frame that ends the batch early is left buffered, so the
+            // payloads already parsed are returned and the next call reports it
+            let (head, payload, consumed) = match self.parse_read_ahead() {
+                Ok(Some(frame)) if frame.0.frame_type != FrameType::Close => frame,
+                Ok(Some(_)) if payloads.is_empty() => return Err(Error::ConnectionClosed),
+                Ok(None) if eof && payloads.is_empty() => return Err(Error::ConnectionClosed),
+                Err(e) if payloads.is_empty() => return Err(e),
+                _ => return Ok(payloads),
+            };
+            let gap = self.frame.sequence_numbers && head.sequence != self.receive_sequence;
+            if gap && !payloads.is_empty() {
+                return Ok(payloads);
+            }
+            self.read_ahead.drain(..consumed);
+            self.check_sequence(&head)?;
+            let handled = match head.frame_type {
+                FrameType::Data => self.count_data_frame().await,
+                FrameType::Ping => self.write_frame(FrameType::Pong, &payload).await,
+                FrameType::Close | FrameType::Pong | FrameType::Control => Ok(()),
+            };
+            match handled {
+                Ok(()) if head.frame_type == FrameType::Data => payloads.push(payload),
+                Ok(()) => {}
+                Err(e) if payloads.is_empty() => return Err(e),
+                // The frame is already consumed, so hold the error for the next call
+                Err(e) => {
+                    self.deferred_error = Some(e);
+                    return Ok(payloads);
+                }
+            }
+        }
+    }
+
+    /// Report an error [`read_ready`](Self::read_ready) held back to return a batch
+    fn take_deferred_error(&mut self) -> Result<()> {
+        match self.deferred_error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Move whatever the stream can deliver without waiting into the read-ahead buffer
+    ///
+    /// Returns `true` once the stream has reached end of stream.
+    fn fill_read_ahead(&mut self) -> Result<bool> {
+        let mut chunk = [0u8; 8192];
+        let mut read = 0;
+        while read < READY_READ_LIMIT {
+            match self.stream.read(&mut chunk).now_or_never() {
+                None => return Ok(false),
+                Some(Ok(0)) => return Ok(true),
+                Some(Ok(n)) => {
+                    self.read_ahead.extend_from_slice(&chunk[..n]);
+                    read += n;
+                }
+                Some(Err(e)) => return Err(e.into()),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Parse the next complete frame from the read-ahead buffer without consuming it
+    ///
+    /// Returns the frame and its length on the wire, or `None` if only part
+    /// of it has arrived.
+    fn parse_read_ahead(&self) -> Result<Option<(FrameHead, Vec<u8>, usize)>> {
+        let Some((head, start)) = self.peek_read_ahead()? else {
+            return Ok(None);
+        };
+        let end = start + head.payload_len;
+        let payload = self.read_ahead[start..end].to_vec();
+        let consumed = end + usize::from(self.frame.delimiter().is_some());
+        Ok(Some((head, payload, consumed)))
+    }
+
+    /// Parse the head of the next frame if all of the frame is in the read-ahead buffer
+    ///
+    /// Returns the head and the offset its payload starts at.
+    fn peek_read_ahead(&self) -> Result<Option<(FrameHead, usize)>> {
+        if self.frame.delimiter().is_some() {
+            let head = find_delimiter(&self.frame, &self.read_ahead, 0)?.map(|len| FrameHead {
+                frame_type: FrameType::Data,
+                sequence: 0,
+                header: Vec::new(),
+                payload_len: len,
+            });
+            return Ok(head.map(|head| (head, 0)));
+        }
+        let Some((head, start)) = self.peek_head()? else {
+            return Ok(None);
+        };
+        if self.read_ahead.len() - start < head.payload_len {
+            return Ok(None);
+        }
+        Ok(Some((head, start)))
+    }
+
+    /// Parse the head of the next length-prefixed frame once it is in the read-ahead buffer
+    ///
+    /// Returns the head and the offset its payload starts at, whether or not
+    /// the payload has arrived.
+    fn peek_head(&self) -> Result<Option<(FrameHead, usize)>> {
+        let mut unread = &self.read_ahead[..];
+        match self
+            .frame
+            .read_head(&mut unread, MAX_FRAME_SIZE)
+            .now_or_never()
+            .expect("reading from memory never waits")
+        {
+            Ok(head) => Ok(Some((head, self.read_ahead.len() - unread.len()))),
+            Err(Error::ConnectionClosed) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Read into the read-ahead buffer until it holds a whole frame or the stream ends
+    ///
+    /// Receive and inactivity timeouts apply as they do to a receive, and
+    /// the payload is reserved against the memory budget as soon as the
+    /// frame's head has arrived, held until the frame is received.
+    async fn buffer_frame(&mut self) -> Result<()> {
+        self.take_deferred_error()?;
+        let receive_timeout = self.receive_timeout;
+        let inactivity_timeout = self.inactivity_timeout;
+        let buffer_op = async {
+            while self.peek_read_ahead()?.is_none() {
+                self.reserve_buffered().await?;
+                // read_buf is cancel-safe: bytes land in read_ahead or not at all
+                let read_op = async { Ok(self.stream.read_buf(&mut self.read_ahead).await?) };
+                if with_timeout(inactivity_timeout, "Receive inactivity", read_op).await? == 0 {
+                    return Ok(());
+                }
+            }
+            self.reserve_buffered().await
+        };
+        with_timeout(receive_timeout, "Receive", buffer_op).await
+    }
+
+    /// Reserve the payload of the frame being buffered, once its head has arrived
+    async fn reserve_buffered(&mut self) -> Result<()> {
+        let Some(budget) = self.memory_budget.clone() else {
+            return Ok(());
+        };
+        if self.buffered_reservation.is_some() || self.frame.delimiter().is_some() {
+            return Ok(());
+        }
+        if let Some((head, _)) = self.peek_head()? {
+            self.buffered_reservation = Some(budget.reserve(head.payload_len).await?);
+        }
+        Ok(())
+    }
+}
+
+impl<S: std::fmt::Debug> std::fmt::Debug for StreamTransport<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamTransport")
+            .field("stream", &self.stream)
+            .field("send_timeout", &self.send_timeout)
+            .field("receive_timeout", &self.receive_timeout)
+            .field("inactivity_timeout", &self.inactivity_timeout)
+            .field("frame", &self.frame)
+            .field("read_ahead", &self.read_ahead.len())
+            .field("write_batch", &self.write_batch)
+            .field("batched", &self.write_buf.len())
+            .field("receive_ring", &self.receive_ring)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> Transport for StreamTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + Sync,
+{
+    async fn send(&mut self, bytes: &[u8]) -> Result<()> {
+        self.check_outgoing(FrameType::Data, self.frame.header_len, &[bytes])?;
+        let result = self.write_frame(FrameType::Data, bytes).await;
+        self.track(result)
+    }
+
+    async fn send_vectored(&mut self, parts: &[&[u8]]) -> Result<()> {
+        self.check_outgoing(FrameType::Data, self.frame.header_len, parts)?;
+        let result = self.write_vectored(parts).await;
+        self.track(result)
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        let result = self.receive_data_frame().await;
+        Ok(self.track(result)?.payload)
+    }
+
+    async fn receive_into(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        let result = self.receive_data_into(buf).await;
+        self.track(result)?;
+        Ok(())
+    }
+
+    async fn receive_ready(&mut self) -> Result<Vec<Vec<u8>>> {
+        let result = self.read_ready().await;
+        self.track(result)
+    }
+
+    async fn readable(&mut self) -> Result<()> {
+        let result = self.buffer_frame().await;
+        self.track(result)
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.state = ConnectionState::Closing;
+        let flushed = self.flush_writes().await;
+        if self.frame.frame_types
+            && self
+                .check_outgoing(FrameType::Close, self.frame.header_len, &[])
+                .is_ok()
+        {
+            // Best effort: the peer sees end of stream either way
+            let _ = self.write_frame(FrameType::Close, &[]).await;
+        }
+        let result = self.stream.shutdown().await;
+        self.state = ConnectionState::Closed;
+        flushed?;
+        result?;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        let result = self.flush_writes().await;
+        self.track(result)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.state != ConnectionState::Connected
+    }
+
+    fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    fn frame_overhead(&self) -> usize {
+        self.frame.overhead()
+    }
+
+    async fn send_control(&mut self, frame_type: FrameType, payload: &[u8]) -> Result<()> {
+        self.check_outgoing(frame_type, self.frame.header_len, &[payload])?;
+        let result = self.write_frame(frame_type, payload).await;
+        self.track(result)
+    }
+
+    async fn receive_frame(&mut self) -> Result<(FrameType, Vec<u8>)> {
+        let result = self.read_frame().await;
+        let frame = self.track(result)?;
+        if frame.frame_type == FrameType::Data {
+            self.count_data_frame().await?;
+        }
+        Ok((frame.frame_type, frame.payload))
+    }
+
+    async fn send_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        len: usize,
+    ) -> Result<()> {
+        self.frame
+            .check_frame(FrameType::Data, self.frame.header_len, len)?;
+        let result = match self.flush_writes().await {
+            Ok(()) => {
+                let sequence = self.send_sequence;
+                let send_op = self
+                    .frame
+                    .write_from_reader(&mut self.stream, reader, sequence, len);
+                let result = with_timeout(self.send_timeout, "Send", send_op).await;
+                self.number_sent(result)
+            }
+            Err(e) => Err(e),
+        };
+        self.track(result)
+    }
+
+    async fn receive_to_writer(
+        &mut self,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<usize> {
+        let timeout = self.receive_timeout;
+        let receive_op = self.copy_data_frame(writer);
+        let result = with_timeout(timeout, "Receive", receive_op)
+            .await
+            .map_err(map_inactivity);
+        self.track(result)
+    }
+}
+
+/// How often the minimum receive rate is checked
+const RATE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Report an expired [`InactivityReader`] like the other timeouts
+fn map_inactivity(e: Error) -> Error {
+    match e {
+        Error::Io(e) if e.kind() == io::ErrorKind::TimedOut => {
+            Error::Custom("Receive inactivity timeout exceeded".to_string())
+        }
+        Error::Io(e) if e.get_ref().is_some_and(|inner| inner.is::<TooSlow>()) => {
+            Error::ReceiveTooSlow
+        }
+        other => other,
+    }
+}
+
+/// Length of the delimited frame at the start of `buf`, searching from `from` on
+///
+/// `None` until its delimiter has arrived; an error once more than
+/// [`MAX_FRAME_SIZE`] bytes have arrived without one.
+fn find_delimiter(frame: &FrameConfig, buf: &[u8], from: usize) -> Result<Option<usize>> {
+    let delimiter = frame.delimiter().expect("framing is delimited");
+    match buf[from..].iter().position(|&byte| byte == delimiter) {
+        Some(pos) if from + pos <= MAX_FRAME_SIZE => Ok(Some(from + pos)),
+        None if buf.len() <= MAX_FRAME_SIZE => Ok(None),
+        _ => Err(frame.unterminated()),
+    }
+}
+
+/// I/O error payload raised by [`InactivityReader`] when the payload is too slow
+#[derive(Debug)]
+struct TooSlow;
+
+impl std::fmt::Display for TooSlow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("receive too slow")
+    }
+}
+
+impl std::error::Error for TooSlow {}
+
+/// Progress of a payload read against the minimum receive rate
+struct RateCheck {
+    min_rate: u64,
+    started: Instant,
+    received: u64,
+    next_check: Pin<Box<Sleep>>,
+}
+
+impl RateCheck {
+    fn new(min_rate: u64) -> Self {
+        Self {
+            min_rate,
+            started: Instant::now(),
+            received: 0,
+            next_check: Box::pin(tokio::time::sleep(RATE_CHECK_INTERVAL)),
+        }
+    }
+
+    /// Fail if a check is due and the average rate is below the minimum
+    ///
+    /// Polls the check timer so a stalled peer still wakes the reader.
+    fn poll_check(&mut self, cx: &mut Context<'_>) -> io::Result<()> {
+        while self.next_check.as_mut().poll(cx).is_ready() {
+            let elapsed = self.started.elapsed().as_secs_f64();
+            if (self.received as f64) < self.min_rate as f64 * elapsed {
+                return Err(io::Error::other(TooSlow));
+            }
+            let next = self.next_check.deadline() + RATE_CHECK_INTERVAL;
+            self.next_check.as_mut().reset(next);
+        }
+        Ok(())
+    }
+}
+
+/// Reader that fails with `TimedOut` once no bytes have arrived for `timeout`
+///
+/// Passes reads straight through when no timeout is set. Bytes already
+/// buffered by `receive_ready` are served before reading the stream. Once
+/// [`start_payload`](Self::start_payload) is called it also enforces the
+/// minimum receive rate.
+struct InactivityReader<'a, S> {
+    stream: &'a mut S,
+    read_ahead: &'a mut Vec<u8>,
+    timer: Option<(Duration, Pin<Box<Sleep>>)>,
+    rate: Option<RateCheck>,
+}
+
+impl<'a, S> InactivityReader<'a, S> {
+    fn new(stream: &'a mut S, read_ahead: &'a mut Vec<u8>, timeout: Option<Duration>) -> Self {
+        Self {
+            stream,
+            read_ahead,
+            timer: timeout.map(|timeout| (timeout, Box::pin(tokio::time::sleep(timeout)))),
+            rate: None,
+        }
+    }
+
+    /// Start checking the minimum receive rate, if any, from here on
+    fn start_payload(&mut self, min_rate: Option<u64>) {
+        self.rate = min_rate.map(RateCheck::new);
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for InactivityReader<'_, S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        if !this.read_ahead.is_empty() {
+            let n = this.read_ahead.len().min(buf.remaining());
+            buf.put_slice(&this.read_ahead[..n]);
+            this.read_ahead.drain(..n);
+            if let Some(rate) = &mut this.rate {
+                rate.received += n as u64;
+            }
+            return Poll::Ready(Ok(()));
+        }
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut *this.stream).poll_read(cx, buf);
+        if let Some(rate) = &mut this.rate {
+            rate.received += (buf.filled().len() - filled) as u64;
+            if poll.is_pending() {
+                rate.poll_check(cx)?;
+            }
+        }
+        let Some((timeout, sleep)) = &mut this.timer else {
+            return poll;
+        };
+        match poll {
+            Poll::Ready(result) => {
+                if buf.filled().len() > filled {
+                    sleep.as_mut().reset(Instant::now() + *timeout);
+                }
+                Poll::Ready(result)
+            }
+            Poll::Pending => match sleep.as_mut().poll(cx) {
+                Poll::Ready(()) => Poll::Ready(Err(io::ErrorKind::TimedOut.into())),
+                Poll::Pending => Poll::Pending,
+            },
+        }
+    }
+}
@@ -1,19 +1,36 @@
+use std::future::Future;
+use std::mem::MaybeUninit;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
+use futures::stream::{self, FuturesUnordered, Stream, StreamExt};
+use socket2::SockRef;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpSocket, TcpStream};
 
 use crate::error::{Error, Result};
-use crate::transport::Transport;
+use crate::transport::budget::MemoryBudget;
+use crate::transport::framing::{Endian, FrameConfig, FrameType, FramingMode, PrefixWidth};
+use crate::transport::socks5::{self, Socks5Auth};
+use crate::transport::stream::StreamTransport;
+use crate::transport::{ConnectionState, Transport};
+
+/// Admission check run on each accepted peer address
+type AcceptFilter = Box<dyn Fn(&SocketAddr) -> bool + Send + Sync>;
+
+/// Listen backlog used when none is configured, same as `TcpListener::bind`
+const DEFAULT_BACKLOG: u32 = 1024;
 
 /// TCP transport with length-prefix framing
 ///
-/// Messages are sent with a 4-byte big-endian length prefix
+/// Messages are sent with a 4-byte big-endian length prefix, see [`framing`](crate::transport::framing)
+///
+/// Call [`Transport::close`] to end the connection in an orderly way.
+/// Dropping the transport closes the socket without a shutdown, which can
+/// discard data the peer has not read yet.
 pub struct TcpTransport {
-    stream: TcpStream,
-    send_timeout: Option<Duration>,
-    receive_timeout: Option<Duration>,
+    inner: StreamTransport<TcpStream>,
 }
 
 impl TcpTransport {
@@ -31,6 +48,29 @@ impl TcpTransport {
             .await
     }
 
+    /// Upgrade the connection in place, e.g. to TLS after a plaintext hello
+    ///
+    /// `upgrade` runs the handshake on the raw socket; the returned
+    /// transport keeps this one's settings. See [`StreamTransport::upgrade`].
+    pub async fn upgrade<S, F, Fut>(self, upgrade: F) -> Result<StreamTransport<S>>
+    where
+        F: FnOnce(TcpStream) -> Fut,
+        Fut: Future<Output = std::io::Result<S>>,
+    {
+        self.inner.upgrade(upgrade).await
+    }
+
+    /// Resolve `host` and connect to the first of its addresses to answer
+    ///
+    /// Addresses are tried happy-eyeballs style (RFC 8305): IPv6 first,
+    /// alternating with IPv4, with a new attempt started every 250ms or as
+    /// soon as the previous one fails, so a dead address does not strand the
+    /// connection. Fails with the last attempt's error if none succeeds. Use
+    /// [`TcpTransportBuilder::host`] to combine this with a connect timeout.
+    pub async fn connect_host(host: &str, port: u16) -> Result<Self> {
+        Self::builder().host(host, port).connect().await
+    }
+
     /// Create a builder for configuring the transport
     pub fn builder() -> TcpTransportBuilder {
         TcpTransportBuilder::new()
@@ -39,110 +79,354 @@ impl TcpTransport {
     /// Create from an existing TcpStream
     pub fn from_stream(stream: TcpStream) -> Self {
         Self {
-            stream,
-            send_timeout: None,
-            receive_timeout: None,
+            inner: StreamTransport::new(stream),
         }
     }
 
+    /// Get the send timeout
+    pub fn send_timeout(&self) -> Option<Duration> {
+        self.inner.send_timeout()
+    }
+
+    /// Set the send timeout, or `None` to disable it
+    ///
+    /// Takes effect from the next `send`.
+    pub fn set_send_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_send_timeout(timeout);
+    }
+
+    /// Get the receive timeout
+    pub fn receive_timeout(&self) -> Option<Duration> {
+        self.inner.receive_timeout()
+    }
+
+    /// Set the receive timeout, or `None` to disable it
+    ///
+    /// Takes effect from the next `receive`.
+    pub fn set_receive_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_receive_timeout(timeout);
+    }
+
+    /// Get the receive inactivity timeout
+    pub fn receive_inactivity_timeout(&self) -> Option<Duration> {
+        self.inner.receive_inactivity_timeout()
+    }
+
+    /// Set the receive inactivity timeout, or `None` to disable it
+    ///
+    /// See [`StreamTransport::set_receive_inactivity_timeout`].
+    pub fn set_receive_inactivity_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_receive_inactivity_timeout(timeout);
+    }
+
+    /// Get a reference to the underlying TcpStream
+    pub fn get_ref(&self) -> &TcpStream {
+        self.inner.get_ref()
+    }
+
+    /// Unwrap the underlying TcpStream
+    ///
+    /// Useful for handing a connection set up by the builder (connect
+    /// timeout, proxy, socket options) to a library that speaks its own
+    /// protocol over a raw `AsyncRead + AsyncWrite` stream. From then on
+    /// framing is bypassed and send/receive timeouts no longer apply. Frames
+    /// held back by write batching are written out first. Fails if
+    /// [`receive_ready`](Transport::receive_ready) or
+    /// [`readable`](Transport::readable) has buffered bytes, as those would
+    /// be lost; plain receives never leave any behind.
+    pub async fn into_inner(self) -> Result<TcpStream> {
+        self.inner.into_inner().await
+    }
+
+    /// Send a payload with an out-of-band metadata header
+    ///
+    /// `N` must match the header length configured on the builder.
+    pub async fn send_with_header<const N: usize>(
+        &mut self,
+        header: &[u8; N],
+        payload: &[u8],
+    ) -> Result<()> {
+        self.inner.send_with_header(header, payload).await
+    }
+
+    /// Receive a payload together with its metadata header
+    ///
+    /// `N` must match the header length configured on the builder.
+    pub async fn receive_with_header<const N: usize>(&mut self) -> Result<([u8; N], Vec<u8>)> {
+        self.inner.receive_with_header().await
+    }
+
+    /// Give [`receive_borrowed`](Self::receive_borrowed) a fixed buffer of `capacity` bytes
+    ///
+    /// See [`StreamTransport::set_receive_ring`].
+    pub fn set_receive_ring(&mut self, capacity: Option<usize>) {
+        self.inner.set_receive_ring(capacity);
+    }
+
+    /// Number of frames too large for the receive ring, which had to allocate
+    pub fn oversized_receives(&self) -> u64 {
+        self.inner.oversized_receives()
+    }
+
+    /// Receive the next payload without allocating, borrowed until the next call
+    ///
+    /// See [`StreamTransport::receive_borrowed`].
+    pub async fn receive_borrowed(&mut self) -> Result<&[u8]> {
+        self.inner.receive_borrowed().await
+    }
+
+    /// Create from a standard library TcpStream
+    ///
+    /// Useful when socket options must be set (e.g. via `socket2`) before the
+    /// stream is handed to tokio. The stream is switched to nonblocking mode.
+    pub fn from_std(stream: std::net::TcpStream) -> Result<Self> {
+        stream.set_nonblocking(true)?;
+        Ok(Self::from_stream(TcpStream::from_std(stream)?))
+    }
+
+    /// Convert back into a standard library TcpStream
+    ///
+    /// Unwraps like [`into_inner`](Self::into_inner). The returned stream is
+    /// still in nonblocking mode.
+    pub async fn into_std(self) -> Result<std::net::TcpStream> {
+        self.inner
+            .into_inner()
+            .await?
+            .into_std()
+            .map_err(Into::into)
+    }
+
     /// Get the remote address of this connection
     pub fn peer_addr(&self) -> Result<SocketAddr> {
-        self.stream.peer_addr().map_err(Into::into)
+        self.inner.get_ref().peer_addr().map_err(Into::into)
     }
 
     /// Get the local address of this connection
     pub fn local_addr(&self) -> Result<SocketAddr> {
-        self.stream.local_addr().map_err(Into::into)
+        self.inner.get_ref().local_addr().map_err(Into::into)
+    }
+
+    /// Free space in the kernel send buffer, in bytes
+    ///
+    /// The SO_SNDBUF size minus the bytes still queued for the peer. A sender
+    /// can use it to pace itself instead of relying on `send` blocking. Only
+    /// supported on Linux; other platforms return an error.
+    ///
+    /// Treat it as an estimate. Linux reports SO_SNDBUF as double the size
+    /// requested, the extra covering its own bookkeeping, while the queued
+    /// count is payload only, so fewer bytes than this fit before `send`
+    /// blocks. Frames held back by write batching are not counted either.
+    pub fn send_buffer_available(&self) -> Result<usize> {
+        let size = SockRef::from(self.inner.get_ref()).send_buffer_size()?;
+        let queued = sockbuf::send_queued(self.inner.get_ref())?;
+        Ok(size.saturating_sub(queued))
+    }
+
+    /// Bytes received by the kernel that have not been read yet
+    ///
+    /// Only supported on Linux; other platforms return an error.
+    pub fn recv_buffer_pending(&self) -> Result<usize> {
+        sockbuf::recv_pending(self.inner.get_ref())
+    }
+
+    /// Get the SO_LINGER setting of this connection
+    pub fn linger(&self) -> Result<Option<Duration>> {
+        SockRef::from(self.inner.get_ref())
+            .linger()
+            .map_err(Into::into)
+    }
+
+    /// The kernel's smoothed round-trip time estimate for this connection
+    ///
+    /// Read from `TCP_INFO`, so it costs no traffic; the estimate only
+    /// updates as data is acknowledged. Only supported on Linux; other
+    /// platforms return an error.
+    pub fn rtt(&self) -> Result<Duration> {
+        tcpinfo::rtt(self.inner.get_ref())
+    }
+
+    /// TCP maximum segment size of this connection (`TCP_MAXSEG`)
+    ///
+    /// Once connected this reflects the MSS negotiated with the peer, which
+    /// bounds the payload of a single segment on the path. Only supported on
+    /// Unix; other platforms return an error.
+    pub fn mss(&self) -> Result<u32> {
+        #[cfg(unix)]
+        {
+            SockRef::from(self.inner.get_ref())
+                .tcp_mss()
+                .map_err(Into::into)
+        }
+        #[cfg(not(unix))]
+        {
+            Err(Error::Custom(
+                "TCP_MAXSEG is not supported on this platform".to_string(),
+            ))
+        }
+    }
+}
+
+impl std::fmt::Debug for TcpTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TcpTransport")
+            .field("peer_addr", &self.peer_addr().ok())
+            .field("local_addr", &self.local_addr().ok())
+            .field("send_timeout", &self.send_timeout())
+            .field("receive_timeout", &self.receive_timeout())
+            .field("state", &self.inner.state())
+            .finish()
     }
 }
 
 #[async_trait::async_trait]
 impl Transport for TcpTransport {
     async fn send(&mut self, bytes: &[u8]) -> Result<()> {
-        let send_op = async {
-            // Write length prefix (4 bytes, big-endian)
-            let len = bytes.len() as u32;
-            self.stream.write_u32(len).await?;
+        self.inner.send(bytes).await
+    }
 
-            // Write data
-            self.stream.write_all(bytes).await?;
-            self.stream.flush().await?;
+    async fn send_vectored(&mut self, parts: &[&[u8]]) -> Result<()> {
+        self.inner.send_vectored(parts).await
+    }
 
-            Ok::<(), Error>(())
-        };
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        self.inner.receive().await
+    }
 
-        if let Some(timeout) = self.send_timeout {
-            tokio::time::timeout(timeout, send_op)
-                .await
-                .map_err(|_| Error::Custom("Send timeout exceeded".to_string()))?
-        } else {
-            send_op.await
+    async fn receive_into(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        self.inner.receive_into(buf).await
+    }
+
+    async fn receive_ready(&mut self) -> Result<Vec<Vec<u8>>> {
+        self.inner.receive_ready().await
+    }
+
+    async fn readable(&mut self) -> Result<()> {
+        self.inner.readable().await
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.inner.flush().await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    fn state(&self) -> ConnectionState {
+        match self.inner.state() {
+            ConnectionState::Connected if self.is_closed() => ConnectionState::Closed,
+            state => state,
         }
     }
 
-    async fn receive(&mut self) -> Result<Vec<u8>> {
-        let receive_op = async {
-            // Read length prefix
-            let len = self.stream.read_u32().await.map_err(|e| {
-                if e.kind() == std::io::ErrorKind::UnexpectedEof {
-                    Error::ConnectionClosed
-                } else {
-                    e.into()
-                }
-            })? as usize;
-
-            // Validate length (max 100MB to prevent DOS)
-            if len > 100 * 1024 * 1024 {
-                return Err(Error::InvalidFrame(format!(
-                    "Message too large: {} bytes",
-                    len
-                )));
-            }
+    fn describe(&self) -> Option<&dyn std::fmt::Debug> {
+        Some(self)
+    }
 
-            // Read data
-            let mut buf = vec![0u8; len];
-            self.stream.read_exact(&mut buf).await.map_err(|e| {
-                if e.kind() == std::io::ErrorKind::UnexpectedEof {
-                    Error::ConnectionClosed
-                } else {
-                    e.into()
-                }
-            })?;
+    fn frame_overhead(&self) -> usize {
+        self.inner.frame_overhead()
+    }
 
-            Ok::<Vec<u8>, Error>(buf)
-        };
+    fn is_closed(&self) -> bool {
+        if self.inner.state() != ConnectionState::Connected {
+            return true;
+        }
 
-        if let Some(timeout) = self.receive_timeout {
-            tokio::time::timeout(timeout, receive_op)
-                .await
-                .map_err(|_| Error::Custom("Receive timeout exceeded".to_string()))?
-        } else {
-            receive_op.await
+        // Peek a single byte without blocking: EOF means the peer closed,
+        // WouldBlock means the connection is idle but alive
+        let mut buf = [MaybeUninit::<u8>::uninit(); 1];
+        match SockRef::from(self.inner.get_ref()).peek(&mut buf) {
+            Ok(0) => true,
+            Ok(_) => false,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => false,
+            Err(_) => true,
         }
     }
 
-    async fn close(&mut self) -> Result<()> {
-        self.stream.shutdown().await?;
-        Ok(())
+    async fn send_control(&mut self, frame_type: FrameType, payload: &[u8]) -> Result<()> {
+        self.inner.send_control(frame_type, payload).await
+    }
+
+    async fn receive_frame(&mut self) -> Result<(FrameType, Vec<u8>)> {
+        self.inner.receive_frame().await
+    }
+
+    async fn send_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        len: usize,
+    ) -> Result<()> {
+        self.inner.send_from_reader(reader, len).await
+    }
+
+    async fn receive_to_writer(
+        &mut self,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<usize> {
+        self.inner.receive_to_writer(writer).await
     }
 }
 
 /// TCP listener for accepting incoming connections
 pub struct TcpTransportListener {
     listener: TcpListener,
+    send_timeout: Option<Duration>,
+    receive_timeout: Option<Duration>,
+    memory_budget: Option<Arc<MemoryBudget>>,
+    max_frames: Option<usize>,
+    min_receive_rate: Option<u64>,
+    frame: FrameConfig,
+    nodelay: bool,
+    on_accept: Option<AcceptFilter>,
 }
 
 impl TcpTransportListener {
-    /// Bind to a local address
+    /// Bind to a local address with default settings
+    ///
+    /// Shorthand for `TcpTransportListener::builder().bind(addr)`.
     pub async fn bind(addr: SocketAddr) -> Result<Self> {
-        let listener = TcpListener::bind(addr).await?;
-        Ok(Self { listener })
+        Self::builder().bind(addr).await
+    }
+
+    /// Wrap an already-listening standard library listener with default settings
+    ///
+    /// Shorthand for `TcpTransportListener::builder().from_std(listener)`.
+    pub fn from_std(listener: std::net::TcpListener) -> Result<Self> {
+        Self::builder().from_std(listener)
+    }
+
+    /// Create a builder for configuring the listener
+    pub fn builder() -> TcpTransportListenerBuilder {
+        TcpTransportListenerBuilder::new()
     }
 
     /// Accept an incoming connection
+    ///
+    /// The transport starts with the listener's per-connection settings.
+    /// Connections refused by the [`on_accept`](TcpTransportListenerBuilder::on_accept)
+    /// callback are closed and skipped.
     pub async fn accept(&self) -> Result<(TcpTransport, SocketAddr)> {
-        let (stream, addr) = self.listener.accept().await?;
-        Ok((TcpTransport::from_stream(stream), addr))
+        let (stream, addr) = loop {
+            let (stream, addr) = self.listener.accept().await?;
+            match &self.on_accept {
+                Some(admit) if !admit(&addr) => drop(stream),
+                _ => break (stream, addr),
+            }
+        };
+        if self.nodelay {
+            stream.set_nodelay(true)?;
+        }
+        let mut transport = TcpTransport {
+            inner: StreamTransport::with_config(stream, self.frame.clone()),
+        };
+        transport.set_send_timeout(self.send_timeout);
+        transport.set_receive_timeout(self.receive_timeout);
+        transport
+            .inner
+            .set_memory_budget(self.memory_budget.clone());
+        transport.inner.set_max_frames(self.max_frames);
+        transport.inner.set_min_receive_rate(self.min_receive_rate);
+        Ok((transport, addr))
     }
 
     /// Get the local address this listener is bound to
@@ -150,6 +434,17 @@ impl TcpTransportListener {
         self.listener.local_addr().map_err(Into::into)
     }
 
+    /// Turn the listener into a stream of accepted connections
+    ///
+    /// Accept errors are yielded as items rather than ending the stream, so
+    /// transient failures can be skipped. The stream never terminates on its own.
+    pub fn incoming(self) -> impl Stream<Item = Result<TcpTransport>> {
+        stream::unfold(self, |listener| async move {
+            let accepted = listener.accept().await.map(|(transport, _)| transport);
+            Some((accepted, listener))
+        })
+    }
+
     /// Close the listener
     ///
     /// Note: Tokio's TcpListener doesn't have an explicit close,
@@ -160,13 +455,25 @@ impl TcpTransportListener {
     }
 }
 
+impl std::fmt::Debug for TcpTransportListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TcpTransportListener")
+            .field("local_addr", &self.local_addr().ok())
+            .field("send_timeout", &self.send_timeout)
+            .field("receive_timeout", &self.receive_timeout)
+            .field("nodelay", &self.nodelay)
+            .field("on_accept", &self.on_accept.is_some())
+            .finish()
+    }
+}
+
 #[async_trait::async_trait]
 impl crate::transport::TransportListener for TcpTransportListener {
     type Transport = TcpTransport;
 
     async fn accept(&self) -> Result<Self::Transport> {
-        let (stream, _) = self.listener.accept().await?;
-        Ok(TcpTransport::from_stream(stream))
+        let (transport, _) = TcpTransportListener::accept(self).await?;
+        Ok(transport)
     }
 
     async fn close(&mut self) -> Result<()> {
@@ -177,12 +484,30 @@ impl crate::transport::TransportListener for TcpTransportListener {
 /// Builder for configuring TCP transport
 #[derive(Default)]
 pub struct TcpTransportBuilder {
-    address: Option<SocketAddr>,
+    target: Option<Target>,
     connect_timeout: Option<Duration>,
     send_timeout: Option<Duration>,
     receive_timeout: Option<Duration>,
+    receive_inactivity_timeout: Option<Duration>,
+    memory_budget: Option<Arc<MemoryBudget>>,
+    max_frames: Option<usize>,
+    min_receive_rate: Option<u64>,
+    frame: FrameConfig,
+    linger: Option<Option<Duration>>,
+    socks5_proxy: Option<(SocketAddr, Option<Socks5Auth>)>,
+    buffers: SocketBuffers,
+    fastopen: bool,
+    nodelay: bool,
+    write_batch: Option<usize>,
+    receive_ring: Option<usize>,
 }
 
+/// Write batch size set by [`TcpTransportBuilder::throughput_mode`]
+const THROUGHPUT_WRITE_BATCH: usize = 64 * 1024;
+
+/// SO_SNDBUF set by [`TcpTransportBuilder::throughput_mode`]
+const THROUGHPUT_SEND_BUFFER: usize = 1024 * 1024;
+
 impl TcpTransportBuilder {
     /// Create a new builder
     pub fn new() -> Self {
@@ -191,7 +516,18 @@ impl TcpTransportBuilder {
 
     /// Set the address to connect to
     pub fn address(mut self, addr: SocketAddr) -> Self {
-        self.address = Some(addr);
+        self.target = Some(Target::Addr(addr));
+        self
+    }
+
+    /// Connect to `host`, resolved when connecting, instead of a fixed address
+    ///
+    /// Its addresses are raced as described in
+    /// [`TcpTransport::connect_host`]; the connect timeout covers resolution
+    /// and all attempts. Through a SOCKS5 proxy only the first resolved
+    /// address is used.
+    pub fn host(mut self, host: impl Into<String>, port: u16) -> Self {
+        self.target = Some(Target::Host(host.into(), port));
         self
     }
 
@@ -213,13 +549,251 @@ impl TcpTransportBuilder {
         self
     }
 
+    /// Fail a receive once no bytes have arrived for `timeout`
+    ///
+    /// The timer resets on every read, so large frames over slow links only
+    /// fail when the transfer actually stalls.
+    pub fn receive_inactivity_timeout(mut self, timeout: Duration) -> Self {
+        self.receive_inactivity_timeout = Some(timeout);
+        self
+    }
+
+    /// Reserve each received payload against a budget shared between connections
+    ///
+    /// Caps the memory all connections spend on receive buffers at once; see
+    /// [`MemoryBudget`].
+    pub fn memory_budget(mut self, budget: Arc<MemoryBudget>) -> Self {
+        self.memory_budget = Some(budget);
+        self
+    }
+
+    /// Fail `receive` with "frame limit exceeded" after `max` data frames
+    ///
+    /// The frame over the limit also closes the connection. Unlimited by
+    /// default.
+    pub fn max_frames(mut self, max: usize) -> Self {
+        self.max_frames = Some(max);
+        self
+    }
+
+    /// Fail `receive` if a payload arrives slower than `bytes_per_sec`
+    ///
+    /// The receive fails with [`Error::ReceiveTooSlow`]; see
+    /// [`StreamTransport::set_min_receive_rate`]. Unlimited by default.
+    pub fn min_receive_rate(mut self, bytes_per_sec: u64) -> Self {
+        self.min_receive_rate = Some(bytes_per_sec);
+        self
+    }
+
+    /// Preallocate a `capacity`-byte buffer for [`TcpTransport::receive_borrowed`]
+    ///
+    /// See [`StreamTransport::set_receive_ring`].
+    pub fn receive_ring(mut self, capacity: usize) -> Self {
+        self.receive_ring = Some(capacity);
+        self
+    }
+
+    /// Carry a fixed-length metadata header of `len` bytes before every payload
+    ///
+    /// Use [`send_with_header`](TcpTransport::send_with_header) and
+    /// [`receive_with_header`](TcpTransport::receive_with_header) to access it; plain
+    /// `send` writes a zeroed header and `receive` discards it.
+    pub fn header_len(mut self, len: usize) -> Self {
+        self.frame.header_len = len;
+        self
+    }
+
+    /// Prefix every frame with a type byte so control frames can be sent
+    ///
+    /// Required for [`Channel::ping`](crate::Channel::ping); both peers must agree.
+    pub fn frame_types(mut self, enabled: bool) -> Self {
+        self.frame.frame_types = enabled;
+        self
+    }
+
+    /// Number every frame and fail `receive` when one is missing or out of order
+    ///
+    /// The receive fails with [`Error::SequenceGap`]. See
+    /// [`FrameConfig::sequence_numbers`]; both peers must agree.
+    pub fn sequence_numbers(mut self, enabled: bool) -> Self {
+        self.frame.sequence_numbers = enabled;
+        self
+    }
+
+    /// Set the overall frame layout
+    ///
+    /// [`FramingMode::GrpcLengthPrefixed`] speaks gRPC message framing and
+    /// also sets the header length to 1 for the compression flag, read and
+    /// written with [`send_with_header`](TcpTransport::send_with_header) and
+    /// [`receive_with_header`](TcpTransport::receive_with_header).
+    pub fn framing_mode(mut self, mode: FramingMode) -> Self {
+        self.frame.mode = mode;
+        if mode == FramingMode::GrpcLengthPrefixed {
+            self.frame.header_len = 1;
+        }
+        self
+    }
+
+    /// Set the byte order of the frame length prefix
+    ///
+    /// Defaults to big-endian; both peers must agree.
+    pub fn length_endian(mut self, endian: Endian) -> Self {
+        self.frame.length_endian = endian;
+        self
+    }
+
+    /// Set the width of the frame length prefix
+    ///
+    /// Defaults to 4 bytes; both peers must agree.
+    pub fn length_width(mut self, width: PrefixWidth) -> Self {
+        self.frame.length_width = width;
+        self
+    }
+
+    /// Protect the length prefix with a checksum
+    ///
+    /// Cheap insurance against a corrupted length; see
+    /// [`FrameConfig::length_checksum`]. Both peers must agree.
+    pub fn length_checksum(mut self, enabled: bool) -> Self {
+        self.frame.length_checksum = enabled;
+        self
+    }
+
+    /// Write `marker` before every frame and check it on receive
+    ///
+    /// Both peers must use the same marker.
+    pub fn sync_marker(mut self, marker: [u8; 4]) -> Self {
+        self.frame.sync_marker = Some(marker);
+        self
+    }
+
+    /// Skip corrupt frames by scanning ahead for the next sync marker
+    ///
+    /// Off by default. Only takes effect together with
+    /// [`sync_marker`](Self::sync_marker); see [`FrameConfig::resync`].
+    pub fn resync_on_corruption(mut self, enabled: bool) -> Self {
+        self.frame.resync = enabled;
+        self
+    }
+
+    /// Set SO_LINGER on the connected socket
+    ///
+    /// `Some(duration)` makes closing the socket wait up to `duration` for
+    /// unsent data to be delivered; `Some(Duration::ZERO)` resets the
+    /// connection on close; `None` restores the OS default. Note that a
+    /// non-zero linger can make the final close of the socket block the
+    /// calling thread.
+    pub fn linger(mut self, linger: Option<Duration>) -> Self {
+        self.linger = Some(linger);
+        self
+    }
+
+    /// Set SO_SNDBUF before connecting
+    ///
+    /// The OS may round the size up (Linux doubles it for bookkeeping).
+    pub fn send_buffer_size(mut self, size: usize) -> Self {
+        self.buffers.send = Some(size);
+        self
+    }
+
+    /// Set SO_RCVBUF before connecting
+    ///
+    /// Set before the handshake so the TCP window scale can account for it.
+    /// The OS may round the size up.
+    pub fn recv_buffer_size(mut self, size: usize) -> Self {
+        self.buffers.recv = Some(size);
+        self
+    }
+
+    /// Set TCP_NODELAY, sending small writes without waiting to coalesce them
+    pub fn nodelay(mut self, enabled: bool) -> Self {
+        self.nodelay = enabled;
+        self
+    }
+
+    /// Batch sent frames into writes of `size` bytes
+    ///
+    /// See [`StreamTransport::set_write_batch`]. Batched frames go out when
+    /// the batch fills, on [`Transport::flush`], before receiving and on
+    /// close.
+    pub fn write_batch(mut self, size: usize) -> Self {
+        self.write_batch = Some(size);
+        self
+    }
+
+    /// Tune the connection for bulk transfer rather than latency
+    ///
+    /// A preset for streaming many frames one way, such as replication:
+    /// sends are batched into 64 KiB writes instead of one write and flush
+    /// per frame, SO_SNDBUF is raised to 1 MiB and TCP_NODELAY is set so the
+    /// tail of each batch is not held back by Nagle's algorithm. Call
+    /// [`Transport::flush`] (or [`Channel::flush`](crate::Channel::flush))
+    /// when the last frame of a burst must go out before the next receive.
+    ///
+    /// Setters called afterwards override individual parts of the preset.
+    pub fn throughput_mode(self) -> Self {
+        self.write_batch(THROUGHPUT_WRITE_BATCH)
+            .send_buffer_size(THROUGHPUT_SEND_BUFFER)
+            .nodelay(true)
+    }
+
+    /// Use TCP Fast Open to carry the first frame in the SYN
+    ///
+    /// Saves a round trip on connections that send first, such as one-off
+    /// requests. Linux only (4.11+): `connect` returns before the handshake
+    /// and the first send completes it. When the kernel, the peer or a
+    /// middlebox does not support TFO the connection silently falls back to
+    /// a normal handshake, and on other platforms this is a no-op. The first
+    /// connection to a server always takes a full handshake to obtain a
+    /// cookie; the server must enable TFO as well, e.g. with
+    /// [`TcpTransportListenerBuilder::tcp_fastopen`].
+    pub fn tcp_fastopen(mut self, enabled: bool) -> Self {
+        self.fastopen = enabled;
+        self
+    }
+
+    /// Connect through a SOCKS5 proxy
+    ///
+    /// The transport connects to `proxy` and asks it to reach the configured
+    /// address. The connect timeout covers the whole proxy negotiation.
+    pub fn socks5_proxy(mut self, proxy: SocketAddr, auth: Option<Socks5Auth>) -> Self {
+        self.socks5_proxy = Some((proxy, auth));
+        self
+    }
+
     /// Connect with the configured settings
     pub async fn connect(self) -> Result<TcpTransport> {
-        let addr = self
-            .address
+        let target = self
+            .target
+            .as_ref()
             .ok_or_else(|| Error::Custom("Address not set".to_string()))?;
+        self.frame.check_delimited()?;
 
-        let connect_op = TcpStream::connect(addr);
+        let open = |addr: SocketAddr| -> Result<TcpSocket> {
+            let socket = self.buffers.socket_for(addr)?;
+            if self.fastopen {
+                fastopen::enable_connect(&socket);
+            }
+            Ok(socket)
+        };
+        let connect_op = async {
+            let addrs = target.resolve().await?;
+            match &self.socks5_proxy {
+                Some((proxy, auth)) => {
+                    let mut stream = open(*proxy)?.connect(*proxy).await?;
+                    socks5::handshake(&mut stream, addrs[0], auth.as_ref()).await?;
+                    Ok::<TcpStream, Error>(stream)
+                }
+                None => {
+                    let open = &open;
+                    race(
+                        addrs,
+                        |addr| async move { Ok(open(addr)?.connect(addr).await?) },
+                    )
+                    .await
+                }
+            }
+        };
 
         let stream = if let Some(timeout) = self.connect_timeout {
             tokio::time::timeout(timeout, connect_op)
@@ -229,10 +803,513 @@ impl TcpTransportBuilder {
             connect_op.await?
         };
 
-        Ok(TcpTransport {
-            stream,
+        if let Some(linger) = self.linger {
+            SockRef::from(&stream).set_linger(linger)?;
+        }
+        if self.nodelay {
+            stream.set_nodelay(true)?;
+        }
+
+        let mut inner = StreamTransport::with_config(stream, self.frame);
+        inner.set_send_timeout(self.send_timeout);
+        inner.set_receive_timeout(self.receive_timeout);
+        inner.set_receive_inactivity_timeout(self.receive_inactivity_timeout);
+        inner.set_memory_budget(self.memory_budget);
+        inner.set_max_frames(self.max_frames);
+        inner.set_min_receive_rate(self.min_receive_rate);
+        inner.set_write_batch(self.write_batch);
+        inner.set_receive_ring(self.receive_ring);
+        Ok(TcpTransport { inner })
+    }
+}
+
+/// Builder for configuring a TCP listener
+///
+/// Per-connection settings (timeouts, frame format, `TCP_NODELAY`) are
+/// applied to each transport as it is accepted. They are only defaults: setters called on an
+/// accepted transport take precedence from then on.
+#[derive(Default)]
+pub struct TcpTransportListenerBuilder {
+    send_timeout: Option<Duration>,
+    receive_timeout: Option<Duration>,
+    memory_budget: Option<Arc<MemoryBudget>>,
+    max_frames: Option<usize>,
+    min_receive_rate: Option<u64>,
+    frame: FrameConfig,
+    on_accept: Option<AcceptFilter>,
+    reuse_address: Option<bool>,
+    backlog: Option<u32>,
+    nodelay: bool,
+    buffers: SocketBuffers,
+    fastopen: bool,
+}
+
+impl TcpTransportListenerBuilder {
+    /// Create a new builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the send timeout of every accepted transport
+    pub fn send_timeout(mut self, timeout: Duration) -> Self {
+        self.send_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the receive timeout of every accepted transport
+    ///
+    /// Keeps a client that connects and never sends from holding a handler
+    /// forever.
+    pub fn receive_timeout(mut self, timeout: Duration) -> Self {
+        self.receive_timeout = Some(timeout);
+        self
+    }
+
+    /// Reserve received payloads of every accepted transport against `budget`
+    ///
+    /// Gives the server a ceiling on receive buffer memory across all its
+    /// connections; see [`MemoryBudget`].
+    pub fn memory_budget(mut self, budget: Arc<MemoryBudget>) -> Self {
+        self.memory_budget = Some(budget);
+        self
+    }
+
+    /// Limit every accepted transport to `max` received data frames
+    ///
+    /// A cheap guard for endpoints with a known interaction pattern: a client
+    /// that keeps sending gets its connection closed and the handler's
+    /// `receive` fails with "frame limit exceeded". Unlimited by default.
+    pub fn max_frames(mut self, max: usize) -> Self {
+        self.max_frames = Some(max);
+        self
+    }
+
+    /// Require every accepted transport to receive payloads at `bytes_per_sec` or faster
+    ///
+    /// Slowloris protection: a client that sends a length prefix and then
+    /// trickles the payload has its connection closed and the handler's
+    /// `receive` fails with [`Error::ReceiveTooSlow`], without having to
+    /// lower the receive timeout that legitimate large frames need. See
+    /// [`StreamTransport::set_min_receive_rate`]. Unlimited by default.
+    pub fn min_receive_rate(mut self, bytes_per_sec: u64) -> Self {
+        self.min_receive_rate = Some(bytes_per_sec);
+        self
+    }
+
+    /// Carry a fixed-length metadata header of `len` bytes before every payload
+    ///
+    /// Applies to every accepted transport; see
+    /// [`TcpTransportBuilder::header_len`]. Clients must use the same length.
+    pub fn header_len(mut self, len: usize) -> Self {
+        self.frame.header_len = len;
+        self
+    }
+
+    /// Prefix every frame of accepted transports with a type byte
+    ///
+    /// Lets handlers answer pings and exchange control frames; clients must
+    /// enable it too.
+    pub fn frame_types(mut self, enabled: bool) -> Self {
+        self.frame.frame_types = enabled;
+        self
+    }
+
+    /// Number every frame of accepted transports and check the numbers received
+    ///
+    /// See [`FrameConfig::sequence_numbers`]; clients must enable it too.
+    pub fn sequence_numbers(mut self, enabled: bool) -> Self {
+        self.frame.sequence_numbers = enabled;
+        self
+    }
+
+    /// Set the overall frame layout of accepted transports
+    ///
+    /// See [`TcpTransportBuilder::framing_mode`].
+    pub fn framing_mode(mut self, mode: FramingMode) -> Self {
+        self.frame.mode = mode;
+        if mode == FramingMode::GrpcLengthPrefixed {
+            self.frame.header_len = 1;
+        }
+        self
+    }
+
+    /// Set the byte order of the frame length prefix of accepted transports
+    ///
+    /// Defaults to big-endian; clients must agree.
+    pub fn length_endian(mut self, endian: Endian) -> Self {
+        self.frame.length_endian = endian;
+        self
+    }
+
+    /// Set the width of the frame length prefix of accepted transports
+    ///
+    /// Defaults to 4 bytes; clients must agree.
+    pub fn length_width(mut self, width: PrefixWidth) -> Self {
+        self.frame.length_width = width;
+        self
+    }
+
+    /// Protect the length prefix of accepted transports with a checksum
+    ///
+    /// See [`FrameConfig::length_checksum`]; clients must agree.
+    pub fn length_checksum(mut self, enabled: bool) -> Self {
+        self.frame.length_checksum = enabled;
+        self
+    }
+
+    /// Write `marker` before every frame of accepted transports and check it on receive
+    ///
+    /// Clients must use the same marker.
+    pub fn sync_marker(mut self, marker: [u8; 4]) -> Self {
+        self.frame.sync_marker = Some(marker);
+        self
+    }
+
+    /// Skip corrupt frames on accepted transports by scanning ahead for the next sync marker
+    ///
+    /// Only takes effect together with [`sync_marker`](Self::sync_marker);
+    /// see [`FrameConfig::resync`].
+    pub fn resync_on_corruption(mut self, enabled: bool) -> Self {
+        self.frame.resync = enabled;
+        self
+    }
+
+    /// Run `callback` with the peer address of every incoming connection
+    ///
+    /// Returning `false` closes the connection immediately and `accept` keeps
+    /// waiting for the next one, so allowlists and per-IP limits can be
+    /// enforced before a handler ever sees the connection. The callback runs
+    /// inside `accept` and should not block.
+    pub fn on_accept<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&SocketAddr) -> bool + Send + Sync + 'static,
+    {
+        self.on_accept = Some(Box::new(callback));
+        self
+    }
+
+    /// Set SO_REUSEADDR on the listening socket
+    ///
+    /// Defaults to enabled on Unix and disabled on Windows, matching tokio.
+    pub fn reuse_address(mut self, enabled: bool) -> Self {
+        self.reuse_address = Some(enabled);
+        self
+    }
+
+    /// Set the maximum number of pending connections queued by the kernel
+    ///
+    /// Defaults to 1024; the OS may cap it lower.
+    pub fn backlog(mut self, backlog: u32) -> Self {
+        self.backlog = Some(backlog);
+        self
+    }
+
+    /// Set SO_SNDBUF for accepted connections
+    ///
+    /// Applied to the listening socket, which accepted sockets inherit it from.
+    pub fn send_buffer_size(mut self, size: usize) -> Self {
+        self.buffers.send = Some(size);
+        self
+    }
+
+    /// Set SO_RCVBUF for accepted connections
+    ///
+    /// Applied to the listening socket before `listen`, so accepted sockets
+    /// inherit it and advertise a matching window scale.
+    pub fn recv_buffer_size(mut self, size: usize) -> Self {
+        self.buffers.recv = Some(size);
+        self
+    }
+
+    /// Accept data carried in the SYN from TCP Fast Open clients
+    ///
+    /// Linux only, and a no-op elsewhere or when the kernel lacks support.
+    /// Clients opt in with [`TcpTransportBuilder::tcp_fastopen`].
+    pub fn tcp_fastopen(mut self, enabled: bool) -> Self {
+        self.fastopen = enabled;
+        self
+    }
+
+    /// Enable `TCP_NODELAY` on every accepted connection
+    pub fn nodelay_on_accept(mut self, enabled: bool) -> Self {
+        self.nodelay = enabled;
+        self
+    }
+
+    /// Bind to a local address with the configured settings
+    pub async fn bind(self, addr: SocketAddr) -> Result<TcpTransportListener> {
+        self.frame.check_delimited()?;
+        let socket = self.buffers.socket_for(addr)?;
+        socket.set_reuseaddr(self.reuse_address.unwrap_or(cfg!(unix)))?;
+        socket.bind(addr)?;
+        let backlog = self.backlog.unwrap_or(DEFAULT_BACKLOG);
+        if self.fastopen {
+            fastopen::enable_listen(&socket, backlog);
+        }
+        let listener = socket.listen(backlog)?;
+        Ok(self.finish(listener))
+    }
+
+    /// Wrap an already-listening standard library listener
+    ///
+    /// For sockets created elsewhere, e.g. passed in by systemd socket
+    /// activation. The listener is switched to nonblocking mode. Only the
+    /// per-connection settings apply; socket options such as the backlog,
+    /// buffer sizes or Fast Open are left as the socket's owner set them.
+    pub fn from_std(self, listener: std::net::TcpListener) -> Result<TcpTransportListener> {
+        self.frame.check_delimited()?;
+        listener.set_nonblocking(true)?;
+        Ok(self.finish(TcpListener::from_std(listener)?))
+    }
+
+    fn finish(self, listener: TcpListener) -> TcpTransportListener {
+        TcpTransportListener {
+            listener,
             send_timeout: self.send_timeout,
             receive_timeout: self.receive_timeout,
-        })
+            memory_budget: self.memory_budget,
+            max_frames: self.max_frames,
+            min_receive_rate: self.min_receive_rate,
+            frame: self.frame,
+            nodelay: self.nodelay,
+            on_accept: self.on_accept,
+        }
+    }
+}
+
+/// What [`TcpTransportBuilder`] connects to
+enum Target {
+    Addr(SocketAddr),
+    Host(String, u16),
+}
+
+impl Target {
+    /// Candidate addresses in the order to try them, never empty
+    async fn resolve(&self) -> Result<Vec<SocketAddr>> {
+        let (host, port) = match self {
+            Self::Addr(addr) => return Ok(vec![*addr]),
+            Self::Host(host, port) => (host, *port),
+        };
+        let (v6, v4): (Vec<_>, Vec<_>) = tokio::net::lookup_host((host.as_str(), port))
+            .await?
+            .partition(SocketAddr::is_ipv6);
+
+        // IPv6 first, then alternate families (RFC 8305 section 4)
+        let mut addrs = Vec::with_capacity(v6.len() + v4.len());
+        let (mut v6, mut v4) = (v6.into_iter(), v4.into_iter());
+        loop {
+            match (v6.next(), v4.next()) {
+                (None, None) => break,
+                (a, b) => addrs.extend(a.into_iter().chain(b)),
+            }
+        }
+        if addrs.is_empty() {
+            return Err(Error::Custom(format!("No addresses found for {}", host)));
+        }
+        Ok(addrs)
+    }
+}
+
+/// Delay before starting the next connection attempt, as recommended by RFC 8305
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Connect to whichever of `addrs` answers first
+///
+/// Attempts start in order, each one `ATTEMPT_DELAY` after the previous or as
+/// soon as it fails, and keep running side by side until one succeeds.
+/// Returns the last error if all of them fail.
+async fn race<F, Fut>(addrs: Vec<SocketAddr>, connect: F) -> Result<TcpStream>
+where
+    F: Fn(SocketAddr) -> Fut,
+    Fut: Future<Output = Result<TcpStream>>,
+{
+    let mut waiting = addrs.into_iter();
+    let mut attempts = FuturesUnordered::new();
+    let mut last_error = None;
+    loop {
+        if attempts.is_empty() {
+            match waiting.next() {
+                Some(addr) => attempts.push(connect(addr)),
+                None => {
+                    return Err(last_error.unwrap_or_else(|| {
+                        Error::Custom("No addresses to connect to".to_string())
+                    }))
+                }
+            }
+        }
+        tokio::select! {
+            Some(result) = attempts.next() => match result {
+                Ok(stream) => return Ok(stream),
+                Err(e) => {
+                    last_error = Some(e);
+                    if let Some(addr) = waiting.next() {
+                        attempts.push(connect(addr));
+                    }
+                }
+            },
+            _ = tokio::time::sleep(ATTEMPT_DELAY), if waiting.len() > 0 => {
+                attempts.extend(waiting.next().map(&connect));
+            }
+        }
+    }
+}
+
+/// SO_SNDBUF / SO_RCVBUF sizes to apply to a new socket
+#[derive(Debug, Clone, Copy, Default)]
+struct SocketBuffers {
+    send: Option<usize>,
+    recv: Option<usize>,
+}
+
+impl SocketBuffers {
+    /// Create a socket for `addr`'s address family with the buffer sizes applied
+    fn socket_for(&self, addr: SocketAddr) -> Result<TcpSocket> {
+        let socket = if addr.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+        let sock_ref = SockRef::from(&socket);
+        if let Some(size) = self.send {
+            sock_ref.set_send_buffer_size(size)?;
+        }
+        if let Some(size) = self.recv {
+            sock_ref.set_recv_buffer_size(size)?;
+        }
+        Ok(socket)
+    }
+}
+
+/// TCP Fast Open socket options
+///
+/// Best effort: a kernel without TFO rejects the option and the connection
+/// uses a normal handshake, so errors are ignored.
+#[cfg(target_os = "linux")]
+mod fastopen {
+    use std::os::fd::AsRawFd;
+
+    /// Carry the first write in the SYN (`TCP_FASTOPEN_CONNECT`)
+    pub(super) fn enable_connect(socket: &impl AsRawFd) {
+        set_option(socket, libc::TCP_FASTOPEN_CONNECT, 1);
+    }
+
+    /// Accept data in the SYN, with up to `queue_len` pending TFO handshakes
+    pub(super) fn enable_listen(socket: &impl AsRawFd, queue_len: u32) {
+        let queue_len = libc::c_int::try_from(queue_len).unwrap_or(libc::c_int::MAX);
+        set_option(socket, libc::TCP_FASTOPEN, queue_len);
+    }
+
+    fn set_option(socket: &impl AsRawFd, option: libc::c_int, value: libc::c_int) {
+        // SAFETY: the descriptor is a live socket borrowed for the call, and
+        // `value` is a c_int whose size is passed alongside it
+        unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                option,
+                (&value as *const libc::c_int).cast(),
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            );
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod fastopen {
+    pub(super) fn enable_connect<S>(_socket: &S) {}
+
+    pub(super) fn enable_listen<S>(_socket: &S, _queue_len: u32) {}
+}
+
+/// Kernel socket buffer queries via `ioctl`
+#[cfg(target_os = "linux")]
+mod sockbuf {
+    use std::io;
+    use std::os::fd::AsRawFd;
+
+    use crate::error::Result;
+
+    /// Bytes written but not yet acknowledged by the peer
+    pub(super) fn send_queued(socket: &impl AsRawFd) -> Result<usize> {
+        ioctl_int(socket, libc::TIOCOUTQ)
+    }
+
+    /// Bytes received but not yet read
+    pub(super) fn recv_pending(socket: &impl AsRawFd) -> Result<usize> {
+        ioctl_int(socket, libc::FIONREAD)
+    }
+
+    fn ioctl_int(socket: &impl AsRawFd, request: libc::Ioctl) -> Result<usize> {
+        let mut value: libc::c_int = 0;
+        // SAFETY: the descriptor is a live socket borrowed for the call, and
+        // both requests write a single c_int into `value`
+        let rc = unsafe { libc::ioctl(socket.as_raw_fd(), request, &mut value) };
+        if rc < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(value as usize)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sockbuf {
+    use crate::error::{Error, Result};
+
+    pub(super) fn send_queued<S>(_socket: &S) -> Result<usize> {
+        Err(unsupported())
+    }
+
+    pub(super) fn recv_pending<S>(_socket: &S) -> Result<usize> {
+        Err(unsupported())
+    }
+
+    fn unsupported() -> Error {
+        Error::Custom("Socket buffer queries are not supported on this platform".to_string())
+    }
+}
+
+/// Connection statistics from `TCP_INFO`
+#[cfg(target_os = "linux")]
+mod tcpinfo {
+    use std::io;
+    use std::mem;
+    use std::os::fd::AsRawFd;
+    use std::time::Duration;
+
+    use crate::error::Result;
+
+    /// Smoothed round-trip time (`tcpi_rtt`)
+    pub(super) fn rtt(socket: &impl AsRawFd) -> Result<Duration> {
+        // SAFETY: tcp_info is plain old data, so all zeroes is a valid value
+        let mut info: libc::tcp_info = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<libc::tcp_info>() as libc::socklen_t;
+        // SAFETY: the descriptor is a live socket borrowed for the call, and
+        // `len` holds the size of `info`, which the kernel fills at most
+        let rc = unsafe {
+            libc::getsockopt(
+                socket.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                libc::TCP_INFO,
+                (&mut info as *mut libc::tcp_info).cast(),
+                &mut len,
+            )
+        };
+        if rc < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(Duration::from_micros(info.tcpi_rtt.into()))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod tcpinfo {
+    use std::time::Duration;
+
+    use crate::error::{Error, Result};
+
+    pub(super) fn rtt<S>(_socket: &S) -> Result<Duration> {
+        Err(Error::Custom(
+            "TCP_INFO is not supported on this platform".to_string(),
+        ))
     }
 }
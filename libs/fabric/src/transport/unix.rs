@@ -1,19 +1,34 @@
+use std::mem::MaybeUninit;
+use std::os::fd::{OwnedFd, RawFd};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use futures::stream::{self, Stream};
+use socket2::SockRef;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, Interest};
+use tokio::net::unix::{SocketAddr, UCred};
 use tokio::net::{UnixListener, UnixStream};
 
 use crate::error::{Error, Result};
-use crate::transport::Transport;
+use crate::transport::budget::MemoryBudget;
+use crate::transport::framing::{Endian, FrameConfig, FrameType, FramingMode, PrefixWidth};
+use crate::transport::stream::StreamTransport;
+use crate::transport::{with_timeout, ConnectionState, Transport};
+
+/// Admission check run on each accepted peer address
+type AcceptFilter = Box<dyn Fn(&SocketAddr) -> bool + Send + Sync>;
 
 /// Unix domain socket transport with length-prefix framing
 ///
-/// Messages are sent with a 4-byte big-endian length prefix
+/// Messages are sent with a 4-byte big-endian length prefix, see [`framing`](crate::transport::framing)
+///
+/// Call [`Transport::close`] to end the connection in an orderly way.
+/// Dropping the transport closes the socket without a shutdown, which can
+/// discard data the peer has not read yet.
 pub struct UnixTransport {
-    stream: UnixStream,
-    send_timeout: Option<Duration>,
-    receive_timeout: Option<Duration>,
+    inner: StreamTransport<UnixStream>,
 }
 
 impl UnixTransport {
@@ -39,81 +54,316 @@ impl UnixTransport {
     /// Create from an existing UnixStream
     pub fn from_stream(stream: UnixStream) -> Self {
         Self {
-            stream,
-            send_timeout: None,
-            receive_timeout: None,
+            inner: StreamTransport::new(stream),
         }
     }
-}
 
-#[async_trait::async_trait]
-impl Transport for UnixTransport {
-    async fn send(&mut self, bytes: &[u8]) -> Result<()> {
-        let send_op = async {
-            // Write length prefix (4 bytes, big-endian)
-            let len = bytes.len() as u32;
-            self.stream.write_u32(len).await?;
+    /// Get the send timeout
+    pub fn send_timeout(&self) -> Option<Duration> {
+        self.inner.send_timeout()
+    }
 
-            // Write data
-            self.stream.write_all(bytes).await?;
-            self.stream.flush().await?;
+    /// Set the send timeout, or `None` to disable it
+    ///
+    /// Takes effect from the next `send`.
+    pub fn set_send_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_send_timeout(timeout);
+    }
 
-            Ok::<(), Error>(())
-        };
+    /// Get the receive timeout
+    pub fn receive_timeout(&self) -> Option<Duration> {
+        self.inner.receive_timeout()
+    }
 
-        if let Some(timeout) = self.send_timeout {
-            tokio::time::timeout(timeout, send_op)
-                .await
-                .map_err(|_| Error::Custom("Send timeout exceeded".to_string()))?
+    /// Set the receive timeout, or `None` to disable it
+    ///
+    /// Takes effect from the next `receive`.
+    pub fn set_receive_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_receive_timeout(timeout);
+    }
+
+    /// Get the receive inactivity timeout
+    pub fn receive_inactivity_timeout(&self) -> Option<Duration> {
+        self.inner.receive_inactivity_timeout()
+    }
+
+    /// Set the receive inactivity timeout, or `None` to disable it
+    ///
+    /// See [`StreamTransport::set_receive_inactivity_timeout`].
+    pub fn set_receive_inactivity_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_receive_inactivity_timeout(timeout);
+    }
+
+    /// Get a reference to the underlying UnixStream
+    pub fn get_ref(&self) -> &UnixStream {
+        self.inner.get_ref()
+    }
+
+    /// Unwrap the underlying UnixStream
+    ///
+    /// Useful for handing a connection set up by the builder (connect
+    /// timeout) to a library that speaks its own
+    /// protocol over a raw `AsyncRead + AsyncWrite` stream. From then on
+    /// framing is bypassed and send/receive timeouts no longer apply. Frames
+    /// held back by write batching are written out first. Fails if
+    /// [`receive_ready`](Transport::receive_ready) or
+    /// [`readable`](Transport::readable) has buffered bytes, as those would
+    /// be lost; plain receives never leave any behind.
+    pub async fn into_inner(self) -> Result<UnixStream> {
+        self.inner.into_inner().await
+    }
+
+    /// Get the credentials of the process on the other end (SO_PEERCRED)
+    ///
+    /// Reflects the peer at the time the connection was established.
+    pub fn peer_cred(&self) -> Result<UCred> {
+        self.get_ref().peer_cred().map_err(Into::into)
+    }
+
+    /// Check that the peer's uid is in `allowed_uids`
+    ///
+    /// Fails with [`Error::Unauthorized`] otherwise, e.g. to let only root
+    /// and a service user connect. Call it right after `accept`.
+    pub fn authorize(&self, allowed_uids: &[u32]) -> Result<()> {
+        let uid = self.peer_cred()?.uid();
+        if allowed_uids.contains(&uid) {
+            Ok(())
         } else {
-            send_op.await
+            Err(Error::Unauthorized(format!(
+                "Peer uid {} is not allowed",
+                uid
+            )))
         }
     }
 
-    async fn receive(&mut self) -> Result<Vec<u8>> {
-        let receive_op = async {
-            // Read length prefix
-            let len = self.stream.read_u32().await.map_err(|e| {
-                if e.kind() == std::io::ErrorKind::UnexpectedEof {
-                    Error::ConnectionClosed
-                } else {
-                    e.into()
-                }
-            })? as usize;
-
-            // Validate length (max 100MB to prevent DOS)
-            if len > 100 * 1024 * 1024 {
-                return Err(Error::InvalidFrame(format!(
-                    "Message too large: {} bytes",
-                    len
-                )));
+    /// Send a payload with open file descriptors attached (SCM_RIGHTS)
+    ///
+    /// The peer receives duplicates of `fds` with
+    /// [`receive_with_fds`](Self::receive_with_fds); ours stay open. At most
+    /// 253 descriptors can be sent per frame. A peer reading the frame with a
+    /// plain `receive` gets the payload and the descriptors are closed.
+    pub async fn send_with_fds(&mut self, bytes: &[u8], fds: &[RawFd]) -> Result<()> {
+        if fds.len() > scm::MAX_FDS {
+            return Err(Error::Custom(format!(
+                "Cannot send {} file descriptors, the limit is {}",
+                fds.len(),
+                scm::MAX_FDS
+            )));
+        }
+
+        let config = self.inner.frame_config().clone();
+        self.inner
+            .check_outgoing(FrameType::Data, config.header_len, &[bytes])?;
+        let header = vec![0u8; config.header_len];
+        let sequence = self.inner.send_sequence();
+        let mut frame = Vec::new();
+        config
+            .write_numbered(&mut frame, FrameType::Data, &header, sequence, bytes)
+            .await?;
+
+        // The descriptors ride on the first byte of the frame, after anything batched
+        let result = match self.inner.flush_writes().await {
+            Ok(()) => {
+                let timeout = self.send_timeout();
+                let send_op = async {
+                    let stream = self.inner.get_ref();
+                    let sent = stream
+                        .async_io(Interest::WRITABLE, || scm::send(stream, &frame, fds))
+                        .await?;
+                    self.inner.get_mut().write_all(&frame[sent..]).await?;
+                    Ok(())
+                };
+                let result = with_timeout(timeout, "Send", send_op).await;
+                self.inner.number_sent(result)
             }
+            Err(e) => Err(e),
+        };
+        self.inner.track(result)
+    }
 
-            // Read data
-            let mut buf = vec![0u8; len];
-            self.stream.read_exact(&mut buf).await.map_err(|e| {
-                if e.kind() == std::io::ErrorKind::UnexpectedEof {
-                    Error::ConnectionClosed
-                } else {
-                    e.into()
+    /// Receive a payload together with any file descriptors attached to it
+    ///
+    /// Descriptors are returned owned, so they are closed when dropped, and
+    /// are created close-on-exec. Timeouts, the memory budget and the frame
+    /// limit apply as they do to [`receive`](Transport::receive), and pings
+    /// are answered along the way.
+    ///
+    /// Bytes already buffered by [`receive_ready`](Transport::receive_ready)
+    /// were read without their descriptors, so the frame they start is
+    /// returned with none.
+    pub async fn receive_with_fds(&mut self) -> Result<(Vec<u8>, Vec<OwnedFd>)> {
+        if self.inner.has_read_ahead() {
+            return Ok((self.inner.receive().await?, Vec::new()));
+        }
+        let timeout = self.receive_timeout();
+        let inactivity_timeout = self.inner.receive_inactivity_timeout();
+        let receive_op = async {
+            self.inner.flush_writes().await?;
+            let mut fds = Vec::new();
+            let mut payload = Vec::new();
+            loop {
+                // Read the first byte with recvmsg to collect its descriptors
+                let mut first = [0u8; 1];
+                let stream = self.inner.get_ref();
+                let recv_op = async {
+                    Ok(stream
+                        .async_io(Interest::READABLE, || {
+                            scm::recv(stream, &mut first, &mut fds)
+                        })
+                        .await?)
+                };
+                if with_timeout(inactivity_timeout, "Receive inactivity", recv_op).await? == 0 {
+                    return Err(Error::ConnectionClosed);
                 }
-            })?;
 
-            Ok::<Vec<u8>, Error>(buf)
+                // The rest of the frame is read like any other
+                self.inner.push_read_ahead(&first);
+                if self.inner.receive_one_into(&mut payload).await?.is_some() {
+                    return Ok((payload, fds));
+                }
+            }
         };
+        let result = with_timeout(timeout, "Receive", receive_op).await;
+        self.inner.track(result)
+    }
 
-        if let Some(timeout) = self.receive_timeout {
-            tokio::time::timeout(timeout, receive_op)
-                .await
-                .map_err(|_| Error::Custom("Receive timeout exceeded".to_string()))?
-        } else {
-            receive_op.await
-        }
+    /// Send a payload with an out-of-band metadata header
+    ///
+    /// `N` must match the header length configured on the builder.
+    pub async fn send_with_header<const N: usize>(
+        &mut self,
+        header: &[u8; N],
+        payload: &[u8],
+    ) -> Result<()> {
+        self.inner.send_with_header(header, payload).await
+    }
+
+    /// Receive a payload together with its metadata header
+    ///
+    /// `N` must match the header length configured on the builder.
+    pub async fn receive_with_header<const N: usize>(&mut self) -> Result<([u8; N], Vec<u8>)> {
+        self.inner.receive_with_header().await
+    }
+
+    /// Give [`receive_borrowed`](Self::receive_borrowed) a fixed buffer of `capacity` bytes
+    ///
+    /// See [`StreamTransport::set_receive_ring`].
+    pub fn set_receive_ring(&mut self, capacity: Option<usize>) {
+        self.inner.set_receive_ring(capacity);
+    }
+
+    /// Number of frames too large for the receive ring, which had to allocate
+    pub fn oversized_receives(&self) -> u64 {
+        self.inner.oversized_receives()
+    }
+
+    /// Receive the next payload without allocating, borrowed until the next call
+    ///
+    /// See [`StreamTransport::receive_borrowed`].
+    pub async fn receive_borrowed(&mut self) -> Result<&[u8]> {
+        self.inner.receive_borrowed().await
+    }
+}
+
+impl std::fmt::Debug for UnixTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stream = self.inner.get_ref();
+        f.debug_struct("UnixTransport")
+            .field("peer_addr", &stream.peer_addr().ok())
+            .field("local_addr", &stream.local_addr().ok())
+            .field("send_timeout", &self.send_timeout())
+            .field("receive_timeout", &self.receive_timeout())
+            .field("state", &self.inner.state())
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for UnixTransport {
+    async fn send(&mut self, bytes: &[u8]) -> Result<()> {
+        self.inner.send(bytes).await
+    }
+
+    async fn send_vectored(&mut self, parts: &[&[u8]]) -> Result<()> {
+        self.inner.send_vectored(parts).await
+    }
+
+    async fn receive(&mut self) -> Result<Vec<u8>> {
+        self.inner.receive().await
+    }
+
+    async fn receive_into(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        self.inner.receive_into(buf).await
+    }
+
+    async fn receive_ready(&mut self) -> Result<Vec<Vec<u8>>> {
+        self.inner.receive_ready().await
+    }
+
+    async fn readable(&mut self) -> Result<()> {
+        self.inner.readable().await
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.inner.flush().await
     }
 
     async fn close(&mut self) -> Result<()> {
-        self.stream.shutdown().await?;
-        Ok(())
+        self.inner.close().await
+    }
+
+    fn state(&self) -> ConnectionState {
+        match self.inner.state() {
+            ConnectionState::Connected if self.is_closed() => ConnectionState::Closed,
+            state => state,
+        }
+    }
+
+    fn describe(&self) -> Option<&dyn std::fmt::Debug> {
+        Some(self)
+    }
+
+    fn frame_overhead(&self) -> usize {
+        self.inner.frame_overhead()
+    }
+
+    fn is_closed(&self) -> bool {
+        if self.inner.state() != ConnectionState::Connected {
+            return true;
+        }
+
+        // Peek a single byte without blocking: EOF means the peer closed,
+        // WouldBlock means the connection is idle but alive
+        let mut buf = [MaybeUninit::<u8>::uninit(); 1];
+        match SockRef::from(self.inner.get_ref()).peek(&mut buf) {
+            Ok(0) => true,
+            Ok(_) => false,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => false,
+            Err(_) => true,
+        }
+    }
+
+    async fn send_control(&mut self, frame_type: FrameType, payload: &[u8]) -> Result<()> {
+        self.inner.send_control(frame_type, payload).await
+    }
+
+    async fn receive_frame(&mut self) -> Result<(FrameType, Vec<u8>)> {
+        self.inner.receive_frame().await
+    }
+
+    async fn send_from_reader(
+        &mut self,
+        reader: &mut (dyn AsyncRead + Unpin + Send),
+        len: usize,
+    ) -> Result<()> {
+        self.inner.send_from_reader(reader, len).await
+    }
+
+    async fn receive_to_writer(
+        &mut self,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<usize> {
+        self.inner.receive_to_writer(writer).await
     }
 }
 
@@ -121,26 +371,63 @@ impl Transport for UnixTransport {
 pub struct UnixTransportListener {
     listener: UnixListener,
     path: PathBuf,
+    send_timeout: Option<Duration>,
+    receive_timeout: Option<Duration>,
+    memory_budget: Option<Arc<MemoryBudget>>,
+    max_frames: Option<usize>,
+    min_receive_rate: Option<u64>,
+    frame: FrameConfig,
+    on_accept: Option<AcceptFilter>,
+    /// Device and inode of the bound socket file, until it is removed
+    socket_file: Option<(u64, u64)>,
 }
 
 impl UnixTransportListener {
     /// Bind to a Unix socket path
+    ///
+    /// A stale socket file left by a dead listener is replaced; one another
+    /// listener is still using makes binding fail. See
+    /// [`UnixTransportListenerBuilder::on_existing`].
     pub async fn bind(path: impl AsRef<Path>) -> Result<Self> {
-        let path = path.as_ref().to_path_buf();
+        Self::builder().bind(path).await
+    }
 
-        // Remove existing socket file if it exists
-        if path.exists() {
-            std::fs::remove_file(&path)?;
-        }
+    /// Wrap an already-listening standard library listener with default settings
+    ///
+    /// Shorthand for `UnixTransportListener::builder().from_std(listener)`.
+    pub fn from_std(listener: std::os::unix::net::UnixListener) -> Result<Self> {
+        Self::builder().from_std(listener)
+    }
 
-        let listener = UnixListener::bind(&path)?;
-        Ok(Self { listener, path })
+    /// Create a builder for configuring the listener
+    pub fn builder() -> UnixTransportListenerBuilder {
+        UnixTransportListenerBuilder::new()
     }
 
     /// Accept an incoming connection
+    ///
+    /// The transport starts with the listener's default timeouts and frame format.
+    /// Connections refused by the [`on_accept`](UnixTransportListenerBuilder::on_accept)
+    /// callback are closed and skipped.
     pub async fn accept(&self) -> Result<UnixTransport> {
-        let (stream, _) = self.listener.accept().await?;
-        Ok(UnixTransport::from_stream(stream))
+        let stream = loop {
+            let (stream, addr) = self.listener.accept().await?;
+            match &self.on_accept {
+                Some(admit) if !admit(&addr) => drop(stream),
+                _ => break stream,
+            }
+        };
+        let mut transport = UnixTransport {
+            inner: StreamTransport::with_config(stream, self.frame.clone()),
+        };
+        transport.set_send_timeout(self.send_timeout);
+        transport.set_receive_timeout(self.receive_timeout);
+        transport
+            .inner
+            .set_memory_budget(self.memory_budget.clone());
+        transport.inner.set_max_frames(self.max_frames);
+        transport.inner.set_min_receive_rate(self.min_receive_rate);
+        Ok(transport)
     }
 
     /// Get the path this listener is bound to
@@ -148,17 +435,63 @@ impl UnixTransportListener {
         &self.path
     }
 
+    /// Turn the listener into a stream of accepted connections
+    ///
+    /// Accept errors are yielded as items rather than ending the stream, so
+    /// transient failures can be skipped. The stream never terminates on its own.
+    pub fn incoming(self) -> impl Stream<Item = Result<UnixTransport>> {
+        stream::unfold(self, |listener| async move {
+            let accepted = listener.accept().await;
+            Some((accepted, listener))
+        })
+    }
+
     /// Close the listener and remove the socket file
+    ///
+    /// Prefer this over dropping the listener: the file is gone once it
+    /// returns, and removal errors are reported.
     pub async fn close(&mut self) -> Result<()> {
-        std::fs::remove_file(&self.path)?;
+        if self.socket_file.take().is_some() {
+            tokio::fs::remove_file(&self.path).await?;
+        }
         Ok(())
     }
 }
 
 impl Drop for UnixTransportListener {
+    /// Remove the socket file unless [`close`](UnixTransportListener::close) already did
+    ///
+    /// Inside a runtime the removal runs on the blocking pool, so the file
+    /// may briefly outlive the listener. It is only removed if it is still
+    /// the socket this listener bound, so a listener re-bound to the same
+    /// path in the meantime keeps its file.
     fn drop(&mut self) {
-        // Clean up socket file on drop
-        let _ = std::fs::remove_file(&self.path);
+        let Some(socket_file) = self.socket_file.take() else {
+            return;
+        };
+        let path = std::mem::take(&mut self.path);
+        let remove = move || {
+            let unchanged = std::fs::symlink_metadata(&path)
+                .is_ok_and(|meta| (meta.dev(), meta.ino()) == socket_file);
+            if unchanged {
+                let _ = std::fs::remove_file(&path);
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => drop(handle.spawn_blocking(remove)),
+            Err(_) => remove(),
+        }
+    }
+}
+
+impl std::fmt::Debug for UnixTransportListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnixTransportListener")
+            .field("path", &self.path)
+            .field("send_timeout", &self.send_timeout)
+            .field("receive_timeout", &self.receive_timeout)
+            .field("on_accept", &self.on_accept.is_some())
+            .finish()
     }
 }
 
@@ -167,8 +500,7 @@ impl crate::transport::TransportListener for UnixTransportListener {
     type Transport = UnixTransport;
 
     async fn accept(&self) -> Result<Self::Transport> {
-        let (stream, _) = self.listener.accept().await?;
-        Ok(UnixTransport::from_stream(stream))
+        UnixTransportListener::accept(self).await
     }
 
     async fn close(&mut self) -> Result<()> {
@@ -183,6 +515,12 @@ pub struct UnixTransportBuilder {
     connect_timeout: Option<Duration>,
     send_timeout: Option<Duration>,
     receive_timeout: Option<Duration>,
+    receive_inactivity_timeout: Option<Duration>,
+    memory_budget: Option<Arc<MemoryBudget>>,
+    max_frames: Option<usize>,
+    min_receive_rate: Option<u64>,
+    frame: FrameConfig,
+    reject_self_connect: bool,
 }
 
 impl UnixTransportBuilder {
@@ -215,13 +553,144 @@ impl UnixTransportBuilder {
         self
     }
 
+    /// Fail a receive once no bytes have arrived for `timeout`
+    ///
+    /// The timer resets on every read, so large frames over slow links only
+    /// fail when the transfer actually stalls.
+    pub fn receive_inactivity_timeout(mut self, timeout: Duration) -> Self {
+        self.receive_inactivity_timeout = Some(timeout);
+        self
+    }
+
+    /// Reserve each received payload against a budget shared between connections
+    ///
+    /// Caps the memory all connections spend on receive buffers at once; see
+    /// [`MemoryBudget`].
+    pub fn memory_budget(mut self, budget: Arc<MemoryBudget>) -> Self {
+        self.memory_budget = Some(budget);
+        self
+    }
+
+    /// Fail `receive` with "frame limit exceeded" after `max` data frames
+    ///
+    /// The frame over the limit also closes the connection. Unlimited by
+    /// default.
+    pub fn max_frames(mut self, max: usize) -> Self {
+        self.max_frames = Some(max);
+        self
+    }
+
+    /// Fail `receive` if a payload arrives slower than `bytes_per_sec`
+    ///
+    /// The receive fails with [`Error::ReceiveTooSlow`]; see
+    /// [`StreamTransport::set_min_receive_rate`]. Unlimited by default.
+    pub fn min_receive_rate(mut self, bytes_per_sec: u64) -> Self {
+        self.min_receive_rate = Some(bytes_per_sec);
+        self
+    }
+
+    /// Carry a fixed-length metadata header of `len` bytes before every payload
+    ///
+    /// Use [`send_with_header`](UnixTransport::send_with_header) and
+    /// [`receive_with_header`](UnixTransport::receive_with_header) to access it; plain
+    /// `send` writes a zeroed header and `receive` discards it.
+    pub fn header_len(mut self, len: usize) -> Self {
+        self.frame.header_len = len;
+        self
+    }
+
+    /// Prefix every frame with a type byte so control frames can be sent
+    ///
+    /// Required for [`Channel::ping`](crate::Channel::ping); both peers must agree.
+    pub fn frame_types(mut self, enabled: bool) -> Self {
+        self.frame.frame_types = enabled;
+        self
+    }
+
+    /// Number every frame and fail `receive` when one is missing or out of order
+    ///
+    /// The receive fails with [`Error::SequenceGap`]. See
+    /// [`FrameConfig::sequence_numbers`]; both peers must agree.
+    pub fn sequence_numbers(mut self, enabled: bool) -> Self {
+        self.frame.sequence_numbers = enabled;
+        self
+    }
+
+    /// Set the overall frame layout
+    ///
+    /// [`FramingMode::GrpcLengthPrefixed`] speaks gRPC message framing and
+    /// also sets the header length to 1 for the compression flag, read and
+    /// written with [`send_with_header`](UnixTransport::send_with_header) and
+    /// [`receive_with_header`](UnixTransport::receive_with_header).
+    pub fn framing_mode(mut self, mode: FramingMode) -> Self {
+        self.frame.mode = mode;
+        if mode == FramingMode::GrpcLengthPrefixed {
+            self.frame.header_len = 1;
+        }
+        self
+    }
+
+    /// Set the byte order of the frame length prefix
+    ///
+    /// Defaults to big-endian; both peers must agree.
+    pub fn length_endian(mut self, endian: Endian) -> Self {
+        self.frame.length_endian = endian;
+        self
+    }
+
+    /// Set the width of the frame length prefix
+    ///
+    /// Defaults to 4 bytes; both peers must agree.
+    pub fn length_width(mut self, width: PrefixWidth) -> Self {
+        self.frame.length_width = width;
+        self
+    }
+
+    /// Protect the length prefix with a checksum
+    ///
+    /// Cheap insurance against a corrupted length; see
+    /// [`FrameConfig::length_checksum`]. Both peers must agree.
+    pub fn length_checksum(mut self, enabled: bool) -> Self {
+        self.frame.length_checksum = enabled;
+        self
+    }
+
+    /// Write `marker` before every frame and check it on receive
+    ///
+    /// Both peers must use the same marker.
+    pub fn sync_marker(mut self, marker: [u8; 4]) -> Self {
+        self.frame.sync_marker = Some(marker);
+        self
+    }
+
+    /// Skip corrupt frames by scanning ahead for the next sync marker
+    ///
+    /// Off by default. Only takes effect together with
+    /// [`sync_marker`](Self::sync_marker); see [`FrameConfig::resync`].
+    pub fn resync_on_corruption(mut self, enabled: bool) -> Self {
+        self.frame.resync = enabled;
+        self
+    }
+
+    /// Fail `connect` if the socket's listener is this process
+    ///
+    /// Guards against a misconfigured deploy pointing a process's client at
+    /// its own listening path, where both ends wait on each other. Compares
+    /// the listener's pid (SO_PEERCRED) with ours, so it also rejects
+    /// in-process loopback connections made on purpose; off by default.
+    pub fn reject_self_connect(mut self, enabled: bool) -> Self {
+        self.reject_self_connect = enabled;
+        self
+    }
+
     /// Connect with the configured settings
     pub async fn connect(self) -> Result<UnixTransport> {
         let path = self
             .path
             .ok_or_else(|| Error::Custom("Path not set".to_string()))?;
+        self.frame.check_delimited()?;
 
-        let connect_op = UnixStream::connect(path);
+        let connect_op = UnixStream::connect(&path);
 
         let stream = if let Some(timeout) = self.connect_timeout {
             tokio::time::timeout(timeout, connect_op)
@@ -231,10 +700,412 @@ impl UnixTransportBuilder {
             connect_op.await?
         };
 
-        Ok(UnixTransport {
-            stream,
+        if self.reject_self_connect && stream.peer_cred()?.pid() == Some(std::process::id() as i32)
+        {
+            return Err(Error::Custom(format!(
+                "Refusing to connect to {}: it is served by this process",
+                path.display()
+            )));
+        }
+
+        let mut inner = StreamTransport::with_config(stream, self.frame);
+        inner.set_send_timeout(self.send_timeout);
+        inner.set_receive_timeout(self.receive_timeout);
+        inner.set_receive_inactivity_timeout(self.receive_inactivity_timeout);
+        inner.set_memory_budget(self.memory_budget);
+        inner.set_max_frames(self.max_frames);
+        inner.set_min_receive_rate(self.min_receive_rate);
+        Ok(UnixTransport { inner })
+    }
+}
+
+/// What binding a Unix listener does when its socket path already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExistingSocket {
+    /// Remove whatever is at the path, even a socket another process is listening on
+    Remove,
+    /// Fail to bind
+    Fail,
+    /// Remove a stale socket nobody listens on, fail if it is in use or not a socket
+    ///
+    /// Probes the socket with a connect; a refused connection means its
+    /// listener is gone. A live listener accepts the probe as a connection
+    /// that closes without sending anything.
+    #[default]
+    FailIfInUse,
+}
+
+/// Builder for configuring a Unix socket listener
+#[derive(Default)]
+pub struct UnixTransportListenerBuilder {
+    send_timeout: Option<Duration>,
+    receive_timeout: Option<Duration>,
+    memory_budget: Option<Arc<MemoryBudget>>,
+    max_frames: Option<usize>,
+    min_receive_rate: Option<u64>,
+    frame: FrameConfig,
+    on_accept: Option<AcceptFilter>,
+    on_existing: ExistingSocket,
+}
+
+impl UnixTransportListenerBuilder {
+    /// Create a new builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the send timeout of every accepted transport
+    pub fn send_timeout(mut self, timeout: Duration) -> Self {
+        self.send_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the receive timeout of every accepted transport
+    ///
+    /// Keeps a client that connects and never sends from holding a handler
+    /// forever.
+    pub fn receive_timeout(mut self, timeout: Duration) -> Self {
+        self.receive_timeout = Some(timeout);
+        self
+    }
+
+    /// Reserve received payloads of every accepted transport against `budget`
+    ///
+    /// Gives the server a ceiling on receive buffer memory across all its
+    /// connections; see [`MemoryBudget`].
+    pub fn memory_budget(mut self, budget: Arc<MemoryBudget>) -> Self {
+        self.memory_budget = Some(budget);
+        self
+    }
+
+    /// Limit every accepted transport to `max` received data frames
+    ///
+    /// A cheap guard for endpoints with a known interaction pattern: a client
+    /// that keeps sending gets its connection closed and the handler's
+    /// `receive` fails with "frame limit exceeded". Unlimited by default.
+    pub fn max_frames(mut self, max: usize) -> Self {
+        self.max_frames = Some(max);
+        self
+    }
+
+    /// Require every accepted transport to receive payloads at `bytes_per_sec` or faster
+    ///
+    /// Slowloris protection: a client that sends a length prefix and then
+    /// trickles the payload has its connection closed and the handler's
+    /// `receive` fails with [`Error::ReceiveTooSlow`], without having to
+    /// lower the receive timeout that legitimate large frames need. See
+    /// [`StreamTransport::set_min_receive_rate`]. Unlimited by default.
+    pub fn min_receive_rate(mut self, bytes_per_sec: u64) -> Self {
+        self.min_receive_rate = Some(bytes_per_sec);
+        self
+    }
+
+    /// Carry a fixed-length metadata header of `len` bytes before every payload
+    ///
+    /// Applies to every accepted transport; see
+    /// [`UnixTransportBuilder::header_len`]. Clients must use the same length.
+    pub fn header_len(mut self, len: usize) -> Self {
+        self.frame.header_len = len;
+        self
+    }
+
+    /// Prefix every frame of accepted transports with a type byte
+    ///
+    /// Lets handlers answer pings and exchange control frames; clients must
+    /// enable it too.
+    pub fn frame_types(mut self, enabled: bool) -> Self {
+        self.frame.frame_types = enabled;
+        self
+    }
+
+    /// Number every frame of accepted transports and check the numbers received
+    ///
+    /// See [`FrameConfig::sequence_numbers`]; clients must enable it too.
+    pub fn sequence_numbers(mut self, enabled: bool) -> Self {
+        self.frame.sequence_numbers = enabled;
+        self
+    }
+
+    /// Set the overall frame layout of accepted transports
+    ///
+    /// See [`UnixTransportBuilder::framing_mode`].
+    pub fn framing_mode(mut self, mode: FramingMode) -> Self {
+        self.frame.mode = mode;
+        if mode == FramingMode::GrpcLengthPrefixed {
+            self.frame.header_len = 1;
+        }
+        self
+    }
+
+    /// Set the byte order of the frame length prefix of accepted transports
+    ///
+    /// Defaults to big-endian; clients must agree.
+    pub fn length_endian(mut self, endian: Endian) -> Self {
+        self.frame.length_endian = endian;
+        self
+    }
+
+    /// Set the width of the frame length prefix of accepted transports
+    ///
+    /// Defaults to 4 bytes; clients must agree.
+    pub fn length_width(mut self, width: PrefixWidth) -> Self {
+        self.frame.length_width = width;
+        self
+    }
+
+    /// Protect the length prefix of accepted transports with a checksum
+    ///
+    /// See [`FrameConfig::length_checksum`]; clients must agree.
+    pub fn length_checksum(mut self, enabled: bool) -> Self {
+        self.frame.length_checksum = enabled;
+        self
+    }
+
+    /// Write `marker` before every frame of accepted transports and check it on receive
+    ///
+    /// Clients must use the same marker.
+    pub fn sync_marker(mut self, marker: [u8; 4]) -> Self {
+        self.frame.sync_marker = Some(marker);
+        self
+    }
+
+    /// Skip corrupt frames on accepted transports by scanning ahead for the next sync marker
+    ///
+    /// Only takes effect together with [`sync_marker`](Self::sync_marker);
+    /// see [`FrameConfig::resync`].
+    pub fn resync_on_corruption(mut self, enabled: bool) -> Self {
+        self.frame.resync = enabled;
+        self
+    }
+
+    /// Run `callback` with the peer address of every incoming connection
+    ///
+    /// Returning `false` closes the connection immediately and `accept` keeps
+    /// waiting for the next one. Clients rarely bind their socket, so the
+    /// address is usually unnamed; use [`UnixTransport::authorize`] on the
+    /// accepted transport for identity checks instead.
+    pub fn on_accept<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&SocketAddr) -> bool + Send + Sync + 'static,
+    {
+        self.on_accept = Some(Box::new(callback));
+        self
+    }
+
+    /// Set what [`bind`](Self::bind) does when the socket path already exists
+    ///
+    /// Defaults to [`ExistingSocket::FailIfInUse`], which cleans up after a
+    /// crashed instance but keeps a second instance from taking over the
+    /// socket of a running one.
+    pub fn on_existing(mut self, policy: ExistingSocket) -> Self {
+        self.on_existing = policy;
+        self
+    }
+
+    /// Bind to a Unix socket path with the configured settings
+    ///
+    /// An existing file at `path` is handled according to
+    /// [`on_existing`](Self::on_existing); when it is kept, binding fails
+    /// with an `AddrInUse` I/O error.
+    pub async fn bind(self, path: impl AsRef<Path>) -> Result<UnixTransportListener> {
+        self.frame.check_delimited()?;
+        let path = path.as_ref().to_path_buf();
+
+        match self.on_existing {
+            ExistingSocket::Remove => {
+                if tokio::fs::try_exists(&path).await? {
+                    tokio::fs::remove_file(&path).await?;
+                }
+            }
+            ExistingSocket::Fail => {}
+            ExistingSocket::FailIfInUse => {
+                let is_socket = tokio::fs::symlink_metadata(&path)
+                    .await
+                    .is_ok_and(|meta| meta.file_type().is_socket());
+                if is_socket {
+                    let probe = UnixStream::connect(&path).await;
+                    if probe.is_err_and(|e| e.kind() == std::io::ErrorKind::ConnectionRefused) {
+                        tokio::fs::remove_file(&path).await?;
+                    }
+                }
+            }
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        let meta = tokio::fs::symlink_metadata(&path).await?;
+        Ok(self.finish(listener, path, Some((meta.dev(), meta.ino()))))
+    }
+
+    /// Wrap an already-listening standard library listener
+    ///
+    /// For sockets created elsewhere, e.g. passed in by systemd socket
+    /// activation. The listener is switched to nonblocking mode. Its socket
+    /// file belongs to whoever created it, so neither `close` nor drop
+    /// removes it.
+    pub fn from_std(
+        self,
+        listener: std::os::unix::net::UnixListener,
+    ) -> Result<UnixTransportListener> {
+        self.frame.check_delimited()?;
+        listener.set_nonblocking(true)?;
+        let path = listener
+            .local_addr()?
+            .as_pathname()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        Ok(self.finish(UnixListener::from_std(listener)?, path, None))
+    }
+
+    fn finish(
+        self,
+        listener: UnixListener,
+        path: PathBuf,
+        socket_file: Option<(u64, u64)>,
+    ) -> UnixTransportListener {
+        UnixTransportListener {
+            listener,
+            path,
             send_timeout: self.send_timeout,
             receive_timeout: self.receive_timeout,
-        })
+            memory_budget: self.memory_budget,
+            max_frames: self.max_frames,
+            min_receive_rate: self.min_receive_rate,
+            frame: self.frame,
+            on_accept: self.on_accept,
+            socket_file,
+        }
+    }
+}
+
+/// `sendmsg`/`recvmsg` with SCM_RIGHTS ancillary data
+mod scm {
+    use std::io;
+    use std::mem;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+    /// Most descriptors one message can carry (`SCM_MAX_FD` on Linux)
+    pub(super) const MAX_FDS: usize = 253;
+
+    #[cfg(target_os = "linux")]
+    const SEND_FLAGS: libc::c_int = libc::MSG_NOSIGNAL;
+    #[cfg(not(target_os = "linux"))]
+    const SEND_FLAGS: libc::c_int = 0;
+
+    #[cfg(target_os = "linux")]
+    const RECV_FLAGS: libc::c_int = libc::MSG_CMSG_CLOEXEC;
+    #[cfg(not(target_os = "linux"))]
+    const RECV_FLAGS: libc::c_int = 0;
+
+    /// Send as much of `bytes` as the socket takes, with `fds` attached
+    pub(super) fn send(socket: &impl AsRawFd, bytes: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        let mut iov = libc::iovec {
+            iov_base: bytes.as_ptr() as *mut libc::c_void,
+            iov_len: bytes.len(),
+        };
+        let fds_len = mem::size_of_val(fds);
+        let (mut control, control_len) = control_buffer(fds_len);
+
+        // SAFETY: msghdr is plain data for which all-zero is a valid value
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        if !fds.is_empty() {
+            msg.msg_control = control.as_mut_ptr().cast();
+            msg.msg_controllen = control_len as _;
+            // SAFETY: the control buffer is aligned and sized by CMSG_SPACE for
+            // one header carrying `fds_len` bytes, so the first header and its
+            // data lie within it
+            unsafe {
+                let cmsg = libc::CMSG_FIRSTHDR(&msg);
+                (*cmsg).cmsg_level = libc::SOL_SOCKET;
+                (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+                (*cmsg).cmsg_len = libc::CMSG_LEN(fds_len as libc::c_uint) as _;
+                std::ptr::copy_nonoverlapping(
+                    fds.as_ptr().cast::<u8>(),
+                    libc::CMSG_DATA(cmsg),
+                    fds_len,
+                );
+            }
+        }
+
+        // SAFETY: msg points at live buffers for the duration of the call
+        let sent = unsafe { libc::sendmsg(socket.as_raw_fd(), &msg, SEND_FLAGS) };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(sent as usize)
+    }
+
+    /// Receive into `buf`, appending any attached descriptors to `fds`
+    pub(super) fn recv(
+        socket: &impl AsRawFd,
+        buf: &mut [u8],
+        fds: &mut Vec<OwnedFd>,
+    ) -> io::Result<usize> {
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr().cast(),
+            iov_len: buf.len(),
+        };
+        let (mut control, control_len) = control_buffer(MAX_FDS * mem::size_of::<RawFd>());
+
+        // SAFETY: msghdr is plain data for which all-zero is a valid value
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = control_len as _;
+
+        // SAFETY: msg points at live buffers for the duration of the call
+        let received = unsafe { libc::recvmsg(socket.as_raw_fd(), &mut msg, RECV_FLAGS) };
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // Take ownership of every delivered descriptor before anything can fail
+        // SAFETY: the kernel filled the control buffer with well-formed
+        // headers, walked with the CMSG macros, and SCM_RIGHTS data holds
+        // descriptors that are now ours
+        unsafe {
+            let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+            while !cmsg.is_null() {
+                if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                    let data = libc::CMSG_DATA(cmsg);
+                    let data_len = (*cmsg).cmsg_len as usize - (data as usize - cmsg as usize);
+                    for i in 0..data_len / mem::size_of::<RawFd>() {
+                        let fd = data
+                            .add(i * mem::size_of::<RawFd>())
+                            .cast::<RawFd>()
+                            .read_unaligned();
+                        fds.push(OwnedFd::from_raw_fd(fd));
+                    }
+                }
+                cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        for fd in fds.iter() {
+            set_cloexec(fd)?;
+        }
+
+        if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+            return Err(io::Error::other("Received file descriptors were truncated"));
+        }
+        Ok(received as usize)
+    }
+
+    /// Zeroed control buffer aligned for `cmsghdr` and its length in bytes
+    fn control_buffer(data_len: usize) -> (Vec<u64>, usize) {
+        // SAFETY: CMSG_SPACE only computes a size
+        let space = unsafe { libc::CMSG_SPACE(data_len as libc::c_uint) } as usize;
+        (vec![0u64; space.div_ceil(mem::size_of::<u64>())], space)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn set_cloexec(fd: &OwnedFd) -> io::Result<()> {
+        // SAFETY: fcntl on a descriptor we own
+        if unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
     }
 }
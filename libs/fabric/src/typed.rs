@@ -0,0 +1,107 @@
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::channel::Channel;
+use crate::codec::Codec;
+use crate::duplex::DuplexChannel;
+use crate::error::Result;
+use crate::transport::Transport;
+
+/// Channel with its outgoing and incoming message types fixed at construction
+///
+/// A thin wrapper over [`Channel`] that only sends `Req` and only receives
+/// `Res`, so mixing up message types is a compile error. A server typically
+/// uses the mirrored `TypedChannel<Res, Req, C>`.
+pub struct TypedChannel<Req, Res, C> {
+    channel: Channel<C>,
+    _types: PhantomData<fn(&Req) -> Res>,
+}
+
+impl<Req, Res, C> TypedChannel<Req, Res, C>
+where
+    Req: Serialize,
+    Res: for<'de> Deserialize<'de>,
+    C: Codec,
+{
+    /// Wrap an existing channel
+    pub fn new(channel: Channel<C>) -> Self {
+        Self {
+            channel,
+            _types: PhantomData,
+        }
+    }
+
+    /// Create a typed channel from an existing transport
+    pub fn from_transport(transport: impl Transport + 'static, codec: C) -> Self {
+        Self::new(Channel::from_transport(transport, codec))
+    }
+
+    /// Open a typed TCP channel
+    pub async fn tcp(addr: SocketAddr, codec: C) -> Result<Self> {
+        Ok(Self::new(Channel::tcp(addr, codec).await?))
+    }
+
+    /// Open a typed Unix socket channel
+    #[cfg(unix)]
+    pub async fn unix(path: impl AsRef<Path>, codec: C) -> Result<Self> {
+        Ok(Self::new(Channel::unix(path, codec).await?))
+    }
+
+    /// Send a message over the channel
+    pub async fn send(&mut self, message: &Req) -> Result<()> {
+        self.channel.send(message).await
+    }
+
+    /// Receive a message from the channel
+    pub async fn receive(&mut self) -> Result<Res> {
+        self.channel.receive().await
+    }
+
+    /// Send a request and receive its response
+    ///
+    /// See [`Channel::request`] for the ordering this assumes.
+    pub async fn request(&mut self, message: &Req) -> Result<Res> {
+        self.channel.request(message).await
+    }
+
+    /// Send a request and receive its response within `timeout`
+    ///
+    /// See [`Channel::request_timeout`].
+    pub async fn request_timeout(&mut self, message: &Req, timeout: Duration) -> Result<Res> {
+        self.channel.request_timeout(message, timeout).await
+    }
+
+    /// Turn into a `Stream` of `Res` and `Sink` of `Req`
+    ///
+    /// See [`DuplexChannel`].
+    pub fn into_duplex(self) -> DuplexChannel<Req, Res, C>
+    where
+        Res: Send + 'static,
+        C: Clone + 'static,
+    {
+        DuplexChannel::new(self.channel)
+    }
+
+    /// Unwrap into the untyped channel
+    pub fn into_inner(self) -> Channel<C> {
+        self.channel
+    }
+
+    /// Close the channel
+    pub async fn close(self) -> Result<()> {
+        self.channel.close().await
+    }
+}
+
+impl<Req, Res, C> std::fmt::Debug for TypedChannel<Req, Res, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TypedChannel")
+            .field("channel", &self.channel)
+            .finish()
+    }
+}
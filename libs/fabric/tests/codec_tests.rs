@@ -0,0 +1,392 @@
+use constellation_fabric::{
+    codec::{
+        BincodeCodec, ChecksummedCodec, Codec, CompressedCodec, DepthLimitedBincodeCodec,
+        LenientBincodeCodec, VersionedCodec,
+    },
+    error::Error,
+    transport::StreamTransport,
+    Channel,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct TestMessage {
+    id: u32,
+    data: String,
+}
+
+fn sample() -> TestMessage {
+    TestMessage {
+        id: 42,
+        data: "test data".to_string(),
+    }
+}
+
+#[test]
+fn bincode_rejects_trailing_bytes() {
+    let mut bytes = BincodeCodec.encode(&sample()).unwrap();
+    bytes.extend_from_slice(&[0xde, 0xad]);
+
+    match BincodeCodec.decode::<TestMessage>(&bytes) {
+        Err(Error::Codec(msg)) => assert!(msg.contains("trailing bytes")),
+        other => panic!("Expected trailing bytes error, got {:?}", other),
+    }
+
+    // The lenient codec keeps the old tolerance
+    let decoded: TestMessage = LenientBincodeCodec.decode(&bytes).unwrap();
+    assert_eq!(decoded, sample());
+}
+
+#[test]
+fn bincode_rejects_length_prefix_beyond_frame() {
+    // A string claiming a terabyte must fail on the short frame, not allocate
+    let mut bytes = 42u32.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&(1u64 << 40).to_le_bytes());
+
+    match BincodeCodec.decode::<TestMessage>(&bytes) {
+        Err(Error::Codec(_)) => {}
+        other => panic!("Expected codec error, got {:?}", other),
+    }
+    match DepthLimitedBincodeCodec::new(8).decode::<TestMessage>(&bytes) {
+        Err(Error::Codec(_)) => {}
+        other => panic!("Expected codec error, got {:?}", other),
+    }
+}
+
+#[test]
+fn encode_into_matches_encode_across_reuse() {
+    let long = TestMessage {
+        id: 7,
+        data: "x".repeat(256),
+    };
+    let mut buf = Vec::new();
+
+    // Shrinking after a long message must not leave stale bytes behind
+    for msg in [long.clone(), sample(), long, sample()] {
+        BincodeCodec.encode_into(&msg, &mut buf).unwrap();
+        assert_eq!(buf, BincodeCodec.encode(&msg).unwrap());
+
+        let checksummed = ChecksummedCodec::new(BincodeCodec);
+        checksummed.encode_into(&msg, &mut buf).unwrap();
+        assert_eq!(buf, checksummed.encode(&msg).unwrap());
+
+        let compressed = CompressedCodec::new(BincodeCodec);
+        compressed.encode_into(&msg, &mut buf).unwrap();
+        assert_eq!(compressed.decode::<TestMessage>(&buf).unwrap(), msg);
+    }
+}
+
+#[test]
+fn checksummed_codec_roundtrip() {
+    let codec = ChecksummedCodec::new(BincodeCodec);
+
+    let bytes = codec.encode(&sample()).unwrap();
+    let decoded: TestMessage = codec.decode(&bytes).unwrap();
+
+    assert_eq!(decoded, sample());
+}
+
+#[test]
+fn checksummed_codec_detects_flipped_byte() {
+    let codec = ChecksummedCodec::new(BincodeCodec);
+
+    let mut bytes = codec.encode(&sample()).unwrap();
+    bytes[10] ^= 0x01;
+
+    match codec.decode::<TestMessage>(&bytes) {
+        Err(Error::Codec(msg)) => assert!(msg.contains("checksum mismatch")),
+        other => panic!("Expected checksum mismatch, got {:?}", other),
+    }
+}
+
+#[test]
+fn compressed_codec_compresses_repetitive_payload() {
+    let codec = CompressedCodec::new(BincodeCodec);
+    let message = TestMessage {
+        id: 1,
+        data: "a".repeat(10 * 1024),
+    };
+
+    let bytes = codec.encode(&message).unwrap();
+    assert_eq!(bytes[0], 1);
+    assert!(bytes.len() < 1024);
+
+    let decoded: TestMessage = codec.decode(&bytes).unwrap();
+    assert_eq!(decoded, message);
+}
+
+#[test]
+fn compressed_codec_skips_frames_below_min_size() {
+    let codec = CompressedCodec::new(BincodeCodec).with_min_compress_size(256);
+
+    let tiny = vec![7u8; 10];
+    let bytes = codec.encode(&tiny).unwrap();
+    assert_eq!(bytes[0], 0);
+    assert_eq!(codec.decode::<Vec<u8>>(&bytes).unwrap(), tiny);
+
+    // Compressible, but below the threshold
+    let small = "a".repeat(200);
+    assert_eq!(
+        CompressedCodec::new(BincodeCodec).encode(&small).unwrap()[0],
+        1
+    );
+    assert_eq!(codec.encode(&small).unwrap()[0], 0);
+
+    let large = "a".repeat(10 * 1024);
+    let bytes = codec.encode(&large).unwrap();
+    assert_eq!(bytes[0], 1);
+    assert!(bytes.len() < 1024);
+
+    // Decoding does not depend on the threshold
+    let decoder = CompressedCodec::new(BincodeCodec);
+    assert_eq!(decoder.decode::<String>(&bytes).unwrap(), large);
+}
+
+#[test]
+fn compressed_codec_falls_back_to_raw() {
+    let codec = CompressedCodec::new(BincodeCodec);
+
+    // Pseudo-random bytes don't compress
+    let mut state = 0x2545_f491_u32;
+    let noise: Vec<u8> = (0..256)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+
+    let bytes = codec.encode(&noise).unwrap();
+    assert_eq!(bytes[0], 0);
+    assert_eq!(&bytes[1..], &BincodeCodec.encode(&noise).unwrap()[..]);
+
+    let decoded: Vec<u8> = codec.decode(&bytes).unwrap();
+    assert_eq!(decoded, noise);
+}
+
+#[test]
+fn compressed_codec_rejects_unknown_flag() {
+    let codec = CompressedCodec::new(BincodeCodec);
+
+    match codec.decode::<TestMessage>(&[7, 0, 0]) {
+        Err(Error::Codec(msg)) => assert!(msg.contains("unknown compression flag")),
+        other => panic!("Expected codec error, got {:?}", other),
+    }
+}
+
+#[cfg(feature = "panic-safe-decode")]
+#[test]
+fn panicking_deserialize_becomes_codec_error() {
+    struct Explosive;
+
+    impl<'de> Deserialize<'de> for Explosive {
+        fn deserialize<D: serde::Deserializer<'de>>(_: D) -> Result<Self, D::Error> {
+            panic!("malformed input");
+        }
+    }
+
+    match BincodeCodec.decode::<Explosive>(&[1, 2, 3]) {
+        Err(Error::Codec(msg)) => assert!(msg.contains("malformed input")),
+        Ok(_) => panic!("Expected codec error"),
+        Err(e) => panic!("Expected codec error, got {:?}", e),
+    }
+}
+
+#[test]
+fn codecs_report_content_type() {
+    struct JsonLike;
+
+    impl Codec for JsonLike {
+        fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, Error> {
+            BincodeCodec.encode(value)
+        }
+
+        fn decode<T: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<T, Error> {
+            BincodeCodec.decode(bytes)
+        }
+
+        fn content_type(&self) -> &'static str {
+            "application/json"
+        }
+    }
+
+    assert_eq!(BincodeCodec.content_type(), "application/octet-stream");
+    assert_eq!(
+        CompressedCodec::new(JsonLike).content_type(),
+        "application/octet-stream"
+    );
+    assert_eq!(JsonLike.content_type(), "application/json");
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+enum Tree {
+    Leaf,
+    Node(Box<Tree>),
+}
+
+#[test]
+fn depth_limited_codec_rejects_deep_nesting() {
+    let codec = BincodeCodec.with_max_depth(64);
+
+    let mut shallow = Tree::Leaf;
+    for _ in 0..10 {
+        shallow = Tree::Node(Box::new(shallow));
+    }
+    let bytes = codec.encode(&shallow).unwrap();
+    assert_eq!(codec.decode::<Tree>(&bytes).unwrap(), shallow);
+
+    // Four bytes per level describe a tree deep enough to overflow the stack
+    let mut hostile: Vec<u8> = std::iter::repeat_n([1u8, 0, 0, 0], 1_000_000)
+        .flatten()
+        .collect();
+    hostile.extend_from_slice(&[0, 0, 0, 0]);
+    match codec.decode::<Tree>(&hostile) {
+        Err(Error::Codec(msg)) => assert_eq!(msg, "max depth exceeded"),
+        other => panic!("Expected max depth error, got {:?}", other),
+    }
+
+    // Every sequence counts as a level too
+    let nested = vec![vec![vec![1u8]]];
+    let bytes = codec.encode(&nested).unwrap();
+    assert!(DepthLimitedBincodeCodec::new(3)
+        .decode::<Vec<Vec<Vec<u8>>>>(&bytes)
+        .is_ok());
+    assert!(DepthLimitedBincodeCodec::new(2)
+        .decode::<Vec<Vec<Vec<u8>>>>(&bytes)
+        .is_err());
+}
+
+#[test]
+fn versioned_codec_rejects_unsupported_version() {
+    let v1 = VersionedCodec::new(BincodeCodec, 1);
+    let v2 = VersionedCodec::new(BincodeCodec, 2);
+
+    let bytes = v2.encode(&sample()).unwrap();
+    assert_eq!(bytes[0], 2);
+    match v1.decode::<TestMessage>(&bytes) {
+        Err(Error::Codec(msg)) => assert_eq!(msg, "unsupported schema version 2"),
+        other => panic!("Expected unsupported version error, got {:?}", other),
+    }
+
+    // A rolling upgrade accepts both versions while still writing v1
+    let upgrading = VersionedCodec::new(BincodeCodec, 1).accept_versions([2]);
+    assert_eq!(upgrading.decode::<TestMessage>(&bytes).unwrap(), sample());
+    assert_eq!(upgrading.encode(&sample()).unwrap()[0], 1);
+}
+
+#[tokio::test]
+async fn channels_share_one_arc_codec() {
+    let codec = Arc::new(CompressedCodec::new(BincodeCodec));
+    let (a, b) = tokio::io::duplex(4096);
+    let mut client = Channel::from_transport(StreamTransport::new(a), codec.clone());
+    let mut server = Channel::from_transport(StreamTransport::new(b), codec.clone());
+    assert_eq!(Arc::strong_count(&codec), 3);
+
+    client.send(&sample()).await.unwrap();
+    assert_eq!(server.receive::<TestMessage>().await.unwrap(), sample());
+    server.send(&7u32).await.unwrap();
+    assert_eq!(client.receive::<u32>().await.unwrap(), 7);
+}
+
+#[tokio::test]
+async fn channel_reports_any_decode_failure_as_codec_error() {
+    /// Codec that rejects empty payloads with a non-codec error
+    struct Picky;
+
+    impl Codec for Picky {
+        fn encode<T: Serialize>(&self, value: &T) -> constellation_fabric::Result<Vec<u8>> {
+            BincodeCodec.encode(value)
+        }
+
+        fn decode<T: for<'de> Deserialize<'de>>(
+            &self,
+            bytes: &[u8],
+        ) -> constellation_fabric::Result<T> {
+            if bytes.is_empty() {
+                return Err(Error::Custom("empty payload".to_string()));
+            }
+            BincodeCodec.decode(bytes)
+        }
+    }
+
+    let (a, b) = tokio::io::duplex(4096);
+    let mut client = Channel::from_transport(StreamTransport::new(a), Picky);
+    let mut server = Channel::from_transport(StreamTransport::new(b), Picky);
+
+    client.send_encoded(&[]).await.unwrap();
+    client.send(&7u32).await.unwrap();
+    let err = server.receive::<u32>().await.unwrap_err();
+    assert!(matches!(err, Error::Codec(msg) if msg == "empty payload"));
+
+    // The framing was fine, so the channel carries on
+    assert_eq!(server.receive::<u32>().await.unwrap(), 7);
+}
+
+#[cfg(feature = "prost")]
+#[derive(Clone, PartialEq, prost::Message)]
+struct Reading {
+    #[prost(string, tag = "1")]
+    sensor: String,
+    #[prost(double, tag = "2")]
+    value: f64,
+    #[prost(uint64, repeated, tag = "3")]
+    samples: Vec<u64>,
+}
+
+#[cfg(feature = "prost")]
+#[test]
+fn prost_codec_roundtrip() {
+    use constellation_fabric::codec::ProstCodec;
+
+    let reading = Reading {
+        sensor: "thermo-1".to_string(),
+        value: 21.5,
+        samples: vec![1, 2, 300],
+    };
+    let codec = ProstCodec::<Reading>::new();
+
+    let bytes = codec.encode(&reading);
+    assert_eq!(bytes, prost::Message::encode_to_vec(&reading));
+    assert_eq!(codec.decode(&bytes).unwrap(), reading);
+    assert_eq!(codec.content_type(), "application/x-protobuf");
+
+    // Truncated input is a codec error
+    assert!(matches!(
+        codec.decode(&bytes[..bytes.len() - 1]),
+        Err(Error::Codec(_))
+    ));
+}
+
+#[cfg(feature = "prost")]
+#[tokio::test]
+async fn prost_channel_exchanges_messages() {
+    use constellation_fabric::codec::ProstChannel;
+    use constellation_fabric::transport::TcpTransportListener;
+
+    let listener = TcpTransportListener::bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (transport, _) = listener.accept().await.unwrap();
+        let mut server = ProstChannel::<Reading, Reading>::from_transport(transport);
+        let mut reading = server.receive().await.unwrap();
+        reading.value *= 2.0;
+        server.send(&reading).await.unwrap();
+    });
+
+    let mut client = ProstChannel::<Reading, Reading>::tcp(addr).await.unwrap();
+    let response = client
+        .request(&Reading {
+            sensor: "thermo-2".to_string(),
+            value: 4.0,
+            samples: vec![],
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.sensor, "thermo-2");
+    assert_eq!(response.value, 8.0);
+}
@@ -0,0 +1,722 @@
+use constellation_fabric::{
+    channel::Channel,
+    codec::BincodeCodec,
+    error::Error,
+    request::request_tcp_pipelined,
+    server::{
+        serve, serve_queued, serve_requests, serve_requests_with_error_hook, serve_with_backoff,
+        AcceptBackoff, OverflowPolicy, QueueConfig,
+    },
+    transport::{
+        AnyListener, ExistingSocket, LimitMode, LimitedListener, TcpTransport,
+        TcpTransportListener, Transport, TransportListener, UnixTransport, UnixTransportListener,
+    },
+};
+use futures::StreamExt;
+use socket2::SockRef;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Helper to get a free port
+async fn get_listener() -> (TcpTransportListener, std::net::SocketAddr) {
+    let listener = TcpTransportListener::bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+    (listener, addr)
+}
+
+#[tokio::test]
+async fn limited_listener_queues_at_capacity() {
+    let (listener, addr) = get_listener().await;
+    let listener = LimitedListener::new(listener, 1);
+
+    let _first_client = TcpTransport::connect(addr).await.unwrap();
+    let first = listener.accept().await.unwrap();
+    assert_eq!(listener.available(), 0);
+
+    // Second connection sits in the backlog while the slot is held
+    let _second_client = TcpTransport::connect(addr).await.unwrap();
+    let pending = tokio::time::timeout(Duration::from_millis(100), listener.accept()).await;
+    assert!(pending.is_err());
+
+    // Releasing the first slot lets the second through
+    drop(first);
+    let second = tokio::time::timeout(Duration::from_secs(1), listener.accept()).await;
+    assert!(second.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn limited_listener_rejects_at_capacity() {
+    let (listener, addr) = get_listener().await;
+    let listener = LimitedListener::new(listener, 1).mode(LimitMode::Reject);
+
+    let mut first_client = TcpTransport::connect(addr).await.unwrap();
+    let mut first = listener.accept().await.unwrap();
+
+    // Second connection is accepted and closed straight away
+    let mut rejected_client = TcpTransport::connect(addr).await.unwrap();
+    let accept_task = tokio::spawn(async move {
+        let transport = listener.accept().await;
+        (listener, transport)
+    });
+
+    match rejected_client.receive().await {
+        Err(Error::ConnectionClosed) => {}
+        other => panic!("Expected ConnectionClosed, got {:?}", other),
+    }
+
+    // First connection is unaffected
+    first_client.send(b"still here").await.unwrap();
+    assert_eq!(first.receive().await.unwrap(), b"still here");
+
+    // Once the slot frees up, new connections are handed out again
+    drop(first);
+    let mut third_client = TcpTransport::connect(addr).await.unwrap();
+    let (_listener, third) = accept_task.await.unwrap();
+    let mut third = third.unwrap();
+    third_client.send(b"third").await.unwrap();
+    assert_eq!(third.receive().await.unwrap(), b"third");
+}
+
+#[tokio::test]
+async fn serve_drains_in_flight_connections() {
+    let (listener, addr) = get_listener().await;
+
+    let handle = serve(listener, |mut transport| async move {
+        while let Ok(msg) = transport.receive().await {
+            let _ = transport.send(&msg).await;
+        }
+    });
+
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+    client.send(b"before").await.unwrap();
+    assert_eq!(client.receive().await.unwrap(), b"before");
+
+    handle.stop_accepting();
+    assert_eq!(handle.active_connections(), 1);
+
+    // Existing connection is still served after accepting stops
+    client.send(b"after").await.unwrap();
+    assert_eq!(client.receive().await.unwrap(), b"after");
+
+    let drained = tokio::spawn(handle.await_drained());
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!drained.is_finished());
+
+    // Closing the last connection completes the drain
+    client.close().await.unwrap();
+    tokio::time::timeout(Duration::from_secs(1), drained)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // New connections are no longer accepted
+    assert!(TcpTransport::connect(addr).await.is_err());
+}
+
+#[tokio::test]
+async fn serve_requests_closes_only_the_connection_that_failed_to_decode() {
+    let (listener, addr) = get_listener().await;
+    let handle = serve_requests(listener, BincodeCodec, |n: u32| async move { n * 2 });
+
+    let mut good = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    let mut bad = TcpTransport::connect(addr).await.unwrap();
+
+    good.send(&1u32).await.unwrap();
+    assert_eq!(good.receive::<u32>().await.unwrap(), 2);
+
+    // Too short to decode as a u32
+    bad.send(b"x").await.unwrap();
+    assert!(matches!(bad.receive().await, Err(Error::ConnectionClosed)));
+
+    good.send(&21u32).await.unwrap();
+    assert_eq!(good.receive::<u32>().await.unwrap(), 42);
+
+    handle.stop_accepting();
+    good.close().await.unwrap();
+    tokio::time::timeout(Duration::from_secs(1), handle.await_drained())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn serve_requests_reports_the_error_that_ended_a_connection() {
+    let (listener, addr) = get_listener().await;
+    let (errors_tx, mut errors) = tokio::sync::mpsc::unbounded_channel();
+    let handle = serve_requests_with_error_hook(
+        listener,
+        BincodeCodec,
+        |n: u32| async move { n * 2 },
+        move |e| errors_tx.send(e.to_string()).unwrap(),
+    );
+
+    // A peer closing the connection is not reported
+    let mut good = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    good.send(&1u32).await.unwrap();
+    assert_eq!(good.receive::<u32>().await.unwrap(), 2);
+    good.close().await.unwrap();
+
+    let mut bad = TcpTransport::connect(addr).await.unwrap();
+    bad.send(b"x").await.unwrap();
+    assert!(matches!(bad.receive().await, Err(Error::ConnectionClosed)));
+
+    let error = errors.recv().await.unwrap();
+    assert!(error.starts_with("Codec error"), "{error}");
+    handle.stop_accepting();
+    tokio::time::timeout(Duration::from_secs(1), handle.await_drained())
+        .await
+        .unwrap();
+    assert!(errors.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn pipelined_requests_get_responses_in_order() {
+    let (listener, addr) = get_listener().await;
+    let connections = Arc::new(Mutex::new(0));
+    let counted = connections.clone();
+    let handle = serve(listener, move |transport| {
+        *counted.lock().unwrap() += 1;
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        async move {
+            while let Ok(n) = channel.receive::<u32>().await {
+                channel.send(&format!("#{n}")).await.unwrap();
+            }
+        }
+    });
+
+    let responses: Vec<String> = request_tcp_pipelined(addr, &[3u32, 1, 2], BincodeCodec)
+        .await
+        .unwrap();
+    assert_eq!(responses, ["#3", "#1", "#2"]);
+    assert_eq!(*connections.lock().unwrap(), 1);
+    handle.stop_accepting();
+}
+
+#[tokio::test]
+async fn serve_pauses_accepting_when_out_of_file_descriptors() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Listener whose accept fails three times with another error, then always with EMFILE
+    struct Exhausted(Arc<AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl TransportListener for Exhausted {
+        type Transport = TcpTransport;
+
+        async fn accept(&self) -> constellation_fabric::Result<TcpTransport> {
+            if self.0.fetch_add(1, Ordering::Relaxed) < 3 {
+                Err(Error::Custom("handshake failed".to_string()))
+            } else {
+                Err(std::io::Error::from_raw_os_error(24).into())
+            }
+        }
+
+        async fn close(&mut self) -> constellation_fabric::Result<()> {
+            Ok(())
+        }
+    }
+
+    let accepts = Arc::new(AtomicUsize::new(0));
+    let warnings = Arc::new(AtomicUsize::new(0));
+    let warned = warnings.clone();
+    let backoff = AcceptBackoff::new()
+        .delay(Duration::from_millis(50))
+        .on_exhausted(move |_| {
+            warned.fetch_add(1, Ordering::Relaxed);
+        });
+    tokio::time::pause();
+    let handle = serve_with_backoff(Exhausted(accepts.clone()), |_| async {}, backoff);
+
+    // Other errors are retried at once, EMFILE at 0, 50, 100, 150 and 200ms
+    tokio::time::sleep(Duration::from_millis(220)).await;
+    assert_eq!(accepts.load(Ordering::Relaxed), 8);
+    assert_eq!(warnings.load(Ordering::Relaxed), 5);
+
+    // Stopping cuts the pause short
+    handle.stop_accepting();
+    tokio::time::timeout(Duration::from_millis(20), handle.await_drained())
+        .await
+        .unwrap();
+}
+
+/// Listener that reports each connection it accepts on a channel
+struct Announcing {
+    inner: TcpTransportListener,
+    accepted: tokio::sync::mpsc::UnboundedSender<()>,
+}
+
+#[async_trait::async_trait]
+impl TransportListener for Announcing {
+    type Transport = TcpTransport;
+
+    async fn accept(&self) -> constellation_fabric::Result<TcpTransport> {
+        let transport = TransportListener::accept(&self.inner).await?;
+        let _ = self.accepted.send(());
+        Ok(transport)
+    }
+
+    async fn close(&mut self) -> constellation_fabric::Result<()> {
+        self.inner.close().await
+    }
+}
+
+/// Serve one echo handler at a time with room for one queued connection
+///
+/// The receiver yields once per accepted connection. The accept loop has
+/// queued it by the time the test task runs again.
+async fn serve_one_queued(
+    policy: OverflowPolicy,
+) -> (
+    constellation_fabric::server::ListenerHandle,
+    std::net::SocketAddr,
+    Arc<Mutex<Vec<OverflowPolicy>>>,
+    tokio::sync::mpsc::UnboundedReceiver<()>,
+) {
+    let (inner, addr) = get_listener().await;
+    let (accepted, accepts) = tokio::sync::mpsc::unbounded_channel();
+    let listener = Announcing { inner, accepted };
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let hook_seen = seen.clone();
+    let config = QueueConfig::new(1, 1)
+        .overflow(policy)
+        .on_overflow(move |policy| hook_seen.lock().unwrap().push(policy));
+
+    let handle = serve_queued(
+        listener,
+        |mut transport| async move {
+            while let Ok(msg) = transport.receive().await {
+                let _ = transport.send(&msg).await;
+            }
+        },
+        config,
+    );
+    (handle, addr, seen, accepts)
+}
+
+#[tokio::test]
+async fn serve_queued_rejects_when_queue_full() {
+    let (handle, addr, seen, mut accepts) = serve_one_queued(OverflowPolicy::Reject).await;
+
+    let mut busy = TcpTransport::connect(addr).await.unwrap();
+    busy.send(b"busy").await.unwrap();
+    assert_eq!(busy.receive().await.unwrap(), b"busy");
+
+    let mut queued = TcpTransport::connect(addr).await.unwrap();
+    accepts.recv().await.unwrap();
+    accepts.recv().await.unwrap();
+    let mut rejected = TcpTransport::connect(addr).await.unwrap();
+    match rejected.receive().await {
+        Err(Error::ConnectionClosed) => {}
+        other => panic!("Expected ConnectionClosed, got {:?}", other),
+    }
+    assert_eq!(handle.overflow_count(), 1);
+    assert_eq!(*seen.lock().unwrap(), vec![OverflowPolicy::Reject]);
+
+    // The queued connection is served once the handler frees up
+    busy.close().await.unwrap();
+    queued.send(b"queued").await.unwrap();
+    assert_eq!(queued.receive().await.unwrap(), b"queued");
+}
+
+#[tokio::test]
+async fn serve_queued_drops_oldest_when_queue_full() {
+    let (handle, addr, _seen, mut accepts) = serve_one_queued(OverflowPolicy::DropOldest).await;
+
+    let mut busy = TcpTransport::connect(addr).await.unwrap();
+    busy.send(b"busy").await.unwrap();
+    assert_eq!(busy.receive().await.unwrap(), b"busy");
+
+    let mut oldest = TcpTransport::connect(addr).await.unwrap();
+    accepts.recv().await.unwrap();
+    accepts.recv().await.unwrap();
+    let mut newest = TcpTransport::connect(addr).await.unwrap();
+    match oldest.receive().await {
+        Err(Error::ConnectionClosed) => {}
+        other => panic!("Expected ConnectionClosed, got {:?}", other),
+    }
+    assert_eq!(handle.overflow_count(), 1);
+
+    busy.close().await.unwrap();
+    newest.send(b"newest").await.unwrap();
+    assert_eq!(newest.receive().await.unwrap(), b"newest");
+}
+
+#[tokio::test]
+async fn any_listener_serves_tcp_and_unix() {
+    let socket_path = "/tmp/constellation_test_any_listener.sock";
+    let _ = std::fs::remove_file(socket_path);
+
+    let (tcp_listener, addr) = get_listener().await;
+    let unix_listener = UnixTransportListener::bind(socket_path).await.unwrap();
+
+    // One handler serves both listeners
+    let listeners: Vec<AnyListener> = vec![tcp_listener.into(), unix_listener.into()];
+    for listener in listeners {
+        tokio::spawn(async move {
+            let mut transport = listener.accept().await.unwrap();
+            let msg = transport.receive().await.unwrap();
+            transport.send(&msg).await.unwrap(); // Echo back
+        });
+    }
+
+    let mut tcp_client = TcpTransport::connect(addr).await.unwrap();
+    tcp_client.send(b"over tcp").await.unwrap();
+    assert_eq!(tcp_client.receive().await.unwrap(), b"over tcp");
+
+    let mut unix_client = UnixTransport::connect(socket_path).await.unwrap();
+    unix_client.send(b"over unix").await.unwrap();
+    assert_eq!(unix_client.receive().await.unwrap(), b"over unix");
+}
+
+#[tokio::test]
+async fn incoming_stream_yields_connections() {
+    let (listener, addr) = get_listener().await;
+
+    let server = tokio::spawn(async move {
+        listener
+            .incoming()
+            .take(3)
+            .for_each_concurrent(2, |transport| async move {
+                let mut transport = transport.unwrap();
+                let msg = transport.receive().await.unwrap();
+                transport.send(&msg).await.unwrap(); // Echo back
+            })
+            .await;
+    });
+
+    for i in 0..3u8 {
+        let mut client = TcpTransport::connect(addr).await.unwrap();
+        client.send(&[i]).await.unwrap();
+        assert_eq!(client.receive().await.unwrap(), [i]);
+    }
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn listener_applies_default_timeouts_to_accepted() {
+    let listener = TcpTransportListener::builder()
+        .receive_timeout(Duration::from_millis(100))
+        .bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Client connects but never sends
+    let _client = TcpTransport::connect(addr).await.unwrap();
+
+    let (mut transport, _) = listener.accept().await.unwrap();
+    assert_eq!(
+        transport.receive_timeout(),
+        Some(Duration::from_millis(100))
+    );
+    match transport.receive().await {
+        Err(Error::Custom(msg)) => assert!(msg.contains("timeout")),
+        other => panic!("Expected timeout error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn listener_builder_applies_socket_options() {
+    let listener = TcpTransportListener::builder()
+        .reuse_address(true)
+        .backlog(16)
+        .nodelay_on_accept(true)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let _client = TcpTransport::connect(addr).await.unwrap();
+    let (transport, _) = listener.accept().await.unwrap();
+    assert!(transport.get_ref().nodelay().unwrap());
+
+    // The plain constructor leaves Nagle enabled
+    let (plain, addr) = get_listener().await;
+    let _client = TcpTransport::connect(addr).await.unwrap();
+    let (transport, _) = plain.accept().await.unwrap();
+    assert!(!transport.get_ref().nodelay().unwrap());
+}
+
+#[tokio::test]
+async fn builders_apply_socket_buffer_sizes() {
+    const SIZE: usize = 256 * 1024;
+
+    let listener = TcpTransportListener::builder()
+        .send_buffer_size(SIZE)
+        .recv_buffer_size(SIZE)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let client = TcpTransport::builder()
+        .address(addr)
+        .send_buffer_size(SIZE)
+        .recv_buffer_size(SIZE)
+        .connect()
+        .await
+        .unwrap();
+    let (accepted, _) = listener.accept().await.unwrap();
+
+    // The OS may round up, but never below the requested size
+    for transport in [&client, &accepted] {
+        let socket = SockRef::from(transport.get_ref());
+        assert!(socket.send_buffer_size().unwrap() >= SIZE);
+        assert!(socket.recv_buffer_size().unwrap() >= SIZE);
+    }
+}
+
+#[tokio::test]
+async fn on_accept_closes_refused_connections() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Refuse the first connection, admit the rest
+    let seen = AtomicUsize::new(0);
+    let listener = TcpTransportListener::builder()
+        .on_accept(move |addr| {
+            assert!(addr.ip().is_loopback());
+            seen.fetch_add(1, Ordering::SeqCst) > 0
+        })
+        .bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (mut transport, _) = listener.accept().await.unwrap();
+        let msg = transport.receive().await.unwrap();
+        transport.send(&msg).await.unwrap();
+    });
+
+    let mut refused = TcpTransport::connect(addr).await.unwrap();
+    match refused.receive().await {
+        Err(Error::ConnectionClosed) => {}
+        other => panic!("Expected ConnectionClosed, got {:?}", other),
+    }
+
+    let mut admitted = TcpTransport::connect(addr).await.unwrap();
+    admitted.send(b"hello").await.unwrap();
+    assert_eq!(admitted.receive().await.unwrap(), b"hello");
+}
+
+#[tokio::test]
+async fn tcp_fastopen_connections_exchange_frames() {
+    let listener = TcpTransportListener::builder()
+        .tcp_fastopen(true)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut transport, _) = listener.accept().await.unwrap();
+            let msg = transport.receive().await.unwrap();
+            transport.send(&msg).await.unwrap();
+        }
+    });
+
+    // The first connection fetches a cookie, later ones may send in the SYN
+    for i in 0..3u8 {
+        let mut client = TcpTransport::builder()
+            .address(addr)
+            .tcp_fastopen(true)
+            .connect()
+            .await
+            .unwrap();
+        client.send(&[i]).await.unwrap();
+        assert_eq!(client.receive().await.unwrap(), vec![i]);
+    }
+}
+
+#[tokio::test]
+async fn listener_max_frames_closes_chatty_connection() {
+    let listener = TcpTransportListener::builder()
+        .max_frames(2)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+    let (mut transport, _) = listener.accept().await.unwrap();
+    for i in 0..3u8 {
+        client.send(&[i]).await.unwrap();
+    }
+
+    assert_eq!(transport.receive().await.unwrap(), vec![0]);
+    assert_eq!(transport.receive().await.unwrap(), vec![1]);
+    match transport.receive().await {
+        Err(Error::Custom(msg)) => assert_eq!(msg, "frame limit exceeded"),
+        other => panic!("Expected frame limit error, got {:?}", other),
+    }
+    assert!(transport.is_closed());
+
+    // The server shut its side down
+    assert!(matches!(
+        client.receive().await,
+        Err(Error::ConnectionClosed)
+    ));
+}
+
+#[tokio::test]
+async fn listener_min_receive_rate_aborts_trickled_payload() {
+    use tokio::io::AsyncWriteExt;
+
+    let listener = TcpTransportListener::builder()
+        .min_receive_rate(1000)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let (mut transport, _) = listener.accept().await.unwrap();
+    let trickle = tokio::spawn(async move {
+        // Idling before a frame is fine
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        client.write_all(&[0, 0, 0, 2, b'o', b'k']).await.unwrap();
+
+        // Announce 100 bytes, then send them one every 200ms
+        client.write_all(&100u32.to_be_bytes()).await.unwrap();
+        for _ in 0..100 {
+            if client.write_all(&[0]).await.is_err() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    });
+
+    assert_eq!(transport.receive().await.unwrap(), b"ok");
+    let started = std::time::Instant::now();
+    match transport.receive().await {
+        Err(Error::ReceiveTooSlow) => {}
+        other => panic!("Expected slow receive error, got {:?}", other),
+    }
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(transport.is_closed());
+    trickle.abort();
+}
+
+#[tokio::test]
+async fn tcp_listener_from_std_accepts_connections() {
+    let std_listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let listener = TcpTransportListener::builder()
+        .receive_timeout(Duration::from_secs(5))
+        .from_std(std_listener)
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+    let (mut transport, _) = listener.accept().await.unwrap();
+    assert_eq!(transport.receive_timeout(), Some(Duration::from_secs(5)));
+
+    client.send(b"activated").await.unwrap();
+    assert_eq!(transport.receive().await.unwrap(), b"activated");
+}
+
+#[tokio::test]
+async fn unix_listener_from_std_leaves_socket_file() {
+    let socket_path = "/tmp/constellation_test_unix_from_std.sock";
+    let _ = std::fs::remove_file(socket_path);
+
+    let std_listener = std::os::unix::net::UnixListener::bind(socket_path).unwrap();
+    let mut listener = UnixTransportListener::from_std(std_listener).unwrap();
+
+    let mut client = UnixTransport::connect(socket_path).await.unwrap();
+    let mut transport = listener.accept().await.unwrap();
+    client.send(b"activated").await.unwrap();
+    assert_eq!(transport.receive().await.unwrap(), b"activated");
+
+    // The socket file belongs to whoever created the listener
+    listener.close().await.unwrap();
+    drop(listener);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(std::path::Path::new(socket_path).exists());
+
+    let _ = std::fs::remove_file(socket_path);
+}
+
+#[tokio::test]
+async fn unix_bind_replaces_only_stale_sockets_by_default() {
+    let socket_path = "/tmp/constellation_test_unix_existing.sock";
+    let _ = std::fs::remove_file(socket_path);
+
+    // A live listener keeps its socket
+    let live = UnixTransportListener::bind(socket_path).await.unwrap();
+    let err = UnixTransportListener::bind(socket_path).await.unwrap_err();
+    assert!(matches!(err, Error::Io(e) if e.kind() == std::io::ErrorKind::AddrInUse));
+
+    // The live listener sees the probe as a connection that closes at once
+    let mut probe = live.accept().await.unwrap();
+    assert!(matches!(
+        probe.receive().await,
+        Err(Error::ConnectionClosed)
+    ));
+    let mut client = UnixTransport::connect(socket_path).await.unwrap();
+    let mut transport = live.accept().await.unwrap();
+    client.send(b"still mine").await.unwrap();
+    assert_eq!(transport.receive().await.unwrap(), b"still mine");
+
+    // Leave a stale socket file behind, as a crashed process would
+    drop(live);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    drop(std::os::unix::net::UnixListener::bind(socket_path).unwrap());
+
+    let err = UnixTransportListener::builder()
+        .on_existing(ExistingSocket::Fail)
+        .bind(socket_path)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Io(e) if e.kind() == std::io::ErrorKind::AddrInUse));
+
+    let listener = UnixTransportListener::bind(socket_path).await.unwrap();
+    let mut client = UnixTransport::connect(socket_path).await.unwrap();
+    let mut transport = listener.accept().await.unwrap();
+    client.send(b"replaced").await.unwrap();
+    assert_eq!(transport.receive().await.unwrap(), b"replaced");
+}
+
+#[tokio::test]
+async fn shared_listener_accepts_from_several_workers() {
+    let (listener, addr) = get_listener().await;
+    let listener = Arc::new(listener);
+
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let listener = listener.clone();
+            tokio::spawn(async move {
+                let mut transport = TransportListener::accept(&listener).await.unwrap();
+                let msg = transport.receive().await.unwrap();
+                transport.send(&msg).await.unwrap();
+            })
+        })
+        .collect();
+
+    for i in 0..4u8 {
+        let mut client = TcpTransport::connect(addr).await.unwrap();
+        client.send(&[i]).await.unwrap();
+        assert_eq!(client.receive().await.unwrap(), vec![i]);
+    }
+    for worker in workers {
+        worker.await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn shared_unix_listener_closes_with_last_clone() {
+    let socket_path = "/tmp/constellation_test_shared_close.sock";
+    let _ = std::fs::remove_file(socket_path);
+
+    let mut first = Arc::new(UnixTransportListener::bind(socket_path).await.unwrap());
+    let mut second = first.clone();
+
+    TransportListener::close(&mut first).await.unwrap();
+    drop(first);
+    assert!(std::path::Path::new(socket_path).exists());
+
+    TransportListener::close(&mut second).await.unwrap();
+    assert!(!std::path::Path::new(socket_path).exists());
+}
@@ -0,0 +1,108 @@
+#![cfg(windows)]
+
+use constellation_fabric::{
+    error::Error,
+    transport::{NamedPipeTransport, NamedPipeTransportListener, Transport},
+};
+use std::time::Duration;
+
+#[tokio::test]
+async fn pipe_send_receive_single_message() {
+    let pipe_name = r"\\.\pipe\constellation_test_pipe_single";
+
+    let listener = NamedPipeTransportListener::bind(pipe_name).await.unwrap();
+
+    // Spawn server
+    tokio::spawn(async move {
+        let mut transport = listener.accept().await.unwrap();
+        let received = transport.receive().await.unwrap();
+        transport.send(&received).await.unwrap(); // Echo back
+    });
+
+    // Client
+    let mut client = NamedPipeTransport::connect(pipe_name).await.unwrap();
+    let msg = b"hello pipe";
+    client.send(msg).await.unwrap();
+    let response = client.receive().await.unwrap();
+
+    assert_eq!(response, msg);
+}
+
+#[tokio::test]
+async fn pipe_multiple_messages_preserve_boundaries() {
+    let pipe_name = r"\\.\pipe\constellation_test_pipe_multi";
+
+    let listener = NamedPipeTransportListener::bind(pipe_name).await.unwrap();
+
+    // Spawn server
+    tokio::spawn(async move {
+        let mut transport = listener.accept().await.unwrap();
+        for _ in 0..3 {
+            let msg = transport.receive().await.unwrap();
+            transport.send(&msg).await.unwrap();
+        }
+    });
+
+    // Client sends 3 distinct messages
+    let mut client = NamedPipeTransport::connect(pipe_name).await.unwrap();
+    let messages = vec![b"first".to_vec(), b"second".to_vec(), b"third".to_vec()];
+
+    for msg in &messages {
+        client.send(msg).await.unwrap();
+        let response = client.receive().await.unwrap();
+        assert_eq!(&response, msg);
+    }
+}
+
+#[tokio::test]
+async fn pipe_listener_accepts_several_clients() {
+    let pipe_name = r"\\.\pipe\constellation_test_pipe_clients";
+
+    let listener = NamedPipeTransportListener::bind(pipe_name).await.unwrap();
+
+    // Each accept leaves a fresh instance waiting for the next client
+    tokio::spawn(async move {
+        loop {
+            let mut transport = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let msg = transport.receive().await.unwrap();
+                transport.send(&msg).await.unwrap();
+            });
+        }
+    });
+
+    for i in 0..3u8 {
+        let mut client = NamedPipeTransport::connect(pipe_name).await.unwrap();
+        client.send(&[i]).await.unwrap();
+        assert_eq!(client.receive().await.unwrap(), vec![i]);
+    }
+}
+
+#[tokio::test]
+async fn pipe_timeout_works() {
+    let pipe_name = r"\\.\pipe\constellation_test_pipe_timeout";
+
+    let listener = NamedPipeTransportListener::bind(pipe_name).await.unwrap();
+
+    // Spawn server that never responds
+    tokio::spawn(async move {
+        let _transport = listener.accept().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    });
+
+    // Client with short receive timeout
+    let mut client = NamedPipeTransport::builder()
+        .name(pipe_name)
+        .receive_timeout(Duration::from_millis(100))
+        .connect()
+        .await
+        .unwrap();
+
+    client.send(b"hello").await.unwrap();
+
+    // Should timeout
+    match client.receive().await {
+        Err(Error::Custom(msg)) => assert!(msg.contains("timeout")),
+        other => panic!("Expected timeout error, got {:?}", other),
+    }
+}
@@ -1,233 +1,2769 @@
 use constellation_fabric::{
-    channel::Channel,
-    codec::BincodeCodec,
+    breaker::{BreakerState, CircuitBreaker},
+    channel::{Channel, ChannelStats},
+    codec::{BincodeCodec, Codec, RawCodec},
     error::Error,
+    pool::ChannelPool,
+    priority::PriorityChannel,
+    request::{
+        broadcast_tcp, broadcast_tcp_with_timeout, request_tcp, request_tcp_idempotent, request_tcp_resilient, request_unix,
+        respond_once, Idempotent, RpcError,
+    },
+    sequenced::SequencedChannel,
     transport::{
-        TcpTransport, TcpTransportListener, Transport, TransportListener, UnixTransport,
-        UnixTransportListener,
+        framing, CloseOnDrop, ConnectionState, Endian, FrameType, FramingMode, MemoryBudget,
+        PrefixWidth, RateLimited, Socks5Auth, StreamTransport, TcpTransport, TcpTransportListener,
+        Transport, TransportKind, TransportListener, UnixTransport, UnixTransportListener,
     },
+    typed::TypedChannel,
 };
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct TestMessage {
+    id: u32,
+    data: String,
+}
+
+/// Helper to get a free port
+async fn get_listener() -> (TcpTransportListener, std::net::SocketAddr) {
+    let listener = TcpTransportListener::bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+    (listener, addr)
+}
+
+#[tokio::test]
+async fn tcp_send_receive_single_message() {
+    let (listener, addr) = get_listener().await;
+
+    // Spawn server
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        let received = transport.receive().await.unwrap();
+        transport.send(&received).await.unwrap(); // Echo back
+    });
+
+    // Client
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+    let msg = b"hello world";
+    client.send(msg).await.unwrap();
+    let response = client.receive().await.unwrap();
+
+    assert_eq!(response, msg);
+}
+
+#[tokio::test]
+async fn tcp_multiple_messages_preserve_boundaries() {
+    let (listener, addr) = get_listener().await;
+
+    // Spawn server
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        // Receive 3 messages and echo each back
+        for _ in 0..3 {
+            let msg = transport.receive().await.unwrap();
+            transport.send(&msg).await.unwrap();
+        }
+    });
+
+    // Client sends 3 distinct messages
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+    let messages = vec![b"first".to_vec(), b"second".to_vec(), b"third".to_vec()];
+
+    for msg in &messages {
+        client.send(msg).await.unwrap();
+        let response = client.receive().await.unwrap();
+        assert_eq!(&response, msg);
+    }
+}
+
+#[tokio::test]
+async fn tcp_receive_timeout_fires() {
+    let (listener, addr) = get_listener().await;
+
+    // Spawn server that never responds
+    tokio::spawn(async move {
+        let (_transport, _addr) = listener.accept().await.unwrap();
+        // Just hold connection open, never send
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    });
+
+    // Client with short receive timeout
+    let mut client = TcpTransport::builder()
+        .address(addr)
+        .receive_timeout(Duration::from_millis(100))
+        .connect()
+        .await
+        .unwrap();
+
+    client.send(b"hello").await.unwrap();
+
+    // Should timeout
+    let result = client.receive().await;
+    assert!(result.is_err());
+    match result.unwrap_err() {
+        Error::Custom(msg) => assert!(msg.contains("timeout")),
+        _ => panic!("Expected timeout error"),
+    }
+}
+
+#[tokio::test]
+async fn timeouts_can_be_changed_on_live_transport() {
+    let (listener, addr) = get_listener().await;
+
+    // Spawn server that never responds
+    tokio::spawn(async move {
+        let (_transport, _addr) = listener.accept().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    });
+
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+    assert_eq!(client.receive_timeout(), None);
+
+    client.set_receive_timeout(Some(Duration::from_millis(50)));
+    client.set_send_timeout(Some(Duration::from_secs(1)));
+    assert_eq!(client.receive_timeout(), Some(Duration::from_millis(50)));
+    assert_eq!(client.send_timeout(), Some(Duration::from_secs(1)));
+
+    match client.receive().await {
+        Err(Error::Custom(msg)) => assert!(msg.contains("timeout")),
+        other => panic!("Expected timeout error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn frame_header_carries_metadata() {
+    let listener = TcpTransportListener::bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Server reads the raw frame: the prefix covers header + payload
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        let raw = transport.receive().await.unwrap();
+        transport.send(&raw).await.unwrap(); // Echo back
+    });
+
+    let mut client = TcpTransport::builder()
+        .address(addr)
+        .header_len(4)
+        .connect()
+        .await
+        .unwrap();
+
+    client.send_with_header(b"tr42", b"payload").await.unwrap();
+    let (header, payload) = client.receive_with_header::<4>().await.unwrap();
+    assert_eq!(&header, b"tr42");
+    assert_eq!(payload, b"payload");
+
+    // Mismatched header length is rejected before touching the wire
+    match client.send_with_header(b"toolong", b"payload").await {
+        Err(Error::InvalidFrame(msg)) => assert!(msg.contains("expected 4")),
+        other => panic!("Expected InvalidFrame, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn channel_ping_measures_round_trip() {
+    let listener = TcpTransportListener::builder()
+        .frame_types(true)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Server only echoes data; pings are answered inside receive
+    tokio::spawn(async move {
+        let (mut transport, _) = listener.accept().await.unwrap();
+        while let Ok(bytes) = transport.receive().await {
+            transport.send(&bytes).await.unwrap();
+        }
+    });
+
+    let transport = TcpTransport::builder()
+        .address(addr)
+        .frame_types(true)
+        .connect()
+        .await
+        .unwrap();
+    let mut channel = Channel::from_transport(transport, RawCodec);
+
+    // Data sent before a ping is buffered rather than lost
+    channel.send_encoded(b"before").await.unwrap();
+    let rtt = channel.ping(Duration::from_secs(1)).await.unwrap();
+    assert!(rtt < Duration::from_secs(1));
+    assert_eq!(channel.receive_encoded().await.unwrap(), b"before");
+}
+
+#[tokio::test]
+async fn control_and_close_frames_interleave_with_data() {
+    let listener = TcpTransportListener::builder()
+        .frame_types(true)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let (mut transport, _) = listener.accept().await.unwrap();
+        transport
+            .send_control(FrameType::Control, b"pause")
+            .await
+            .unwrap();
+        transport.send(b"data").await.unwrap();
+        transport.close().await.unwrap();
+    });
+
+    let mut client = TcpTransport::builder()
+        .address(addr)
+        .frame_types(true)
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(
+        client.receive_frame().await.unwrap(),
+        (FrameType::Control, b"pause".to_vec())
+    );
+    assert_eq!(
+        client.receive_frame().await.unwrap(),
+        (FrameType::Data, b"data".to_vec())
+    );
+    // close announces itself before the stream ends
+    assert_eq!(
+        client.receive_frame().await.unwrap(),
+        (FrameType::Close, Vec::new())
+    );
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn receive_skips_control_frames_and_stops_at_close() {
+    let listener = TcpTransportListener::builder()
+        .frame_types(true)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (mut transport, _) = listener.accept().await.unwrap();
+        transport
+            .send_control(FrameType::Control, b"pause")
+            .await
+            .unwrap();
+        transport.send(b"data").await.unwrap();
+        // Announce the close but keep the socket open
+        transport.send_control(FrameType::Close, &[]).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    });
+
+    let mut client = TcpTransport::builder()
+        .address(addr)
+        .frame_types(true)
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(client.receive().await.unwrap(), b"data");
+    assert!(matches!(
+        client.receive().await,
+        Err(Error::ConnectionClosed)
+    ));
+}
+
+#[tokio::test]
+async fn channel_receive_ready_drains_buffered_frames() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (resume_tx, resume_rx) = tokio::sync::oneshot::channel::<()>();
+
+    // Three whole frames and the first half of a fourth in one write
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut wire = Vec::new();
+        for n in 1u32..=4 {
+            let payload = BincodeCodec.encode(&n).unwrap();
+            framing::write_frame(&mut wire, &payload).await.unwrap();
+        }
+        let split = wire.len() - 3;
+        stream.write_all(&wire[..split]).await.unwrap();
+        resume_rx.await.unwrap();
+        stream.write_all(&wire[split..]).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    });
+
+    let mut channel = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    assert_eq!(channel.receive::<u32>().await.unwrap(), 1);
+    assert_eq!(channel.receive_ready::<u32>().await.unwrap(), vec![2, 3]);
+    assert!(channel.receive_ready::<u32>().await.unwrap().is_empty());
+
+    // The partial frame survives and completes on a blocking receive
+    resume_tx.send(()).unwrap();
+    assert_eq!(channel.receive::<u32>().await.unwrap(), 4);
+    assert!(channel.receive_ready::<u32>().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn channel_receive_ready_works_without_transport_support() {
+    // Echoes every frame back and has no receive_ready of its own
+    struct Loopback(Vec<Vec<u8>>);
+
+    #[async_trait::async_trait]
+    impl Transport for Loopback {
+        async fn send(&mut self, bytes: &[u8]) -> constellation_fabric::error::Result<()> {
+            self.0.push(bytes.to_vec());
+            Ok(())
+        }
+
+        async fn receive(&mut self) -> constellation_fabric::error::Result<Vec<u8>> {
+            self.0.pop().ok_or(Error::ConnectionClosed)
+        }
+
+        async fn close(&mut self) -> constellation_fabric::error::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut channel = Channel::from_transport(Loopback(Vec::new()), BincodeCodec);
+    assert!(channel.receive_ready::<u32>().await.unwrap().is_empty());
+
+    // A peeked frame is still handed back
+    channel.send(&7u32).await.unwrap();
+    assert_eq!(channel.peek::<u32>().await.unwrap(), 7);
+    assert_eq!(channel.receive_ready::<u32>().await.unwrap(), vec![7]);
+    assert!(channel.receive_ready::<u32>().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn connection_state_tracks_close_and_fatal_errors() {
+    let (listener, addr) = get_listener().await;
+    tokio::spawn(async move {
+        let (mut transport, _) = listener.accept().await.unwrap();
+        transport.send(b"bye").await.unwrap();
+        transport.close().await.unwrap();
+    });
+
+    let mut channel = Channel::tcp(addr, RawCodec).await.unwrap();
+    assert_eq!(channel.state(), ConnectionState::Connected);
+    assert_eq!(channel.receive_encoded().await.unwrap(), b"bye");
+    assert!(matches!(
+        channel.receive_encoded().await,
+        Err(Error::ConnectionClosed)
+    ));
+    assert_eq!(channel.state(), ConnectionState::Closed);
+
+    let (listener, addr) = get_listener().await;
+    tokio::spawn(async move {
+        let _conn = listener.accept().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    });
+    let mut transport = TcpTransport::connect(addr).await.unwrap();
+    assert_eq!(transport.state(), ConnectionState::Connected);
+    transport.close().await.unwrap();
+    assert_eq!(transport.state(), ConnectionState::Closed);
+}
+
+#[tokio::test]
+async fn throughput_mode_batches_sends_until_flush() {
+    let (listener, addr) = get_listener().await;
+    let mut client = TcpTransport::builder()
+        .address(addr)
+        .throughput_mode()
+        .connect()
+        .await
+        .unwrap();
+    let (mut server, _) = listener.accept().await.unwrap();
+
+    for n in 0u8..3 {
+        client.send(&[n; 16]).await.unwrap();
+    }
+    let early = tokio::time::timeout(Duration::from_millis(100), server.receive()).await;
+    assert!(early.is_err(), "batched frames were written before a flush");
+
+    client.flush().await.unwrap();
+    for n in 0u8..3 {
+        assert_eq!(server.receive().await.unwrap(), [n; 16]);
+    }
+
+    // Waiting to receive writes the batch out first, so request/response still works
+    client.send(b"ping").await.unwrap();
+    let echo = tokio::spawn(async move {
+        let bytes = server.receive().await.unwrap();
+        server.send(&bytes).await.unwrap();
+    });
+    assert_eq!(client.receive().await.unwrap(), b"ping");
+    echo.await.unwrap();
+}
+
+#[tokio::test]
+async fn tcp_connect_host_falls_back_between_address_families() {
+    // Only listening on IPv4, so a `::1` candidate for localhost is refused
+    let (listener, addr) = get_listener().await;
+    tokio::spawn(async move {
+        let (mut transport, _) = listener.accept().await.unwrap();
+        transport.send(b"hello").await.unwrap();
+    });
+
+    let mut transport = TcpTransport::connect_host("localhost", addr.port())
+        .await
+        .unwrap();
+    assert_eq!(transport.peer_addr().unwrap(), addr);
+    assert_eq!(transport.receive().await.unwrap(), b"hello");
+
+    let unresolvable = TcpTransport::builder()
+        .host("no-such-host.invalid", addr.port())
+        .connect_timeout(Duration::from_secs(5))
+        .connect()
+        .await;
+    assert!(unresolvable.is_err());
+}
+
+#[tokio::test]
+async fn tcp_upgrade_keeps_settings_after_plaintext_hello() {
+    let (listener, addr) = get_listener().await;
+    tokio::spawn(async move {
+        let (mut transport, _) = listener.accept().await.unwrap();
+        assert_eq!(transport.receive().await.unwrap(), b"STARTTLS");
+        transport.send(b"GO").await.unwrap();
+        // Stand-in for a TLS acceptor
+        let mut upgraded = transport
+            .upgrade(|tcp| async { Ok(tokio::io::BufStream::new(tcp)) })
+            .await
+            .unwrap();
+        let bytes = upgraded.receive().await.unwrap();
+        upgraded.send(&bytes).await.unwrap();
+    });
+
+    let mut transport = TcpTransport::builder()
+        .address(addr)
+        .receive_timeout(Duration::from_secs(5))
+        .connect()
+        .await
+        .unwrap();
+    transport.send(b"STARTTLS").await.unwrap();
+    assert_eq!(transport.receive().await.unwrap(), b"GO");
+
+    let mut upgraded = transport
+        .upgrade(|tcp| async { Ok(tokio::io::BufStream::new(tcp)) })
+        .await
+        .unwrap();
+    assert_eq!(upgraded.receive_timeout(), Some(Duration::from_secs(5)));
+    upgraded.send(b"secret").await.unwrap();
+    assert_eq!(upgraded.receive().await.unwrap(), b"secret");
+}
+
+#[tokio::test]
+async fn send_vectored_delivers_parts_as_one_frame() {
+    let (listener, addr) = get_listener().await;
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+    let (mut server, _) = listener.accept().await.unwrap();
+
+    let header = b"hdr";
+    let body: Vec<u8> = (0..1_000_000u32).map(|i| i as u8).collect();
+    let send = tokio::spawn(async move {
+        client
+            .send_vectored(&[header, &[], &body[..]])
+            .await
+            .unwrap();
+        client.send_vectored(&[]).await.unwrap();
+        client.send(b"next").await.unwrap();
+        body
+    });
+
+    let frame = server.receive().await.unwrap();
+    let body = send.await.unwrap();
+    assert_eq!(frame.len(), header.len() + body.len());
+    assert_eq!(&frame[..3], header);
+    assert!(frame[3..] == body[..]);
+    assert!(server.receive().await.unwrap().is_empty());
+    assert_eq!(server.receive().await.unwrap(), b"next");
+}
+
+#[tokio::test]
+async fn receive_borrowed_reuses_ring_and_counts_oversized_frames() {
+    let (listener, addr) = get_listener().await;
+
+    let server = tokio::spawn(async move {
+        let (mut server, _) = listener.accept().await.unwrap();
+        server.send(b"first").await.unwrap();
+        server.send(&[7u8; 100]).await.unwrap();
+        server.send(b"last").await.unwrap();
+        server
+    });
+
+    let mut client = TcpTransport::builder()
+        .address(addr)
+        .receive_ring(16)
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(client.receive_borrowed().await.unwrap(), b"first");
+    assert_eq!(client.oversized_receives(), 0);
+    assert_eq!(client.receive_borrowed().await.unwrap(), &[7u8; 100][..]);
+    assert_eq!(client.oversized_receives(), 1);
+    assert_eq!(client.receive_borrowed().await.unwrap(), b"last");
+    assert_eq!(client.oversized_receives(), 1);
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn duplex_channel_sends_all_and_streams_replies() {
+    use futures::{SinkExt, StreamExt};
+
+    let (listener, addr) = get_listener().await;
+
+    let server = tokio::spawn(async move {
+        let (transport, _) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        while let Ok(n) = channel.receive::<u32>().await {
+            channel.send(&(n * 10)).await.unwrap();
+        }
+    });
+
+    let mut duplex = TypedChannel::<u32, u32, _>::tcp(addr, BincodeCodec)
+        .await
+        .unwrap()
+        .into_duplex();
+    let mut messages = futures::stream::iter([1u32, 2, 3].map(Ok));
+    duplex.send_all(&mut messages).await.unwrap();
+
+    let replies: Vec<u32> = (&mut duplex)
+        .take(3)
+        .map(|reply| reply.unwrap())
+        .collect()
+        .await;
+    assert_eq!(replies, [10, 20, 30]);
+
+    SinkExt::<u32>::close(&mut duplex).await.unwrap();
+    server.await.unwrap();
+    assert!(duplex.next().await.is_none());
+}
+
+#[tokio::test]
+async fn channel_stats_count_messages_and_framed_bytes() {
+    let (listener, addr) = get_listener().await;
+
+    let server = tokio::spawn(async move {
+        let (transport, _) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        let request: u32 = channel.receive().await.unwrap();
+        channel.send(&(u64::from(request) * 2)).await.unwrap();
+        channel.stats()
+    });
+
+    let mut client = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    assert_eq!(client.stats(), ChannelStats::default());
+    let response: u64 = client.request(&21u32).await.unwrap();
+    assert_eq!(response, 42);
+
+    // Every frame carries a 4-byte length prefix
+    let expected = ChannelStats {
+        bytes_sent: 4 + 4,
+        bytes_received: 8 + 4,
+        messages_sent: 1,
+        messages_received: 1,
+    };
+    assert_eq!(client.stats(), expected);
+    let server_stats = server.await.unwrap();
+    assert_eq!(server_stats.bytes_received, expected.bytes_sent);
+    assert_eq!(server_stats.bytes_sent, expected.bytes_received);
+}
+
+#[tokio::test]
+async fn receive_stream_ends_at_sequence_end_marker() {
+    use futures::StreamExt;
+
+    let (listener, addr) = get_listener().await;
+
+    let server = tokio::spawn(async move {
+        let (transport, _) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        let items: Vec<u32> = channel
+            .receive_stream::<u32>()
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
+        let after: String = channel.receive().await.unwrap();
+        (items, after)
+    });
+
+    let mut client = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    client.send_sequence(&[1u32, 2, 3]).await.unwrap();
+    client.send(&"done".to_string()).await.unwrap();
+
+    let (items, after) = server.await.unwrap();
+    assert_eq!(items, [1, 2, 3]);
+    assert_eq!(after, "done");
+}
+
+/// Send 0..20 through a chaos transport seeded with `seed`, returning what arrives
+#[cfg(feature = "test-util")]
+async fn deliver_through_chaos(seed: u64, drop_rate: f64) -> Vec<u32> {
+    use constellation_fabric::transport::ChaosTransport;
+
+    let (a, b) = tokio::io::duplex(4096);
+    let chaos = ChaosTransport::new(StreamTransport::new(a), seed)
+        .with_latency(Duration::ZERO, Duration::from_millis(2))
+        .with_drop_rate(drop_rate);
+    let mut sender = Channel::from_transport(chaos, BincodeCodec);
+    let mut receiver = Channel::from_transport(StreamTransport::new(b), BincodeCodec);
+
+    for n in 0..20u32 {
+        sender.send(&n).await.unwrap();
+    }
+    sender.close().await.unwrap();
+
+    let mut delivered = Vec::new();
+    while let Ok(n) = receiver.receive::<u32>().await {
+        delivered.push(n);
+    }
+    delivered
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn chaos_transport_drops_frames_reproducibly() {
+    assert_eq!(
+        deliver_through_chaos(1, 0.0).await,
+        (0..20).collect::<Vec<_>>()
+    );
+
+    let lossy = deliver_through_chaos(7, 0.5).await;
+    assert!(!lossy.is_empty() && lossy.len() < 20, "{lossy:?}");
+    assert_eq!(deliver_through_chaos(7, 0.5).await, lossy);
+}
+
+#[cfg(feature = "test-util")]
+#[tokio::test]
+async fn chaos_transport_forwards_receive_ready() {
+    use constellation_fabric::transport::ChaosTransport;
+
+    let (a, b) = tokio::io::duplex(4096);
+    let mut sender = StreamTransport::new(a);
+    let mut receiver = ChaosTransport::new(StreamTransport::new(b), 1);
+
+    sender.send(b"one").await.unwrap();
+    sender.send(b"two").await.unwrap();
+    receiver.readable().await.unwrap();
+    assert_eq!(
+        receiver.receive_ready().await.unwrap(),
+        [b"one".to_vec(), b"two".to_vec()]
+    );
+}
+
+#[tokio::test]
+async fn receive_ready_returns_batch_before_frame_limit_error() {
+    let (a, b) = tokio::io::duplex(4096);
+    let mut sender = StreamTransport::new(a);
+    let mut receiver = StreamTransport::new(b);
+    receiver.set_max_frames(Some(2));
+
+    for payload in [b"one", b"two", b"six"] {
+        sender.send(payload).await.unwrap();
+    }
+    receiver.readable().await.unwrap();
+    assert_eq!(
+        receiver.receive_ready().await.unwrap(),
+        [b"one".to_vec(), b"two".to_vec()]
+    );
+    match receiver.receive_ready().await {
+        Err(Error::Custom(msg)) => assert_eq!(msg, "frame limit exceeded"),
+        other => panic!("expected frame limit error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn receive_or_leaves_partial_frame_for_next_receive() {
+    use futures::future::Either;
+
+    let (a, mut peer) = tokio::io::duplex(4096);
+    let mut channel = Channel::from_transport(StreamTransport::new(a), BincodeCodec);
+    let frame = [0, 0, 0, 4, 7, 0, 0, 0];
+
+    // Nothing has arrived, so the other future wins
+    let idle = channel
+        .receive_or::<u32, _>(std::future::ready("shutdown"))
+        .await
+        .unwrap();
+    assert!(matches!(idle, Either::Right("shutdown")));
+
+    // Part of a frame has arrived when the other future wins
+    peer.write_all(&frame[..3]).await.unwrap();
+    let partial = channel
+        .receive_or::<u32, _>(tokio::time::sleep(Duration::from_millis(50)))
+        .await
+        .unwrap();
+    assert!(matches!(partial, Either::Right(())));
+
+    peer.write_all(&frame[3..]).await.unwrap();
+    assert_eq!(channel.receive::<u32>().await.unwrap(), 7);
+
+    peer.write_all(&frame).await.unwrap();
+    let message = channel
+        .receive_or::<u32, _>(tokio::time::sleep(Duration::from_secs(5)))
+        .await
+        .unwrap();
+    assert!(matches!(message, Either::Left(7)));
+}
+
+#[tokio::test]
+async fn readable_applies_timeouts_and_memory_budget() {
+    let (a, mut peer) = tokio::io::duplex(4096);
+    let budget = Arc::new(MemoryBudget::new(1024));
+    let mut transport = StreamTransport::new(a);
+    transport.set_receive_inactivity_timeout(Some(Duration::from_millis(50)));
+    transport.set_memory_budget(Some(budget.clone()));
+
+    // The payload is reserved as soon as the length prefix is in
+    peer.write_all(&[0, 0, 0, 100, 1, 2]).await.unwrap();
+    match transport.readable().await {
+        Err(Error::Custom(msg)) => assert_eq!(msg, "Receive inactivity timeout exceeded"),
+        other => panic!("Expected inactivity timeout, got {:?}", other),
+    }
+    assert_eq!(budget.available(), 924);
+
+    // ...and held until the frame is received
+    peer.write_all(&[0u8; 98]).await.unwrap();
+    transport.readable().await.unwrap();
+    assert_eq!(budget.available(), 924);
+    assert_eq!(transport.receive().await.unwrap().len(), 100);
+    assert_eq!(budget.available(), 1024);
+}
+
+#[tokio::test]
+async fn channel_reports_whether_errors_leave_it_reusable() {
+    let (a, mut peer) = tokio::io::duplex(4096);
+    let mut channel = Channel::from_transport(StreamTransport::new(a), BincodeCodec);
+
+    // A whole frame that fails to decode leaves the stream at a frame boundary
+    peer.write_all(&[0, 0, 0, 1, 7]).await.unwrap();
+    peer.write_all(&[0, 0, 0, 4, 8, 0, 0, 0]).await.unwrap();
+    let err = channel.receive::<u32>().await.unwrap_err();
+    assert!(matches!(err, Error::Codec(_)));
+    assert!(channel.is_reusable_after(&err));
+    assert_eq!(channel.receive::<u32>().await.unwrap(), 8);
+
+    // A frame cut short does not
+    peer.write_all(&[0, 0, 0, 4, 9]).await.unwrap();
+    drop(peer);
+    let err = channel.receive::<u32>().await.unwrap_err();
+    assert!(matches!(err, Error::ConnectionClosed));
+    assert!(!channel.is_reusable_after(&err));
+    assert!(!channel.is_reusable_after(&Error::Codec("late".to_string())));
+}
+
+#[tokio::test]
+async fn frames_rejected_before_writing_keep_connection_open() {
+    let (listener, addr) = get_listener().await;
+    tokio::spawn(async move {
+        let (mut transport, _) = listener.accept().await.unwrap();
+        let payload = transport.receive().await.unwrap();
+        transport.send(&payload).await.unwrap();
+    });
+
+    let mut transport = TcpTransport::connect(addr).await.unwrap();
+    assert!(matches!(
+        transport.send_control(FrameType::Ping, b"").await,
+        Err(Error::InvalidFrame(_))
+    ));
+    assert_eq!(transport.state(), ConnectionState::Connected);
+
+    transport.send(b"still usable").await.unwrap();
+    assert_eq!(transport.receive().await.unwrap(), b"still usable");
+}
+
+#[tokio::test]
+async fn ping_requires_frame_types() {
+    let (listener, addr) = get_listener().await;
+    tokio::spawn(async move {
+        let _conn = listener.accept().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    });
+
+    let mut channel = Channel::tcp(addr, RawCodec).await.unwrap();
+    match channel.ping(Duration::from_millis(100)).await {
+        Err(Error::InvalidFrame(msg)) => assert!(msg.contains("frame types")),
+        other => panic!("Expected InvalidFrame, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn ping_timeout_leaves_partial_frame_unread() {
+    let (a, mut peer) = tokio::io::duplex(4096);
+    let config = framing::FrameConfig {
+        frame_types: true,
+        ..Default::default()
+    };
+    let mut channel = Channel::from_transport(StreamTransport::with_config(a, config), RawCodec);
+
+    // Half a data frame arrives, and no pong
+    let frame = [0, 0, 0, 3, 0, b'h', b'i'];
+    peer.write_all(&frame[..5]).await.unwrap();
+    match channel.ping(Duration::from_millis(50)).await {
+        Err(e @ Error::Custom(_)) => {
+            assert_eq!(e.to_string(), "Ping timeout exceeded");
+            assert!(channel.is_reusable_after(&e));
+        }
+        other => panic!("Expected ping timeout, got {:?}", other),
+    }
+
+    peer.write_all(&frame[5..]).await.unwrap();
+    assert_eq!(channel.receive_encoded().await.unwrap(), b"hi");
+}
+
+#[tokio::test]
+async fn tcp_grpc_framing_carries_compression_flag() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // gRPC-framed peer: flag byte, 4-byte big-endian length, payload
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut frame = [0u8; 10];
+        stream.read_exact(&mut frame).await.unwrap();
+        assert_eq!(frame, [1, 0, 0, 0, 5, b'h', b'e', b'l', b'l', b'o']);
+        stream
+            .write_all(&[1, 0, 0, 0, 2, b'o', b'k'])
+            .await
+            .unwrap();
+        stream.write_all(&[0, 0, 0, 0, 0]).await.unwrap();
+    });
+
+    let mut transport = TcpTransport::builder()
+        .address(addr)
+        .framing_mode(FramingMode::GrpcLengthPrefixed)
+        .connect()
+        .await
+        .unwrap();
+    transport.send_with_header(&[1], b"hello").await.unwrap();
+    let (flag, payload) = transport.receive_with_header::<1>().await.unwrap();
+    assert_eq!((flag, payload.as_slice()), ([1], &b"ok"[..]));
+    assert!(transport.receive().await.unwrap().is_empty());
+
+    // Options the gRPC layout has no room for are rejected
+    let config = framing::FrameConfig {
+        mode: FramingMode::GrpcLengthPrefixed,
+        header_len: 1,
+        frame_types: true,
+        ..Default::default()
+    };
+    let mut wire = Vec::new();
+    assert!(matches!(
+        config.write(&mut wire, FrameType::Data, &[0], b"x").await,
+        Err(Error::InvalidFrame(_))
+    ));
+}
+
+#[tokio::test]
+async fn tcp_delimited_framing_speaks_line_protocol() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Newline-delimited JSON peer, writing two lines in one go and one split in two
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut line = [0u8; 13];
+        stream.read_exact(&mut line).await.unwrap();
+        assert_eq!(&line, b"{\"op\":\"get\"}\n");
+        stream
+            .write_all(b"{\"id\":1}\n{\"id\":2}\n{\"id")
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        stream.write_all(b"\":3}\n").await.unwrap();
+    });
+
+    let transport = TcpTransport::builder()
+        .address(addr)
+        .framing_mode(FramingMode::Delimited(b'\n'))
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(transport.frame_overhead(), 1);
+    let mut channel = Channel::from_transport(transport, RawCodec);
+
+    channel.send_encoded(b"{\"op\":\"get\"}").await.unwrap();
+    for expected in [&b"{\"id\":1}"[..], b"{\"id\":2}", b"{\"id\":3}"] {
+        assert_eq!(channel.receive_encoded().await.unwrap(), expected);
+    }
+    assert!(matches!(
+        channel.receive_encoded().await,
+        Err(Error::ConnectionClosed)
+    ));
+
+    // A payload containing the delimiter would split into two frames
+    let config = framing::FrameConfig {
+        mode: FramingMode::Delimited(b'\n'),
+        ..Default::default()
+    };
+    let mut wire = Vec::new();
+    assert!(matches!(
+        config.write(&mut wire, FrameType::Data, &[], b"a\nb").await,
+        Err(Error::InvalidFrame(msg)) if msg.contains("delimiter")
+    ));
+    config
+        .write(&mut wire, FrameType::Data, &[], b"ab")
+        .await
+        .unwrap();
+    assert_eq!(wire, b"ab\n");
+    let frame = config.read(&mut &wire[..]).await.unwrap();
+    assert_eq!(frame.payload, b"ab");
+
+    // No reader would find the end of a frame past the limit
+    let oversized = vec![b'a'; framing::MAX_FRAME_SIZE + 1];
+    assert!(matches!(
+        config.write(&mut wire, FrameType::Data, &[], &oversized).await,
+        Err(Error::InvalidFrame(msg)) if msg.contains("frame limit")
+    ));
+
+    // Options the layout cannot carry are refused before connecting
+    assert!(matches!(
+        TcpTransport::builder()
+            .address(addr)
+            .framing_mode(FramingMode::Delimited(b'\n'))
+            .frame_types(true)
+            .connect()
+            .await,
+        Err(Error::InvalidFrame(_))
+    ));
+}
+
+#[tokio::test]
+async fn sequence_numbers_detect_gaps_and_reordering() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Each frame carries its number after the length prefix
+    let peer = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut frames = [0u8; 26];
+        stream.read_exact(&mut frames).await.unwrap();
+        frames
+    });
+    let mut sender = TcpTransport::builder()
+        .address(addr)
+        .sequence_numbers(true)
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(sender.frame_overhead(), 12);
+    sender.send(b"a").await.unwrap();
+    // A frame rejected before writing does not use up a number
+    assert!(sender.send_control(FrameType::Ping, b"").await.is_err());
+    sender.send(b"b").await.unwrap();
+    let mut expected = Vec::new();
+    for (sequence, payload) in [(0u64, b'a'), (1, b'b')] {
+        expected.extend_from_slice(&9u32.to_be_bytes());
+        expected.extend_from_slice(&sequence.to_be_bytes());
+        expected.push(payload);
+    }
+    assert_eq!(peer.await.unwrap().to_vec(), expected);
+
+    // A peer that skips a number, then sends the skipped frame late
+    let (a, mut peer) = tokio::io::duplex(4096);
+    let config = framing::FrameConfig {
+        sequence_numbers: true,
+        ..Default::default()
+    };
+    let mut receiver = StreamTransport::with_config(a, config);
+    for sequence in [0u64, 2, 1] {
+        peer.write_u32(9).await.unwrap();
+        peer.write_u64(sequence).await.unwrap();
+        peer.write_u8(b'x').await.unwrap();
+    }
+    assert_eq!(receiver.receive().await.unwrap(), b"x");
+    for (expected, got) in [(1, 2), (3, 1)] {
+        match receiver.receive().await {
+            Err(e @ Error::SequenceGap { .. }) => {
+                assert_eq!(
+                    e.to_string(),
+                    format!("sequence gap: expected {} got {}", expected, got)
+                );
+                assert!(receiver.is_reusable_after(&e));
+            }
+            other => panic!("Expected sequence gap, got {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn tcp_little_endian_length_prefix() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Legacy peer that speaks little-endian prefixes by hand
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut prefix = [0u8; 4];
+        stream.read_exact(&mut prefix).await.unwrap();
+        assert_eq!(prefix, 5u32.to_le_bytes());
+        let mut payload = [0u8; 5];
+        stream.read_exact(&mut payload).await.unwrap();
+        assert_eq!(&payload, b"hello");
+
+        stream.write_all(&[2, 0, 0, 0]).await.unwrap();
+        stream.write_all(b"ok").await.unwrap();
+    });
+
+    let mut client = TcpTransport::builder()
+        .address(addr)
+        .length_endian(Endian::Little)
+        .connect()
+        .await
+        .unwrap();
+
+    client.send(b"hello").await.unwrap();
+    assert_eq!(client.receive().await.unwrap(), b"ok");
+}
+
+#[tokio::test]
+async fn resync_skips_corrupt_frame() {
+    const MARKER: [u8; 4] = *b"SYNC";
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // A frame with a corrupted length, junk, then a good frame
+    tokio::spawn(async move {
+        for _ in 0..2 {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_all(&MARKER).await.unwrap();
+            stream.write_u32(u32::MAX).await.unwrap();
+            stream.write_all(b"junk").await.unwrap();
+            stream.write_all(&MARKER).await.unwrap();
+            stream.write_u32(5).await.unwrap();
+            stream.write_all(b"hello").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    });
+
+    let mut client = TcpTransport::builder()
+        .address(addr)
+        .sync_marker(MARKER)
+        .resync_on_corruption(true)
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(client.receive().await.unwrap(), b"hello");
+
+    // Without resync the corrupt frame is fatal
+    let mut strict = TcpTransport::builder()
+        .address(addr)
+        .sync_marker(MARKER)
+        .connect()
+        .await
+        .unwrap();
+    match strict.receive().await {
+        Err(Error::InvalidFrame(msg)) => assert!(msg.contains("too large")),
+        other => panic!("Expected InvalidFrame, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn stream_payload_from_reader_to_writer() {
+    let (listener, addr) = get_listener().await;
+    let data: Vec<u8> = (0..1_000_000u32).map(|i| i as u8).collect();
+    let expected = data.clone();
+
+    // Server streams the frame into a writer instead of a Vec of its own
+    let server = tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        let mut sink = Vec::new();
+        let len = transport.receive_to_writer(&mut sink).await.unwrap();
+        let _ = transport.receive().await; // Hold the connection open
+        (len, sink)
+    });
+
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+    let mut reader = std::io::Cursor::new(data);
+    client
+        .send_from_reader(&mut reader, expected.len())
+        .await
+        .unwrap();
+
+    // A reader that runs dry leaves a truncated frame behind, which ends the connection
+    let mut short = std::io::Cursor::new(vec![0u8; 3]);
+    match client.send_from_reader(&mut short, 10).await {
+        Err(Error::InvalidFrame(msg)) => assert_eq!(msg, "Source ended after 3 of 10 bytes"),
+        other => panic!("Expected InvalidFrame, got {:?}", other),
+    }
+    assert!(client.is_closed());
+    drop(client);
+
+    let (len, sink) = server.await.unwrap();
+    assert_eq!(len, expected.len());
+    assert_eq!(sink, expected);
+}
+
+#[tokio::test]
+async fn length_checksum_rejects_corrupted_prefix() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // One valid frame, then a prefix whose length bits were flipped in transit
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let prefix = 2u32.to_be_bytes();
+        stream.write_all(&prefix).await.unwrap();
+        stream.write_u32(crc32fast::hash(&prefix)).await.unwrap();
+        stream.write_all(b"ok").await.unwrap();
+
+        stream
+            .write_all(&0x7fff_ffffu32.to_be_bytes())
+            .await
+            .unwrap();
+        stream.write_u32(crc32fast::hash(&prefix)).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    });
+
+    let mut client = TcpTransport::builder()
+        .address(addr)
+        .length_checksum(true)
+        .connect()
+        .await
+        .unwrap();
+
+    assert_eq!(client.receive().await.unwrap(), b"ok");
+    match client.receive().await {
+        Err(Error::InvalidFrame(msg)) => assert!(msg.contains("checksum mismatch")),
+        other => panic!("Expected InvalidFrame, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn memory_budget_bounds_receive_buffers() {
+    let budget = Arc::new(MemoryBudget::new(64));
+    let listener = TcpTransportListener::builder()
+        .memory_budget(budget.clone())
+        .bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let (mut transport, _) = listener.accept().await.unwrap();
+        let small = transport.receive().await.unwrap();
+        let large = transport.receive().await;
+        (small, large, transport.is_closed())
+    });
+
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+    client.send(&[1u8; 32]).await.unwrap();
+    client.send(&[2u8; 128]).await.unwrap();
+
+    let (small, large, closed) = server.await.unwrap();
+    assert_eq!(small, vec![1u8; 32]);
+    match large {
+        Err(Error::InvalidFrame(msg)) => assert!(msg.contains("memory budget")),
+        other => panic!("Expected InvalidFrame, got {:?}", other),
+    }
+    // The rejected payload was never read, so the stream is out of step
+    assert!(closed);
+    // Reservations are released once each receive finishes, even with `small` still held
+    assert_eq!(budget.available(), budget.capacity());
+}
+
+#[cfg(feature = "bytes")]
+#[tokio::test]
+async fn receive_shared_clones_share_one_allocation() {
+    let (listener, addr) = get_listener().await;
+
+    tokio::spawn(async move {
+        let (mut transport, _) = listener.accept().await.unwrap();
+        transport.send(b"broadcast me").await.unwrap();
+    });
+
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+    let frame = client.receive_shared().await.unwrap();
+    let copies: Vec<_> = (0..4).map(|_| frame.clone()).collect();
+
+    assert_eq!(&frame[..], b"broadcast me");
+    for copy in &copies {
+        assert_eq!(copy.as_ptr(), frame.as_ptr());
+    }
+}
+
+#[cfg(feature = "tokio-codec")]
+#[tokio::test]
+async fn fabric_codec_interoperates_with_transport() {
+    use constellation_fabric::transport::FabricCodec;
+    use futures::{SinkExt, StreamExt};
+    use tokio_util::codec::Framed;
+
+    let listener = TcpTransportListener::bind("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (mut transport, _) = listener.accept().await.unwrap();
+        let msg = transport.receive().await.unwrap();
+        transport.send(&msg).await.unwrap();
+    });
+
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let mut framed = Framed::new(stream, FabricCodec::new());
+    framed.send(&b"hello framed"[..]).await.unwrap();
+    let echoed = framed.next().await.unwrap().unwrap();
+    assert_eq!(&echoed[..], b"hello framed");
+}
+
+#[cfg(feature = "tokio-codec")]
+#[test]
+fn fabric_codec_rejects_oversized_frame() {
+    use constellation_fabric::transport::FabricCodec;
+    use tokio_util::codec::Decoder;
+
+    let mut src = bytes::BytesMut::from(&(200 * 1024 * 1024u32).to_be_bytes()[..]);
+    match FabricCodec::new().decode(&mut src) {
+        Err(Error::InvalidFrame(msg)) => assert!(msg.contains("too large")),
+        other => panic!("Expected InvalidFrame, got {:?}", other),
+    }
+}
+
+#[cfg(feature = "tokio-codec")]
+#[test]
+fn fabric_codec_refuses_to_encode_oversized_payload() {
+    use constellation_fabric::transport::{framing::MAX_FRAME_SIZE, FabricCodec};
+    use tokio_util::codec::Encoder;
+
+    let payload = vec![0u8; MAX_FRAME_SIZE + 1];
+    let mut dst = bytes::BytesMut::new();
+    match FabricCodec::new().encode(&payload[..], &mut dst) {
+        Err(Error::InvalidFrame(msg)) => assert!(msg.contains("frame limit")),
+        other => panic!("Expected InvalidFrame, got {:?}", other),
+    }
+    assert!(dst.is_empty());
+}
+
+#[test]
+fn wire_format_fingerprint_is_stable() {
+    use constellation_fabric::compat::{wire_format_fingerprint, wire_format_sample};
+
+    // Changing any of these bytes breaks mixed-version deployments
+    let expected = concat!(
+        "0000003c",                                   // big-endian length prefix
+        "04030201",                                   // id: u32, little-endian
+        "0d00000000000000636f6e7374656c6c6174696f6e", // name: u64 length + UTF-8
+        "02000000000000000100ffff",                   // tags: Vec<u16>
+        "012a00000000000000",                         // parent: Some(42u64)
+        "01000000ff01",                               // kind: variant 1 (-1i8, true)
+        "000000000000e03f",                           // ratio: 0.5f64
+    );
+    assert_eq!(wire_format_fingerprint(), expected);
+    assert_eq!(wire_format_sample().len(), 4 + 0x3c);
+}
+
+#[tokio::test]
+async fn prefix_width_u64_rejects_huge_claim_without_allocating() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // An 8-byte prefix claiming 5GB, with no payload behind it
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        stream.write_u64(5 * 1024 * 1024 * 1024).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    });
+
+    let mut client = TcpTransport::builder()
+        .address(addr)
+        .length_width(PrefixWidth::U64)
+        .connect()
+        .await
+        .unwrap();
+
+    match client.receive().await {
+        Err(Error::InvalidFrame(msg)) => assert!(msg.contains("too large")),
+        other => panic!("Expected InvalidFrame, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn prefix_width_u16_roundtrip_and_limit() {
+    let config = framing::FrameConfig {
+        length_width: PrefixWidth::U16,
+        length_endian: Endian::Little,
+        ..Default::default()
+    };
+    let (mut a, mut b) = tokio::io::duplex(1024);
+
+    config
+        .write(&mut a, FrameType::Data, &[], b"small")
+        .await
+        .unwrap();
+    let mut prefix = [0u8; 2];
+    b.read_exact(&mut prefix).await.unwrap();
+    assert_eq!(u16::from_le_bytes(prefix), 5);
+    let mut payload = [0u8; 5];
+    b.read_exact(&mut payload).await.unwrap();
+
+    config
+        .write(&mut a, FrameType::Data, &[], b"again")
+        .await
+        .unwrap();
+    assert_eq!(config.read(&mut b).await.unwrap().payload, b"again");
+
+    match config
+        .write(&mut a, FrameType::Data, &[], &vec![0u8; 70_000])
+        .await
+    {
+        Err(Error::InvalidFrame(msg)) => assert!(msg.contains("does not fit")),
+        other => panic!("Expected InvalidFrame, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn tcp_rejects_oversized_frame() {
+    // Test that our framing validates message size limits
+    // We test this by sending a raw malformed frame header
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Spawn server that sends a malformed frame with huge size claim
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        // Write frame header claiming 200MB (over our 100MB limit)
+        stream.write_u32(200 * 1024 * 1024).await.unwrap();
+        stream.flush().await.unwrap();
+
+        // Keep connection open
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    });
+
+    // Give server time to start
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // Client should reject the oversized frame
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+
+    let result = client.receive().await;
+    assert!(result.is_err());
+    match result.unwrap_err() {
+        Error::InvalidFrame(msg) => assert!(msg.contains("too large")),
+        _ => panic!("Expected InvalidFrame error"),
+    }
+}
+
+#[tokio::test]
+async fn framing_works_over_any_stream() {
+    let (mut a, mut b) = tokio::io::duplex(64);
+
+    framing::write_frame(&mut a, b"framed").await.unwrap();
+    assert_eq!(framing::read_frame(&mut b).await.unwrap(), b"framed");
+
+    let transport = TcpTransport::connect(get_listener().await.1).await.unwrap();
+    assert_eq!(transport.kind(), TransportKind::Stream);
+}
+
+#[tokio::test]
+async fn channel_with_codec_roundtrip() {
+    let (listener, addr) = get_listener().await;
+
+    let expected_msg = TestMessage {
+        id: 42,
+        data: "test data".to_string(),
+    };
+    let expected_clone = expected_msg.clone();
+
+    // Spawn server
+    tokio::spawn(async move {
+        let (transport, _addr) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+
+        let msg: TestMessage = channel.receive().await.unwrap();
+        channel.send(&msg).await.unwrap(); // Echo back
+    });
+
+    // Client
+    let transport = TcpTransport::connect(addr).await.unwrap();
+    let mut channel = Channel::from_transport(transport, BincodeCodec);
+
+    channel.send(&expected_msg).await.unwrap();
+    let response: TestMessage = channel.receive().await.unwrap();
+
+    assert_eq!(response, expected_clone);
+}
+
+#[tokio::test]
+async fn typed_channel_roundtrip() {
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Ack {
+        id: u32,
+    }
+
+    let (listener, addr) = get_listener().await;
+
+    // Spawn server with the mirrored type pair
+    tokio::spawn(async move {
+        let (transport, _addr) = listener.accept().await.unwrap();
+        let mut channel: TypedChannel<Ack, TestMessage, _> =
+            TypedChannel::from_transport(transport, BincodeCodec);
+
+        let msg = channel.receive().await.unwrap();
+        channel.send(&Ack { id: msg.id }).await.unwrap();
+    });
+
+    let mut channel: TypedChannel<TestMessage, Ack, _> =
+        TypedChannel::tcp(addr, BincodeCodec).await.unwrap();
+
+    channel
+        .send(&TestMessage {
+            id: 9,
+            data: "typed".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(channel.receive().await.unwrap(), Ack { id: 9 });
+}
+
+#[tokio::test]
+async fn channel_peek_keeps_frame_for_receive() {
+    let (listener, addr) = get_listener().await;
+
+    // Spawn server that sends two messages
+    tokio::spawn(async move {
+        let (transport, _addr) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        for id in [1, 2] {
+            channel
+                .send(&TestMessage {
+                    id,
+                    data: "peek".to_string(),
+                })
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut channel = Channel::tcp(addr, BincodeCodec).await.unwrap();
+
+    let peeked: TestMessage = channel.peek().await.unwrap();
+    let peeked_again: TestMessage = channel.peek().await.unwrap();
+    assert_eq!(peeked.id, 1);
+    assert_eq!(peeked_again, peeked);
+
+    let first: TestMessage = channel.receive().await.unwrap();
+    assert_eq!(first, peeked);
+
+    let second: TestMessage = channel.receive().await.unwrap();
+    assert_eq!(second.id, 2);
+}
+
+#[tokio::test]
+async fn debug_output_shows_metadata_not_payloads() {
+    let (listener, addr) = get_listener().await;
+    assert!(format!("{:?}", listener).contains(&addr.to_string()));
+
+    tokio::spawn(async move {
+        let (transport, _addr) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        channel
+            .send(&TestMessage {
+                id: 1,
+                data: "secret-payload".to_string(),
+            })
+            .await
+            .unwrap();
+    });
+
+    let transport = TcpTransport::builder()
+        .address(addr)
+        .receive_timeout(Duration::from_secs(5))
+        .connect()
+        .await
+        .unwrap();
+    let transport_debug = format!("{:?}", transport);
+    assert!(transport_debug.contains(&addr.to_string()));
+    assert!(transport_debug.contains("5s"));
+
+    // A peeked frame stays buffered in the channel
+    let mut channel = Channel::from_transport(transport, BincodeCodec);
+    let _: TestMessage = channel.peek().await.unwrap();
+    let channel_debug = format!("{:?}", channel);
+    assert!(channel_debug.contains("pending_frames: 1"));
+    assert!(channel_debug.contains(&addr.to_string()));
+    assert!(!channel_debug.contains("secret-payload"));
+}
+
+#[tokio::test]
+async fn receive_into_reuses_buffer_allocation() {
+    let (listener, addr) = get_listener().await;
+
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        transport.send(b"small").await.unwrap();
+        transport.send(&[7u8; 4096]).await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        for data in ["a", "much longer message than the hint", "b"] {
+            channel
+                .send(&TestMessage {
+                    id: 0,
+                    data: data.to_string(),
+                })
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+    let mut buf = Vec::with_capacity(1024);
+    let ptr = buf.as_ptr();
+    client.receive_into(&mut buf).await.unwrap();
+    assert_eq!(buf, b"small");
+    assert_eq!(buf.as_ptr(), ptr);
+    client.receive_into(&mut buf).await.unwrap();
+    assert_eq!(buf, vec![7u8; 4096]);
+
+    // Frames beyond the hint are still received
+    let mut channel = Channel::from_transport(client, BincodeCodec).with_receive_buffer_hint(16);
+    for data in ["a", "much longer message than the hint", "b"] {
+        let msg: TestMessage = channel.receive().await.unwrap();
+        assert_eq!(msg.data, data);
+    }
+}
+
+#[tokio::test]
+async fn channel_receive_lenient_skips_undecodable_frames() {
+    let (listener, addr) = get_listener().await;
+
+    // Good frame, garbage frame, good frame
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        transport
+            .send(&BincodeCodec.encode(&1u32).unwrap())
+            .await
+            .unwrap();
+        transport.send(&[0xff]).await.unwrap();
+        transport
+            .send(&BincodeCodec.encode(&2u32).unwrap())
+            .await
+            .unwrap();
+    });
+
+    let mut channel = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    assert_eq!(channel.receive_lenient::<u32>().await.unwrap(), Some(1));
+    assert_eq!(channel.receive_lenient::<u32>().await.unwrap(), None);
+    assert_eq!(channel.receive_lenient::<u32>().await.unwrap(), Some(2));
+
+    // Transport errors still propagate
+    match channel.receive_lenient::<u32>().await {
+        Err(Error::ConnectionClosed) => {}
+        other => panic!("Expected ConnectionClosed, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn channel_receive_with_bytes_returns_original_frame() {
+    let (listener, addr) = get_listener().await;
+    let msg = TestMessage {
+        id: 3,
+        data: "audit".to_string(),
+    };
+    let expected = BincodeCodec.encode(&msg).unwrap();
+
+    let frame = expected.clone();
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        transport.send(&frame).await.unwrap();
+    });
+
+    let mut channel = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    let (decoded, bytes): (TestMessage, _) = channel.receive_with_bytes().await.unwrap();
+    assert_eq!(decoded, msg);
+    assert_eq!(bytes, expected);
+}
+
+#[tokio::test]
+async fn channel_inspectors_see_raw_frames() {
+    let (listener, addr) = get_listener().await;
+
+    // Echo server
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        for _ in 0..2 {
+            let bytes = transport.receive().await.unwrap();
+            transport.send(&bytes).await.unwrap();
+        }
+    });
+
+    let inbound = Arc::new(Mutex::new(Vec::new()));
+    let outbound = Arc::new(Mutex::new(Vec::new()));
+    let (inbound_tap, outbound_tap) = (inbound.clone(), outbound.clone());
+    let mut channel = Channel::tcp(addr, BincodeCodec)
+        .await
+        .unwrap()
+        .with_inbound_inspector(move |bytes| inbound_tap.lock().unwrap().push(bytes.to_vec()))
+        .with_outbound_inspector(move |bytes| outbound_tap.lock().unwrap().push(bytes.to_vec()));
+
+    let msg = TestMessage {
+        id: 1,
+        data: "tap".to_string(),
+    };
+    channel.send(&msg).await.unwrap();
+    channel.send_encoded(b"raw").await.unwrap();
+
+    // A peeked frame is only inspected once
+    let _: TestMessage = channel.peek().await.unwrap();
+    let _: TestMessage = channel.receive().await.unwrap();
+    channel.receive_encoded().await.unwrap();
+
+    let expected = vec![BincodeCodec.encode(&msg).unwrap(), b"raw".to_vec()];
+    assert_eq!(*outbound.lock().unwrap(), expected);
+    assert_eq!(*inbound.lock().unwrap(), expected);
+}
+
+#[tokio::test]
+async fn channel_reuses_send_buffer() {
+    let (listener, addr) = get_listener().await;
+
+    // Echo server
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        while let Ok(bytes) = transport.receive().await {
+            transport.send(&bytes).await.unwrap();
+        }
+    });
+
+    let mut channel = Channel::tcp(addr, BincodeCodec).await.unwrap();
+
+    // Alternate sizes so a reused buffer would expose leftover bytes
+    for id in 0..50 {
+        let msg = TestMessage {
+            id,
+            data: "y".repeat(if id % 2 == 0 { 1000 } else { 3 }),
+        };
+        channel.send(&msg).await.unwrap();
+        let echoed: TestMessage = channel.receive().await.unwrap();
+        assert_eq!(echoed, msg);
+    }
+}
+
+#[tokio::test]
+async fn channel_connect_then_runs_handshake_first() {
+    let (listener, addr) = get_listener().await;
+
+    // Server requires a greeting before anything else
+    tokio::spawn(async move {
+        let (transport, _addr) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        let greeting: String = channel.receive().await.unwrap();
+        assert_eq!(greeting, "HELLO");
+        channel.send(&"WELCOME".to_string()).await.unwrap();
+        let msg: TestMessage = channel.receive().await.unwrap();
+        channel.send(&msg).await.unwrap();
+    });
+
+    let mut channel = Channel::connect_then(addr, BincodeCodec, async |chan| {
+        chan.send(&"HELLO".to_string()).await?;
+        let reply: String = chan.receive().await?;
+        assert_eq!(reply, "WELCOME");
+        Ok(())
+    })
+    .await
+    .unwrap();
+
+    let msg = TestMessage {
+        id: 9,
+        data: "after handshake".to_string(),
+    };
+    channel.send(&msg).await.unwrap();
+    let echoed: TestMessage = channel.receive().await.unwrap();
+    assert_eq!(echoed, msg);
+
+    // A failing setup surfaces its error instead of a channel
+    let (listener, addr) = get_listener().await;
+    tokio::spawn(async move {
+        let _conn = listener.accept().await.unwrap();
+    });
+    let result = Channel::connect_then(addr, BincodeCodec, async |_chan| {
+        Err(Error::Custom("rejected".to_string()))
+    })
+    .await;
+    assert!(matches!(result, Err(Error::Custom(msg)) if msg == "rejected"));
+}
+
+#[tokio::test]
+async fn respond_once_answers_request_tcp() {
+    let (listener, addr) = get_listener().await;
+
+    let server = tokio::spawn(async move {
+        respond_once(&listener, BincodeCodec, |request: TestMessage| async move {
+            TestMessage {
+                id: request.id + 1,
+                data: request.data.to_uppercase(),
+            }
+        })
+        .await
+    });
+
+    let request = TestMessage {
+        id: 1,
+        data: "ping".to_string(),
+    };
+    let response: TestMessage = request_tcp(addr, &request, BincodeCodec).await.unwrap();
+    assert_eq!(response.id, 2);
+    assert_eq!(response.data, "PING");
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn broadcast_tcp_reports_each_destination() {
+    let (first, first_addr) = get_listener().await;
+    let (second, second_addr) = get_listener().await;
+    let (dead, dead_addr) = get_listener().await;
+    drop(dead);
+
+    let receive = |listener: TcpTransportListener| async move {
+        let (transport, _) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        channel.receive::<TestMessage>().await.unwrap()
+    };
+    let first = tokio::spawn(receive(first));
+    let second = tokio::spawn(receive(second));
+
+    let message = TestMessage {
+        id: 9,
+        data: "config changed".to_string(),
+    };
+    let results = broadcast_tcp(
+        &[first_addr, dead_addr, second_addr],
+        &message,
+        BincodeCodec,
+    )
+    .await;
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(Error::Io(_))));
+    assert!(results[2].is_ok());
+    assert_eq!(first.await.unwrap(), message);
+    assert_eq!(second.await.unwrap(), message);
+}
+
+#[tokio::test]
+async fn broadcast_tcp_with_timeout_gives_up_on_a_stalled_destination() {
+    let (stalled, stalled_addr) = get_listener().await;
+    let (ready, ready_addr) = get_listener().await;
+
+    // One destination accepts but never reads, so a large send cannot finish
+    let held = tokio::spawn(async move {
+        let (transport, _) = stalled.accept().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        drop(transport);
+    });
+    let received = tokio::spawn(async move {
+        let (transport, _) = ready.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        channel.receive::<String>().await.unwrap().len()
+    });
+
+    let message = "x".repeat(32 * 1024 * 1024);
+    let results = broadcast_tcp_with_timeout(
+        &[stalled_addr, ready_addr],
+        &message,
+        BincodeCodec,
+        Duration::from_secs(2),
+    )
+    .await;
+    match &results[0] {
+        Err(Error::Custom(msg)) => assert_eq!(msg, "Broadcast timeout exceeded"),
+        other => panic!("Expected broadcast timeout, got {:?}", other),
+    }
+    assert!(results[1].is_ok());
+    assert_eq!(received.await.unwrap(), message.len());
+    held.abort();
+}
+
+#[tokio::test]
+async fn request_tcp_resilient_retries_attempts_that_time_out() {
+    use constellation_fabric::subscription::Backoff;
+
+    let (listener, addr) = get_listener().await;
+
+    // Accept every attempt but never answer
+    let server = tokio::spawn(async move {
+        let mut held = Vec::new();
+        for _ in 0..2 {
+            held.push(listener.accept().await.unwrap());
+        }
+        held
+    });
+
+    let policy = Backoff::new()
+        .initial(Duration::from_millis(10))
+        .max_attempts(2)
+        .attempt_timeout(Duration::from_millis(50));
+    let result: Result<u32, Error> = request_tcp_resilient(addr, &1u32, BincodeCodec, policy).await;
+    match result {
+        Err(Error::Custom(msg)) => assert_eq!(msg, "Attempt timeout exceeded"),
+        other => panic!("Expected attempt timeout, got {:?}", other),
+    }
+    assert_eq!(server.await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn request_tcp_idempotent_retries_dropped_connection_only() {
+    use constellation_fabric::subscription::Backoff;
+
+    let (listener, addr) = get_listener().await;
+
+    let server = tokio::spawn(async move {
+        let mut keys = Vec::new();
+
+        // Read the request, then drop the connection without answering
+        let (transport, _) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        let request: Idempotent<TestMessage> = channel.receive().await.unwrap();
+        keys.push(request.key);
+        drop(channel);
+
+        // Answer the retry with an application-level error
+        let (transport, _) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        let request: Idempotent<TestMessage> = channel.receive().await.unwrap();
+        keys.push(request.key);
+        let response: Result<u32, String> = Err(format!("rejected {}", request.request.id));
+        channel.send(&response).await.unwrap();
+
+        // The error response must not be retried
+        let retried = tokio::time::timeout(Duration::from_millis(200), listener.accept()).await;
+        assert!(retried.is_err());
+        keys
+    });
+
+    let request = TestMessage {
+        id: 7,
+        data: "transfer".to_string(),
+    };
+    let response: Result<u32, String> = request_tcp_idempotent(
+        addr,
+        "req-7",
+        &request,
+        BincodeCodec,
+        Backoff::new()
+            .initial(Duration::from_millis(10))
+            .max_attempts(3),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response, Err("rejected 7".to_string()));
+    assert_eq!(server.await.unwrap(), ["req-7", "req-7"]);
+}
+
+#[tokio::test]
+async fn response_envelope_carries_success_and_rpc_error() {
+    let (listener, addr) = get_listener().await;
+    let mut client = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    let (transport, _) = listener.accept().await.unwrap();
+    let mut server = Channel::from_transport(transport, BincodeCodec);
+
+    server.send_response(Ok(&42u32)).await.unwrap();
+    server
+        .send_response::<u32>(Err(RpcError::new(404, "no such key")))
+        .await
+        .unwrap();
+
+    assert_eq!(client.receive_response::<u32>().await.unwrap(), Ok(42));
+    let error = client.receive_response::<u32>().await.unwrap().unwrap_err();
+    assert_eq!(error, RpcError::new(404, "no such key"));
+    assert_eq!(error.to_string(), "RPC error 404: no such key");
+}
+
+#[tokio::test]
+async fn circuit_breaker_opens_and_recovers_after_probe() {
+    let (listener, addr) = get_listener().await;
+    drop(listener);
+
+    let breaker = CircuitBreaker::new(2, Duration::from_millis(100));
+    for _ in 0..2 {
+        let result: Result<u32, _> = breaker.request(addr, &1u32, BincodeCodec).await;
+        assert!(matches!(result, Err(Error::Io(_))));
+    }
+    assert_eq!(breaker.state(), BreakerState::Open);
+
+    let result: Result<u32, _> = breaker.request(addr, &1u32, BincodeCodec).await;
+    assert!(matches!(result, Err(Error::Custom(msg)) if msg == "circuit open"));
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(breaker.state(), BreakerState::HalfOpen);
+
+    let listener = TcpTransportListener::bind(addr).await.unwrap();
+    let server = tokio::spawn(async move {
+        respond_once(&listener, BincodeCodec, |n: u32| async move { n + 1 }).await
+    });
+
+    let response: u32 = breaker.request(addr, &1u32, BincodeCodec).await.unwrap();
+    assert_eq!(response, 2);
+    assert_eq!(breaker.state(), BreakerState::Closed);
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn circuit_breaker_failed_probe_reopens() {
+    let breaker = CircuitBreaker::new(1, Duration::from_millis(50));
+    let failing = || async { Err::<(), _>(Error::ConnectionClosed) };
+
+    assert!(matches!(
+        breaker.call(failing()).await,
+        Err(Error::ConnectionClosed)
+    ));
+    assert_eq!(breaker.state(), BreakerState::Open);
+
+    tokio::time::sleep(Duration::from_millis(80)).await;
+    assert!(matches!(
+        breaker.call(failing()).await,
+        Err(Error::ConnectionClosed)
+    ));
+    assert_eq!(breaker.state(), BreakerState::Open);
+    assert!(matches!(
+        breaker.call(async { Ok(()) }).await,
+        Err(Error::Custom(msg)) if msg == "circuit open"
+    ));
+}
+
+#[tokio::test]
+async fn channel_pool_warmup_parks_up_to_max_idle() {
+    let (_listener, addr) = get_listener().await;
+    let pool = ChannelPool::new(BincodeCodec, 2);
+
+    assert!(pool.warmup(addr, 5).await.is_empty());
+    assert_eq!(pool.idle_count(addr), 2);
+
+    let client = pool.get(addr).await.unwrap();
+    assert_eq!(pool.idle_count(addr), 1);
+    assert!(!client.is_closed());
+
+    assert!(pool.put(addr, client));
+    let extra = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    assert!(!pool.put(addr, extra));
+    assert_eq!(pool.idle_count(addr), 2);
+}
+
+#[tokio::test]
+async fn channel_pool_warmup_reports_connect_failures() {
+    let (listener, addr) = get_listener().await;
+    drop(listener);
+    let pool = ChannelPool::new(BincodeCodec, 4);
+
+    let errors = pool.warmup(addr, 3).await;
+    assert_eq!(errors.len(), 3);
+    assert_eq!(pool.idle_count(addr), 0);
+}
+
+#[tokio::test]
+async fn priority_channel_writes_urgent_messages_first() {
+    let (listener, addr) = get_listener().await;
+    let client = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    let (transport, _) = listener.accept().await.unwrap();
+    let mut server = Channel::from_transport(transport, BincodeCodec);
+
+    let sender = PriorityChannel::new(client, 2);
+    // Large enough to fill the socket buffers, so the writer blocks on it
+    sender.send(&vec![0u8; 16 * 1024 * 1024]).unwrap();
+    while sender.queued() > 0 {
+        tokio::task::yield_now().await;
+    }
+
+    sender.send(&vec![1u8]).unwrap();
+    sender.send(&vec![2u8]).unwrap();
+    sender.send_priority(&vec![9u8], 0).unwrap();
+
+    let mut order = Vec::new();
+    for _ in 0..4 {
+        let message: Vec<u8> = server.receive().await.unwrap();
+        order.push(message[0]);
+    }
+    assert_eq!(order, vec![0, 9, 1, 2]);
+
+    let mut client = sender.shutdown().await.unwrap();
+    client.send(&vec![3u8]).await.unwrap();
+    assert_eq!(server.receive::<Vec<u8>>().await.unwrap(), vec![3]);
+}
+
+#[tokio::test]
+async fn priority_channel_drop_writes_queued_then_closes() {
+    let (listener, addr) = get_listener().await;
+    let client = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    let (transport, _) = listener.accept().await.unwrap();
+    let mut server = Channel::from_transport(transport, BincodeCodec);
+
+    let sender = PriorityChannel::new(client, 1);
+    sender.send(&1u32).unwrap();
+    sender.send(&2u32).unwrap();
+    drop(sender);
+
+    assert_eq!(server.receive::<u32>().await.unwrap(), 1);
+    assert_eq!(server.receive::<u32>().await.unwrap(), 2);
+    assert!(matches!(
+        server.receive::<u32>().await,
+        Err(Error::ConnectionClosed)
+    ));
+}
+
+#[tokio::test]
+async fn priority_channel_drops_expired_messages() {
+    let (listener, addr) = get_listener().await;
+    let client = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    let (transport, _) = listener.accept().await.unwrap();
+    let mut server = Channel::from_transport(transport, BincodeCodec);
+
+    let expired = Arc::new(Mutex::new(Vec::new()));
+    let seen = expired.clone();
+    let sender = PriorityChannel::new(client, 2).with_on_expired(move |priority, bytes| {
+        let message: Vec<u8> = BincodeCodec.decode(bytes).unwrap();
+        seen.lock().unwrap().push((priority, message[0]));
+    });
+
+    // Block the writer on a frame larger than the socket buffers
+    sender.send(&vec![0u8; 16 * 1024 * 1024]).unwrap();
+    while sender.queued() > 0 {
+        tokio::task::yield_now().await;
+    }
+    sender
+        .send_with_ttl(&vec![1u8], 0, Duration::from_millis(10))
+        .unwrap();
+    sender
+        .send_with_ttl(&vec![2u8], 0, Duration::from_secs(60))
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let first: Vec<u8> = server.receive().await.unwrap();
+    assert_eq!(first.len(), 16 * 1024 * 1024);
+    assert_eq!(server.receive::<Vec<u8>>().await.unwrap(), vec![2]);
+    assert_eq!(*expired.lock().unwrap(), vec![(0, 1)]);
+    sender.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn priority_channel_send_deadline_starts_when_queued() {
+    let (listener, addr) = get_listener().await;
+    let client = Channel::tcp(addr, BincodeCodec)
+        .await
+        .unwrap()
+        .with_send_deadline(Duration::from_millis(20));
+    let (transport, _) = listener.accept().await.unwrap();
+    let mut server = Channel::from_transport(transport, BincodeCodec);
+
+    let expired = Arc::new(Mutex::new(Vec::new()));
+    let seen = expired.clone();
+    let sender = PriorityChannel::new(client, 1).with_on_expired(move |_, bytes| {
+        let message: u32 = BincodeCodec.decode(bytes).unwrap();
+        seen.lock().unwrap().push(message);
+    });
+
+    // Keep the writer from running until the queued message is past its deadline
+    tokio::time::pause();
+    sender.send(&1u32).unwrap();
+    tokio::time::advance(Duration::from_millis(40)).await;
+    sender.send(&2u32).unwrap();
+
+    assert_eq!(server.receive::<u32>().await.unwrap(), 2);
+    assert_eq!(*expired.lock().unwrap(), vec![1]);
+    sender.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn sequenced_channel_alternates_send_and_receive() {
+    let (listener, addr) = get_listener().await;
+
+    let server = tokio::spawn(async move {
+        let (transport, _) = listener.accept().await.unwrap();
+        let mut server = SequencedChannel::server(Channel::from_transport(transport, BincodeCodec));
+        for _ in 0..2 {
+            let (n, replying) = server.receive::<u32>().await.unwrap();
+            server = replying.send(&(n * 10)).await.unwrap();
+        }
+    });
+
+    let mut client = SequencedChannel::client(Channel::tcp(addr, BincodeCodec).await.unwrap());
+    for n in 1..=2u32 {
+        let (reply, next) = client
+            .send(&n)
+            .await
+            .unwrap()
+            .receive::<u32>()
+            .await
+            .unwrap();
+        assert_eq!(reply, n * 10);
+        client = next;
+    }
+    server.await.unwrap();
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn respond_once_serves_unix_listener() {
+    let socket_path = "/tmp/constellation_test_respond_once.sock";
+    let _ = std::fs::remove_file(socket_path);
+    let listener = UnixTransportListener::bind(socket_path).await.unwrap();
+
+    let server = tokio::spawn(async move {
+        respond_once(&listener, BincodeCodec, |n: u32| async move { n * 2 }).await
+    });
+
+    let response: u32 = request_unix(socket_path, &21u32, BincodeCodec)
+        .await
+        .unwrap();
+    assert_eq!(response, 42);
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn subscription_stream_resubscribes_after_disconnect() {
+    use constellation_fabric::subscription::{Backoff, StreamItem, SubscriptionStream};
+    use futures::StreamExt;
+
+    let (listener, addr) = get_listener().await;
+
+    // Each connection must subscribe, gets one event, then is dropped
+    tokio::spawn(async move {
+        for id in [1u32, 2] {
+            let (transport, _) = listener.accept().await.unwrap();
+            let mut channel = Channel::from_transport(transport, BincodeCodec);
+            let topic: String = channel.receive().await.unwrap();
+            assert_eq!(topic, "events");
+            channel.send(&id).await.unwrap();
+            channel.close().await.unwrap();
+        }
+    });
+
+    let mut events = SubscriptionStream::<u32>::with_backoff(
+        move || Channel::tcp(addr, BincodeCodec),
+        "events".to_string(),
+        Backoff::new().initial(Duration::from_millis(10)),
+    );
+
+    assert_eq!(events.next().await.unwrap().unwrap(), StreamItem::Event(1));
+    assert_eq!(
+        events.next().await.unwrap().unwrap(),
+        StreamItem::Reconnected
+    );
+    assert_eq!(events.next().await.unwrap().unwrap(), StreamItem::Event(2));
+
+    // Nobody listens any more, so the retries run out
+    let mut gone = SubscriptionStream::<u32>::with_backoff(
+        move || Channel::tcp(addr, BincodeCodec),
+        "events".to_string(),
+        Backoff::new()
+            .initial(Duration::from_millis(10))
+            .max_attempts(2),
+    );
+    assert!(gone.next().await.unwrap().is_err());
+    assert!(gone.next().await.is_none());
+}
+
+#[tokio::test]
+async fn subscription_stream_reconnects_after_unrecoverable_errors() {
+    use constellation_fabric::subscription::{Backoff, StreamItem, SubscriptionStream};
+    use futures::StreamExt;
+
+    let (listener, addr) = get_listener().await;
+
+    tokio::spawn(async move {
+        // An event that fails to decode, then a corrupt length prefix
+        let (mut transport, _) = listener.accept().await.unwrap();
+        transport.receive().await.unwrap();
+        transport.send(b"no").await.unwrap();
+        let mut stream = transport.into_inner().await.unwrap();
+        stream.write_all(&[0xff; 8]).await.unwrap();
+
+        let (transport, _) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        channel.receive::<String>().await.unwrap();
+        channel.send(&7u32).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    });
+
+    let mut events = SubscriptionStream::<u32>::with_backoff(
+        move || Channel::tcp(addr, BincodeCodec),
+        "events".to_string(),
+        Backoff::new().initial(Duration::from_millis(10)),
+    );
+
+    assert!(matches!(events.next().await.unwrap(), Err(Error::Codec(_))));
+    assert_eq!(
+        events.next().await.unwrap().unwrap(),
+        StreamItem::Reconnected
+    );
+    assert_eq!(events.next().await.unwrap().unwrap(), StreamItem::Event(7));
+}
+
+#[tokio::test]
+async fn channel_request_reuses_connection() {
+    let (listener, addr) = get_listener().await;
+
+    // Answers two requests, then goes quiet
+    tokio::spawn(async move {
+        let (transport, _addr) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        for _ in 0..2 {
+            let mut msg: TestMessage = channel.receive().await.unwrap();
+            msg.id += 1;
+            channel.send(&msg).await.unwrap();
+        }
+        let _: TestMessage = channel.receive().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    });
+
+    let mut channel = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    for id in [1, 10] {
+        let request = TestMessage {
+            id,
+            data: "rpc".to_string(),
+        };
+        let response: TestMessage = channel.request(&request).await.unwrap();
+        assert_eq!(response.id, id + 1);
+    }
+
+    let request = TestMessage {
+        id: 0,
+        data: "unanswered".to_string(),
+    };
+    match channel
+        .request_timeout::<_, TestMessage>(&request, Duration::from_millis(100))
+        .await
+    {
+        Err(Error::Custom(msg)) => assert_eq!(msg, "Request timeout exceeded"),
+        other => panic!("Expected request timeout, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn channel_into_parts_and_back() {
+    let (listener, addr) = get_listener().await;
+
+    // Spawn server
+    tokio::spawn(async move {
+        let (transport, _addr) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+        for _ in 0..2 {
+            let msg: TestMessage = channel.receive().await.unwrap();
+            channel.send(&msg).await.unwrap(); // Echo back
+        }
+    });
+
+    let msg = TestMessage {
+        id: 3,
+        data: "parts".to_string(),
+    };
+
+    let mut channel = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    channel.send(&msg).await.unwrap();
+    let _: TestMessage = channel.receive().await.unwrap();
+
+    // Rebuild the channel around the same transport
+    let (transport, codec) = channel.into_parts();
+    let mut channel = Channel::from_parts(transport, codec);
+
+    channel.send(&msg).await.unwrap();
+    let response: TestMessage = channel.receive().await.unwrap();
+    assert_eq!(response, msg);
+}
+
+#[tokio::test]
+async fn channel_send_encoded_skips_codec() {
+    let (listener, addr) = get_listener().await;
+
+    let expected_msg = TestMessage {
+        id: 7,
+        data: "pre-encoded".to_string(),
+    };
+    let encoded = BincodeCodec.encode(&expected_msg).unwrap();
+
+    // Spawn server
+    tokio::spawn(async move {
+        let (transport, _addr) = listener.accept().await.unwrap();
+        let mut channel = Channel::from_transport(transport, BincodeCodec);
+
+        let msg: TestMessage = channel.receive().await.unwrap();
+        channel.send(&msg).await.unwrap(); // Echo back
+    });
+
+    // Client
+    let mut channel = Channel::tcp(addr, BincodeCodec).await.unwrap();
+
+    channel.send_encoded(&encoded).await.unwrap();
+    let response: TestMessage = channel.receive().await.unwrap();
+
+    assert_eq!(response, expected_msg);
+}
+
+#[tokio::test]
+async fn empty_frames_roundtrip() {
+    let (listener, addr) = get_listener().await;
+
+    // Spawn server that echoes two frames
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        for _ in 0..2 {
+            let msg = transport.receive().await.unwrap();
+            transport.send(&msg).await.unwrap();
+        }
+    });
+
+    let mut channel = Channel::tcp(addr, RawCodec).await.unwrap();
+
+    // Zero-length frame on the wire
+    channel.send_encoded(&[]).await.unwrap();
+    let response = channel.receive_encoded().await.unwrap();
+    assert!(response.is_empty());
+
+    // Empty Vec through the codec
+    channel.send(&Vec::<u8>::new()).await.unwrap();
+    let response: Vec<u8> = channel.receive().await.unwrap();
+    assert!(response.is_empty());
+}
+
+#[tokio::test]
+async fn receive_cancellable_returns_cancelled() {
+    let (listener, addr) = get_listener().await;
+
+    // Spawn server that never responds
+    tokio::spawn(async move {
+        let (_transport, _addr) = listener.accept().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    });
+
+    let mut channel = Channel::tcp(addr, BincodeCodec).await.unwrap();
+    let token = CancellationToken::new();
+
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        canceller.cancel();
+    });
+
+    let result: Result<TestMessage, Error> = channel.receive_cancellable(&token).await;
+    match result {
+        Err(Error::Cancelled) => {}
+        other => panic!("Expected Cancelled, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn builder_applies_send_timeout() {
+    let (listener, addr) = get_listener().await;
+
+    // Spawn server that never reads
+    tokio::spawn(async move {
+        let (_transport, _addr) = listener.accept().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    });
+
+    // Client with very short send timeout
+    let mut client = TcpTransport::builder()
+        .address(addr)
+        .send_timeout(Duration::from_millis(10))
+        .connect()
+        .await
+        .unwrap();
+
+    // Try to send a large message that won't fit in TCP buffer
+    // This should eventually timeout
+    let large_msg = vec![0u8; 10 * 1024 * 1024]; // 10MB
+
+    // Keep sending until timeout (TCP buffer will fill up)
+    let mut _timeout_hit = false;
+    for _ in 0..100 {
+        match client.send(&large_msg).await {
+            Err(Error::Custom(msg)) if msg.contains("timeout") => {
+                _timeout_hit = true;
+                break;
+            }
+            Err(e) => panic!("Unexpected error: {:?}", e),
+            Ok(_) => continue,
+        }
+    }
+
+    // Note: This test is probabilistic - TCP buffers might be large enough
+    // that we don't hit timeout. We just verify timeout logic exists.
+    // A real timeout would be better tested with a custom transport.
+}
+
+#[tokio::test]
+async fn inactivity_timeout_resets_on_progress() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Trickle a frame in slower than its total deadline would allow, then stall
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        stream.write_u32(5).await.unwrap();
+        for byte in b"hello" {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            stream.write_u8(*byte).await.unwrap();
+        }
+        stream.write_u32(5).await.unwrap();
+        stream.write_u8(b'x').await.unwrap();
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    });
+
+    let mut client = TcpTransport::builder()
+        .address(addr)
+        .receive_inactivity_timeout(Duration::from_millis(150))
+        .connect()
+        .await
+        .unwrap();
+
+    assert_eq!(client.receive().await.unwrap(), b"hello");
+    match client.receive().await {
+        Err(Error::Custom(msg)) => assert!(msg.contains("inactivity timeout")),
+        other => panic!("Expected inactivity timeout, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn connection_closed_error() {
+    let (listener, addr) = get_listener().await;
+
+    // Spawn server that immediately closes
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        transport.close().await.unwrap();
+    });
+
+    // Client tries to receive from closed connection
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+
+    // Give server time to close
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let result = client.receive().await;
+    assert!(result.is_err());
+    match result.unwrap_err() {
+        Error::ConnectionClosed => {}
+        e => panic!("Expected ConnectionClosed, got {:?}", e),
+    }
+}
+
+#[tokio::test]
+async fn close_delivers_all_buffered_frames() {
+    let (listener, addr) = get_listener().await;
+
+    // Server queues a burst of frames and closes immediately
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        for i in 0..100u32 {
+            transport.send(&i.to_be_bytes()).await.unwrap();
+        }
+        transport.close().await.unwrap();
+    });
+
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+
+    // Give server time to send and close before we start reading
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    for i in 0..100u32 {
+        assert_eq!(client.receive().await.unwrap(), i.to_be_bytes());
+    }
+    match client.receive().await {
+        Err(Error::ConnectionClosed) => {}
+        other => panic!("Expected ConnectionClosed, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn is_closed_detects_peer_shutdown() {
+    let (listener, addr) = get_listener().await;
+
+    let (close_tx, close_rx) = tokio::sync::oneshot::channel::<()>();
+
+    // Spawn server that closes once signalled
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        close_rx.await.unwrap();
+        transport.close().await.unwrap();
+    });
+
+    let mut client = TcpTransport::connect(addr).await.unwrap();
+    assert!(!client.is_closed());
+
+    close_tx.send(()).unwrap();
+
+    // Give server time to close
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(client.is_closed());
+
+    client.close().await.unwrap();
+    assert!(client.is_closed());
+}
+
+#[tokio::test]
+async fn rate_limited_delays_sends_beyond_burst() {
+    let (listener, addr) = get_listener().await;
+
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        while transport.receive().await.is_ok() {}
+    });
+
+    // 20 msg/s with a burst of 2: the first two go immediately, the next two wait ~50ms each
+    let client = TcpTransport::connect(addr).await.unwrap();
+    let mut client = RateLimited::new(client, 20.0, 2);
+
+    let start = std::time::Instant::now();
+    for _ in 0..4 {
+        client.send(b"tick").await.unwrap();
+    }
+
+    assert!(start.elapsed() >= Duration::from_millis(90));
+}
+
+#[tokio::test]
+async fn rate_limited_fails_when_wait_exceeds_send_timeout() {
+    let (listener, addr) = get_listener().await;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-struct TestMessage {
-    id: u32,
-    data: String,
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        while transport.receive().await.is_ok() {}
+    });
+
+    // One message per second, but only willing to wait 10ms
+    let client = TcpTransport::connect(addr).await.unwrap();
+    let mut client = RateLimited::new(client, 1.0, 1).with_send_timeout(Duration::from_millis(10));
+
+    client.send(b"first").await.unwrap();
+    match client.send(b"second").await {
+        Err(Error::Custom(msg)) => assert!(msg.contains("timeout")),
+        other => panic!("Expected timeout error, got {:?}", other),
+    }
 }
 
-/// Helper to get a free port
-async fn get_listener() -> (TcpTransportListener, std::net::SocketAddr) {
-    let listener = TcpTransportListener::bind("127.0.0.1:0".parse().unwrap())
-        .await
-        .unwrap();
-    let addr = listener.local_addr().unwrap();
-    (listener, addr)
+#[tokio::test]
+async fn send_deadline_covers_rate_limiter_wait() {
+    let (listener, addr) = get_listener().await;
+
+    tokio::spawn(async move {
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        while transport.receive().await.is_ok() {}
+    });
+
+    // The transport has no send timeout, yet waiting for a token still counts
+    let client = TcpTransport::connect(addr).await.unwrap();
+    let mut channel = Channel::from_transport(RateLimited::new(client, 1.0, 1), BincodeCodec)
+        .with_send_deadline(Duration::from_millis(50));
+
+    channel.send(&1u32).await.unwrap();
+    match channel.send(&2u32).await {
+        Err(Error::Custom(msg)) => assert_eq!(msg, "Send deadline exceeded"),
+        other => panic!("Expected deadline error, got {:?}", other),
+    }
 }
 
 #[tokio::test]
-async fn tcp_send_receive_single_message() {
+async fn send_deadline_covers_cancellable_send_and_closes_channel() {
     let (listener, addr) = get_listener().await;
 
-    // Spawn server
     tokio::spawn(async move {
         let (mut transport, _addr) = listener.accept().await.unwrap();
-        let received = transport.receive().await.unwrap();
-        transport.send(&received).await.unwrap(); // Echo back
+        while transport.receive().await.is_ok() {}
     });
 
-    // Client
-    let mut client = TcpTransport::connect(addr).await.unwrap();
-    let msg = b"hello world";
-    client.send(msg).await.unwrap();
-    let response = client.receive().await.unwrap();
+    let client = TcpTransport::connect(addr).await.unwrap();
+    let mut channel = Channel::from_transport(RateLimited::new(client, 1.0, 1), BincodeCodec)
+        .with_send_deadline(Duration::from_millis(50));
+    let token = CancellationToken::new();
 
-    assert_eq!(response, msg);
+    channel.send_cancellable(&1u32, &token).await.unwrap();
+    assert!(!channel.is_closed());
+    match channel.send_cancellable(&2u32, &token).await {
+        Err(Error::Custom(msg)) => assert_eq!(msg, "Send deadline exceeded"),
+        other => panic!("Expected deadline error, got {:?}", other),
+    }
+    assert!(channel.is_closed());
+    assert_eq!(channel.state(), ConnectionState::Closed);
 }
 
 #[tokio::test]
-async fn tcp_multiple_messages_preserve_boundaries() {
+async fn tcp_from_std_and_into_std() {
     let (listener, addr) = get_listener().await;
 
     // Spawn server
     tokio::spawn(async move {
         let (mut transport, _addr) = listener.accept().await.unwrap();
-        // Receive 3 messages and echo each back
-        for _ in 0..3 {
-            let msg = transport.receive().await.unwrap();
-            transport.send(&msg).await.unwrap();
-        }
+        let received = transport.receive().await.unwrap();
+        transport.send(&received).await.unwrap(); // Echo back
     });
 
-    // Client sends 3 distinct messages
-    let mut client = TcpTransport::connect(addr).await.unwrap();
-    let messages = vec![b"first".to_vec(), b"second".to_vec(), b"third".to_vec()];
+    // Configure a std stream before handing it over
+    let std_stream = std::net::TcpStream::connect(addr).unwrap();
+    std_stream.set_nodelay(true).unwrap();
 
-    for msg in &messages {
-        client.send(msg).await.unwrap();
-        let response = client.receive().await.unwrap();
-        assert_eq!(&response, msg);
-    }
+    let mut client = TcpTransport::from_std(std_stream).unwrap();
+    client.send(b"from std").await.unwrap();
+    assert_eq!(client.receive().await.unwrap(), b"from std");
+
+    let std_stream = client.into_std().await.unwrap();
+    assert!(std_stream.nodelay().unwrap());
+    assert_eq!(std_stream.peer_addr().unwrap(), addr);
 }
 
 #[tokio::test]
-async fn tcp_receive_timeout_fires() {
+async fn tcp_into_inner_hands_over_raw_stream() {
     let (listener, addr) = get_listener().await;
 
-    // Spawn server that never responds
+    // Server reads one frame, then switches to raw bytes as well
     tokio::spawn(async move {
-        let (_transport, _addr) = listener.accept().await.unwrap();
-        // Just hold connection open, never send
-        tokio::time::sleep(Duration::from_secs(10)).await;
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        let hello = transport.receive().await.unwrap();
+        let mut stream = transport.into_inner().await.unwrap();
+        stream.write_all(&hello).await.unwrap();
+        stream.write_all(b" raw").await.unwrap();
     });
 
-    // Client with short receive timeout
+    // Batched frames go out before the stream is handed over
     let mut client = TcpTransport::builder()
         .address(addr)
-        .receive_timeout(Duration::from_millis(100))
+        .write_batch(1024)
         .connect()
         .await
         .unwrap();
+    client.send(b"framed").await.unwrap();
 
-    client.send(b"hello").await.unwrap();
-
-    // Should timeout
-    let result = client.receive().await;
-    assert!(result.is_err());
-    match result.unwrap_err() {
-        Error::Custom(msg) => assert!(msg.contains("timeout")),
-        _ => panic!("Expected timeout error"),
-    }
+    let mut stream = client.into_inner().await.unwrap();
+    let mut buf = [0u8; 10];
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"framed raw");
 }
 
 #[tokio::test]
-async fn tcp_rejects_oversized_frame() {
-    // Test that our framing validates message size limits
-    // We test this by sending a raw malformed frame header
+async fn into_inner_refuses_unread_buffered_bytes() {
+    let (a, b) = tokio::io::duplex(4096);
+    let mut sender = StreamTransport::new(a);
+    let mut receiver = StreamTransport::new(b);
+
+    // readable() buffers the frame's first bytes, which unwrapping would lose
+    sender.send(b"pending").await.unwrap();
+    receiver.readable().await.unwrap();
+    let err = receiver.into_inner().await.unwrap_err();
+    assert!(err.to_string().contains("unread bytes"));
+}
 
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
-        .await
-        .unwrap();
-    let addr = listener.local_addr().unwrap();
+#[tokio::test]
+async fn tcp_connects_through_socks5_proxy() {
+    let proxy = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy_addr = proxy.local_addr().unwrap();
+    let target: std::net::SocketAddr = "10.1.2.3:4567".parse().unwrap();
 
-    // Spawn server that sends a malformed frame with huge size claim
+    // Mock proxy: performs the handshake, then acts as the target itself
     tokio::spawn(async move {
-        let (mut stream, _) = listener.accept().await.unwrap();
-
-        // Write frame header claiming 200MB (over our 100MB limit)
-        stream.write_u32(200 * 1024 * 1024).await.unwrap();
-        stream.flush().await.unwrap();
-
-        // Keep connection open
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        let (mut stream, _) = proxy.accept().await.unwrap();
+
+        let mut greeting = [0u8; 3];
+        stream.read_exact(&mut greeting).await.unwrap();
+        assert_eq!(greeting, [0x05, 0x01, 0x02]);
+        stream.write_all(&[0x05, 0x02]).await.unwrap();
+
+        let mut auth = [0u8; 11];
+        stream.read_exact(&mut auth).await.unwrap();
+        assert_eq!(&auth, b"\x01\x04user\x04pass");
+        stream.write_all(&[0x01, 0x00]).await.unwrap();
+
+        let mut request = [0u8; 10];
+        stream.read_exact(&mut request).await.unwrap();
+        assert_eq!(&request[..4], &[0x05, 0x01, 0x00, 0x01]);
+        assert_eq!(&request[4..8], &[10, 1, 2, 3]);
+        assert_eq!(u16::from_be_bytes([request[8], request[9]]), 4567);
+        stream
+            .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+            .await
+            .unwrap();
+
+        let mut transport = TcpTransport::from_stream(stream);
+        let received = transport.receive().await.unwrap();
+        transport.send(&received).await.unwrap(); // Echo back
     });
 
-    // Give server time to start
-    tokio::time::sleep(Duration::from_millis(50)).await;
-
-    // Client should reject the oversized frame
-    let mut client = TcpTransport::connect(addr).await.unwrap();
+    let mut client = TcpTransport::builder()
+        .address(target)
+        .socks5_proxy(proxy_addr, Some(Socks5Auth::new("user", "pass")))
+        .connect_timeout(Duration::from_secs(1))
+        .connect()
+        .await
+        .unwrap();
 
-    let result = client.receive().await;
-    assert!(result.is_err());
-    match result.unwrap_err() {
-        Error::InvalidFrame(msg) => assert!(msg.contains("too large")),
-        _ => panic!("Expected InvalidFrame error"),
-    }
+    client.send(b"via proxy").await.unwrap();
+    assert_eq!(client.receive().await.unwrap(), b"via proxy");
 }
 
+#[cfg(target_os = "linux")]
 #[tokio::test]
-async fn channel_with_codec_roundtrip() {
+async fn tcp_reports_kernel_buffer_levels() {
     let (listener, addr) = get_listener().await;
 
-    let expected_msg = TestMessage {
-        id: 42,
-        data: "test data".to_string(),
-    };
-    let expected_clone = expected_msg.clone();
-
-    // Spawn server
+    // Server sends one frame the client leaves unread
     tokio::spawn(async move {
-        let (transport, _addr) = listener.accept().await.unwrap();
-        let mut channel = Channel::from_transport(transport, BincodeCodec);
-
-        let msg: TestMessage = channel.receive().await.unwrap();
-        channel.send(&msg).await.unwrap(); // Echo back
+        let (mut transport, _addr) = listener.accept().await.unwrap();
+        transport.send(&[7u8; 100]).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
     });
 
-    // Client
-    let transport = TcpTransport::connect(addr).await.unwrap();
-    let mut channel = Channel::from_transport(transport, BincodeCodec);
-
-    channel.send(&expected_msg).await.unwrap();
-    let response: TestMessage = channel.receive().await.unwrap();
+    let client = TcpTransport::connect(addr).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
 
-    assert_eq!(response, expected_clone);
+    // 4-byte length prefix plus payload
+    assert_eq!(client.recv_buffer_pending().unwrap(), 104);
+    assert!(client.send_buffer_available().unwrap() > 0);
 }
 
 #[tokio::test]
-async fn builder_applies_send_timeout() {
+async fn builder_applies_linger() {
     let (listener, addr) = get_listener().await;
 
-    // Spawn server that never reads
     tokio::spawn(async move {
-        let (_transport, _addr) = listener.accept().await.unwrap();
-        tokio::time::sleep(Duration::from_secs(10)).await;
+        let _ = listener.accept().await.unwrap();
     });
 
-    // Client with very short send timeout
-    let mut client = TcpTransport::builder()
+    let client = TcpTransport::builder()
         .address(addr)
-        .send_timeout(Duration::from_millis(10))
+        .linger(Some(Duration::from_secs(5)))
         .connect()
         .await
         .unwrap();
 
-    // Try to send a large message that won't fit in TCP buffer
-    // This should eventually timeout
-    let large_msg = vec![0u8; 10 * 1024 * 1024]; // 10MB
+    assert_eq!(client.linger().unwrap(), Some(Duration::from_secs(5)));
+}
 
-    // Keep sending until timeout (TCP buffer will fill up)
-    let mut _timeout_hit = false;
-    for _ in 0..100 {
-        match client.send(&large_msg).await {
-            Err(Error::Custom(msg)) if msg.contains("timeout") => {
-                _timeout_hit = true;
-                break;
-            }
-            Err(e) => panic!("Unexpected error: {:?}", e),
-            Ok(_) => continue,
-        }
-    }
+#[tokio::test]
+async fn tcp_reports_mss() {
+    let (listener, addr) = get_listener().await;
 
-    // Note: This test is probabilistic - TCP buffers might be large enough
-    // that we don't hit timeout. We just verify timeout logic exists.
-    // A real timeout would be better tested with a custom transport.
+    let server = tokio::spawn(async move { listener.accept().await.unwrap() });
+    let client = TcpTransport::connect(addr).await.unwrap();
+    let (server, _) = server.await.unwrap();
+
+    assert!(client.mss().unwrap() > 0);
+    assert!(server.mss().unwrap() > 0);
 }
 
 #[tokio::test]
-async fn connection_closed_error() {
+async fn tcp_reports_rtt() {
     let (listener, addr) = get_listener().await;
 
-    // Spawn server that immediately closes
     tokio::spawn(async move {
-        let (mut transport, _addr) = listener.accept().await.unwrap();
-        transport.close().await.unwrap();
+        let (mut transport, _) = listener.accept().await.unwrap();
+        let msg = transport.receive().await.unwrap();
+        transport.send(&msg).await.unwrap();
     });
 
-    // Client tries to receive from closed connection
     let mut client = TcpTransport::connect(addr).await.unwrap();
+    client.send(b"ping").await.unwrap();
+    client.receive().await.unwrap();
 
-    // Give server time to close
-    tokio::time::sleep(Duration::from_millis(50)).await;
-
-    let result = client.receive().await;
-    assert!(result.is_err());
-    match result.unwrap_err() {
-        Error::ConnectionClosed => {}
-        e => panic!("Expected ConnectionClosed, got {:?}", e),
-    }
+    // Loopback round trips are well under a second
+    let rtt = client.rtt().unwrap();
+    assert!(rtt > Duration::ZERO);
+    assert!(rtt < Duration::from_secs(1));
 }
 
 #[tokio::test]
@@ -339,6 +2875,47 @@ async fn unix_listener_cleans_up_socket() {
     assert!(!std::path::Path::new(socket_path).exists());
 }
 
+#[tokio::test]
+async fn unix_listener_drop_keeps_rebound_socket() {
+    let socket_path = "/tmp/constellation_test_unix_drop_rebind.sock";
+    let _ = std::fs::remove_file(socket_path);
+
+    let first = UnixTransportListener::bind(socket_path).await.unwrap();
+    drop(first);
+    let second = UnixTransportListener::bind(socket_path).await.unwrap();
+
+    // Give the deferred removal of the first socket file a chance to run
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let server = tokio::spawn(async move { second.accept().await.map(|_| second) });
+    UnixTransport::connect(socket_path).await.unwrap();
+    let mut second = server.await.unwrap().unwrap();
+
+    second.close().await.unwrap();
+    assert!(!std::path::Path::new(socket_path).exists());
+}
+
+#[tokio::test]
+async fn close_on_drop_closes_transport_on_early_return() {
+    let (listener, addr) = get_listener().await;
+
+    let server = tokio::spawn(async move {
+        let (mut transport, _) = listener.accept().await.unwrap();
+        let first = transport.receive().await.unwrap();
+        (first, transport.receive().await)
+    });
+
+    async fn send_then_fail(addr: std::net::SocketAddr) -> Result<(), Error> {
+        let mut transport = CloseOnDrop::new(TcpTransport::connect(addr).await?);
+        transport.send(b"partial").await?;
+        Err(Error::Custom("handler failed".to_string()))
+    }
+    assert!(send_then_fail(addr).await.is_err());
+
+    let (first, rest) = server.await.unwrap();
+    assert_eq!(first, b"partial");
+    assert!(matches!(rest, Err(Error::ConnectionClosed)));
+}
+
 #[tokio::test]
 async fn unix_timeout_works() {
     let socket_path = "/tmp/constellation_test_unix_timeout.sock";
@@ -375,3 +2952,167 @@ async fn unix_timeout_works() {
 
     let _ = std::fs::remove_file(socket_path);
 }
+
+#[tokio::test]
+async fn unix_authorize_checks_peer_uid() {
+    let socket_path = "/tmp/constellation_test_unix_authorize.sock";
+    let _ = std::fs::remove_file(socket_path);
+
+    let listener = UnixTransportListener::bind(socket_path).await.unwrap();
+    let _client = UnixTransport::connect(socket_path).await.unwrap();
+    let server = listener.accept().await.unwrap();
+
+    // Both ends run in this process, so the peer is the current uid
+    let uid = server.peer_cred().unwrap().uid();
+    server.authorize(&[0, uid]).unwrap();
+    match server.authorize(&[uid.wrapping_add(1)]) {
+        Err(Error::Unauthorized(msg)) => assert!(msg.contains(&uid.to_string())),
+        other => panic!("Expected Unauthorized, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn unix_rejects_self_connect_when_enabled() {
+    let socket_path = "/tmp/constellation_test_unix_self_connect.sock";
+    let _ = std::fs::remove_file(socket_path);
+
+    let _listener = UnixTransportListener::bind(socket_path).await.unwrap();
+    match UnixTransport::builder()
+        .path(socket_path)
+        .reject_self_connect(true)
+        .connect()
+        .await
+    {
+        Err(Error::Custom(msg)) => assert!(msg.contains("served by this process")),
+        other => panic!("Expected self-connect error, got {:?}", other),
+    }
+
+    // Off by default
+    UnixTransport::connect(socket_path).await.unwrap();
+}
+
+#[tokio::test]
+async fn unix_passes_file_descriptors() {
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+
+    let socket_path = "/tmp/constellation_test_unix_fds.sock";
+    let _ = std::fs::remove_file(socket_path);
+
+    let listener = UnixTransportListener::bind(socket_path).await.unwrap();
+    let mut client = UnixTransport::connect(socket_path).await.unwrap();
+    let mut server = listener.accept().await.unwrap();
+
+    // Hand the write end of a pipe to the other side
+    let (mut reader, writer) = std::io::pipe().unwrap();
+    client
+        .send_with_fds(b"here is a pipe", &[writer.as_raw_fd()])
+        .await
+        .unwrap();
+    drop(writer);
+
+    let (payload, fds) = server.receive_with_fds().await.unwrap();
+    assert_eq!(payload, b"here is a pipe");
+    assert_eq!(fds.len(), 1);
+
+    let mut received = std::fs::File::from(fds.into_iter().next().unwrap());
+    received.write_all(b"through the pipe").unwrap();
+    drop(received);
+
+    let mut piped = String::new();
+    reader.read_to_string(&mut piped).unwrap();
+    assert_eq!(piped, "through the pipe");
+
+    // Frames without descriptors still arrive
+    client.send(b"plain").await.unwrap();
+    let (payload, fds) = server.receive_with_fds().await.unwrap();
+    assert_eq!(payload, b"plain");
+    assert!(fds.is_empty());
+}
+
+#[tokio::test]
+async fn unix_fd_receive_answers_pings_and_applies_budget() {
+    use std::os::fd::AsRawFd;
+
+    let socket_path = "/tmp/constellation_test_unix_fds_ping.sock";
+    let _ = std::fs::remove_file(socket_path);
+
+    let listener = UnixTransportListener::builder()
+        .frame_types(true)
+        .memory_budget(Arc::new(MemoryBudget::new(8)))
+        .bind(socket_path)
+        .await
+        .unwrap();
+    let mut client = UnixTransport::builder()
+        .path(socket_path)
+        .frame_types(true)
+        .connect()
+        .await
+        .unwrap();
+    let mut server = listener.accept().await.unwrap();
+
+    // The ping ahead of the descriptors is answered on the way
+    let (_reader, writer) = std::io::pipe().unwrap();
+    client.send_control(FrameType::Ping, b"hi").await.unwrap();
+    client
+        .send_with_fds(b"pipe", &[writer.as_raw_fd()])
+        .await
+        .unwrap();
+    let (payload, fds) = server.receive_with_fds().await.unwrap();
+    assert_eq!(payload, b"pipe");
+    assert_eq!(fds.len(), 1);
+    assert_eq!(
+        client.receive_frame().await.unwrap(),
+        (FrameType::Pong, b"hi".to_vec())
+    );
+
+    // A payload over the budget fails the receive and closes the connection
+    client
+        .send_with_fds(b"over budget", &[writer.as_raw_fd()])
+        .await
+        .unwrap();
+    assert!(matches!(
+        server.receive_with_fds().await,
+        Err(Error::InvalidFrame(msg)) if msg.contains("memory budget")
+    ));
+    assert!(server.is_closed());
+}
+
+#[tokio::test]
+async fn unix_listener_applies_frame_options() {
+    use std::os::fd::AsRawFd;
+
+    let socket_path = "/tmp/constellation_test_unix_listener_frame.sock";
+    let _ = std::fs::remove_file(socket_path);
+
+    let listener = UnixTransportListener::builder()
+        .frame_types(true)
+        .sequence_numbers(true)
+        .bind(socket_path)
+        .await
+        .unwrap();
+    let mut client = UnixTransport::builder()
+        .path(socket_path)
+        .frame_types(true)
+        .sequence_numbers(true)
+        .connect()
+        .await
+        .unwrap();
+    let mut server = listener.accept().await.unwrap();
+    assert_eq!(server.frame_overhead(), client.frame_overhead());
+
+    // Frames with descriptors share the numbering of plain ones
+    let (_reader, writer) = std::io::pipe().unwrap();
+    client.send(b"one").await.unwrap();
+    client
+        .send_with_fds(b"two", &[writer.as_raw_fd()])
+        .await
+        .unwrap();
+    client.send(b"three").await.unwrap();
+
+    assert_eq!(server.receive().await.unwrap(), b"one");
+    let (payload, fds) = server.receive_with_fds().await.unwrap();
+    assert_eq!(payload, b"two");
+    assert_eq!(fds.len(), 1);
+    assert_eq!(server.receive().await.unwrap(), b"three");
+}